@@ -0,0 +1,88 @@
+//! Rolling NtHash hashing against hashing String k-mers, for the counter, MinHash sketches and
+//! Bloom filters. Run with `cargo bench`; each line is the best of several runs.
+
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use kmer_analysis::kmer::{canonical_kmer, count_records_parallel, generate_kmers_filtered, seeded_hash};
+use kmer_analysis::nthash::NtHash;
+use kmer_analysis::simulate::generate_random_dna_sequence_seeded;
+use kmer_analysis::sketch::{BloomFilter, MinHashSketch};
+
+const GENOME_LENGTH: usize = 200_000;
+const COVERAGE: usize = 10;
+const K: usize = 21;
+const RUNS: usize = 5;
+
+fn main() {
+    // Every k-mer recurs COVERAGE times, as in a read set, rather than nearly all being distinct
+    let sequences = vec![generate_random_dna_sequence_seeded(GENOME_LENGTH, 1); COVERAGE];
+    let kmers = || sequences.iter().flat_map(|sequence| generate_kmers_filtered(sequence, K));
+    let hashes = || sequences.iter().flat_map(|sequence| NtHash::new(sequence, K, true)).map(|(_, hash)| hash);
+    // One thread, so the counter is compared with the String one and not with more cores
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(1).build().expect("build a one-thread pool");
+    println!("{} bp at {}x, k = {}, best of {} runs", GENOME_LENGTH, COVERAGE, K, RUNS);
+
+    compare(
+        "canonical hashes",
+        || kmers().map(|kmer| seeded_hash(&canonical_kmer(&kmer), 0)).fold(0, u64::wrapping_add),
+        || hashes().fold(0, u64::wrapping_add),
+    );
+    compare(
+        "counting",
+        || {
+            let mut counts: HashMap<String, usize> = HashMap::new();
+            for kmer in kmers() {
+                *counts.entry(canonical_kmer(&kmer)).or_insert(0) += 1;
+            }
+            counts.len()
+        },
+        || pool.install(|| count_records_parallel(&sequences, K, true).len()),
+    );
+    compare(
+        "MinHash sketch",
+        || {
+            let mut sketch: Vec<u64> = kmers().map(|kmer| seeded_hash(&canonical_kmer(&kmer), 0)).collect();
+            sketch.sort_unstable();
+            sketch.dedup();
+            sketch.truncate(1000);
+            sketch.len()
+        },
+        || MinHashSketch::from_records(&sequences, K, 1000).hashes.len(),
+    );
+    compare(
+        "Bloom filter",
+        || {
+            let mut filter = BloomFilter::new(1 << 24, 4);
+            kmers().filter(|kmer| filter.insert(&canonical_kmer(kmer))).count()
+        },
+        || {
+            let mut filter = BloomFilter::new(1 << 24, 4);
+            hashes().filter(|&hash| filter.insert_hash(hash)).count()
+        },
+    );
+}
+
+// Time hashing Strings against rolling hashes and print both with the speedup
+fn compare<A, B>(name: &str, strings: impl Fn() -> A, rolling: impl Fn() -> B) {
+    let strings = best_of(strings);
+    let rolling = best_of(rolling);
+    println!(
+        "{:<18} strings {:>9.2?}  rolling {:>9.2?}  speedup {:.1}x",
+        name,
+        strings,
+        rolling,
+        strings.as_secs_f64() / rolling.as_secs_f64()
+    );
+}
+
+fn best_of<T>(run: impl Fn() -> T) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(run());
+            start.elapsed()
+        })
+        .min()
+        .unwrap_or_default()
+}
@@ -0,0 +1,214 @@
+//! Sequence alphabets: which symbols a sequence may hold, which ambiguity codes stand in for
+//! them, and whether they have complements. Validation, k-mer extraction, packing and random
+//! generation are parameterised by an Alphabet, so proteins go through the same machinery as DNA.
+
+use std::collections::HashSet;
+use rand::distributions::{Distribution, Uniform};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::error::KmerError;
+use crate::kmer::AmbiguousBasePolicy;
+
+// Marks a byte that isn't a symbol of the alphabet in Alphabet::codes
+const NO_CODE: u8 = u8::MAX;
+
+/// The symbols of a kind of sequence, in code order, with its ambiguity codes and complement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alphabet {
+    name: String,
+    symbols: Vec<u8>,
+    ambiguous: Vec<u8>,
+    wildcard: Option<u8>,
+    complement: Option<Vec<u8>>,
+    codes: Vec<u8>,
+}
+
+impl Alphabet {
+    /// A, C, G, T; the IUPAC codes N, R, Y, S, W, K, M, B, D, H and V are ambiguous and stand
+    /// as N. The complement pairs A with T and C with G.
+    pub fn dna() -> Self {
+        Alphabet::new("dna", b"ACGT", b"NRYSWKMBDHV", Some(b'N'), Some(b"TGCA"))
+    }
+
+    /// As dna, with U in place of T.
+    pub fn rna() -> Self {
+        Alphabet::new("rna", b"ACGU", b"NRYSWKMBDHV", Some(b'N'), Some(b"UGCA"))
+    }
+
+    /// The 20 standard amino acids; B, Z, J, U, O and X are ambiguous or nonstandard and stand
+    /// as X. Proteins have no complement.
+    pub fn protein() -> Self {
+        Alphabet::new("protein", b"ACDEFGHIKLMNPQRSTVWY", b"BZJUOX", Some(b'X'), None)
+    }
+
+    /// An alphabet of the given ASCII symbols, uppercased, in the order given, with no ambiguity
+    /// codes or complement. Whitespace is ignored; errors on an empty or repeated symbol list.
+    pub fn custom(symbols: &str) -> Result<Self, KmerError> {
+        let symbols: Vec<u8> = symbols
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| u8::try_from(c.to_ascii_uppercase()).ok().filter(u8::is_ascii).ok_or(c))
+            .collect::<Result<_, char>>()
+            .map_err(|c| KmerError::InvalidAlphabet(format!("symbol {:?} is not ASCII", c)))?;
+        if symbols.is_empty() {
+            return Err(KmerError::InvalidAlphabet("no symbols given".to_string()));
+        }
+        let mut seen = HashSet::new();
+        if let Some(&symbol) = symbols.iter().find(|&&symbol| !seen.insert(symbol)) {
+            return Err(KmerError::InvalidAlphabet(format!("symbol '{}' is given twice", symbol as char)));
+        }
+        Ok(Alphabet::new("custom", &symbols, b"", None, None))
+    }
+
+    fn new(name: &str, symbols: &[u8], ambiguous: &[u8], wildcard: Option<u8>, complement: Option<&[u8]>) -> Self {
+        let mut codes = vec![NO_CODE; 256];
+        for (code, &symbol) in symbols.iter().enumerate() {
+            codes[symbol as usize] = code as u8;
+        }
+        Alphabet {
+            name: name.to_string(),
+            symbols: symbols.to_vec(),
+            ambiguous: ambiguous.to_vec(),
+            wildcard,
+            complement: complement.map(<[u8]>::to_vec),
+            codes,
+        }
+    }
+
+    /// Name of the alphabet: dna, rna, protein or custom.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The symbols, uppercase, in code order.
+    pub fn symbols(&self) -> &[u8] {
+        &self.symbols
+    }
+
+    /// Number of symbols.
+    pub fn size(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// Code of an uppercase symbol (its index in symbols()), or None for anything else,
+    /// ambiguity codes included.
+    pub fn code(&self, symbol: u8) -> Option<u8> {
+        Some(self.codes[symbol as usize]).filter(|&code| code != NO_CODE)
+    }
+
+    /// Whether the alphabet has a complement, which reverse complements and canonical k-mers
+    /// need.
+    pub fn has_complement(&self) -> bool {
+        self.complement.is_some()
+    }
+
+    /// Bits a packed k-mer spends per symbol: enough to number every symbol, and at least 1.
+    pub fn bits_per_symbol(&self) -> u32 {
+        usize::BITS - (self.size().max(2) - 1).leading_zeros()
+    }
+
+    /// Longest k-mer encode packs into a u64: 32 for DNA, 12 for protein.
+    pub fn max_packed_k(&self) -> usize {
+        (u64::BITS / self.bits_per_symbol()) as usize
+    }
+
+    /// Pack a k-mer into a u64 at bits_per_symbol bits per symbol, first symbol in the highest
+    /// bits; for DNA the codes equal encode_kmer's. Returns None for k-mers longer than
+    /// max_packed_k or holding anything but uppercase symbols.
+    pub fn encode(&self, kmer: &str) -> Option<u64> {
+        if kmer.len() > self.max_packed_k() {
+            return None;
+        }
+        let bits = self.bits_per_symbol();
+        kmer.bytes().try_fold(0u64, |packed, symbol| self.code(symbol).map(|code| (packed << bits) | code as u64))
+    }
+
+    /// Unpack a k-mer of length k packed by encode.
+    pub fn decode(&self, packed: u64, k: usize) -> String {
+        let bits = self.bits_per_symbol();
+        let mask = (1u64 << bits) - 1;
+        (0..k)
+            .rev()
+            .map(|i| self.symbols.get(((packed >> (bits as usize * i)) & mask) as usize).map_or('?', |&symbol| symbol as char))
+            .collect()
+    }
+
+    /// validate_sequence for this alphabet: uppercase the sequence and strip whitespace, turn
+    /// ambiguity codes into the wildcard or reject them according to `policy`, and reject any
+    /// other character, naming it and its 1-based position.
+    pub fn validate(&self, seq: &str, policy: AmbiguousBasePolicy) -> Result<String, KmerError> {
+        let mut clean = String::with_capacity(seq.len());
+        for (i, c) in seq.chars().enumerate() {
+            if c.is_whitespace() {
+                continue;
+            }
+            let upper = c.to_ascii_uppercase();
+            let byte = u8::try_from(upper).unwrap_or(NO_CODE);
+            if self.code(byte).is_some() {
+                clean.push(upper);
+            } else if self.ambiguous.contains(&byte) {
+                match (policy, self.wildcard) {
+                    (AmbiguousBasePolicy::Skip, Some(wildcard)) => clean.push(wildcard as char),
+                    _ => return Err(KmerError::AmbiguousBase { base: upper, position: i + 1 }),
+                }
+            } else {
+                return Err(KmerError::InvalidCharacter { character: c, position: i + 1 });
+            }
+        }
+        Ok(clean)
+    }
+
+    /// The k-mers of a sequence made only of the alphabet's uppercase symbols, skipping every
+    /// window that holds anything else (the wildcard included), like generate_kmers_filtered.
+    pub fn kmers(&self, seq: &str, k: usize) -> Vec<String> {
+        let mut kmers = Vec::new();
+        if k == 0 || k > seq.len() {
+            return kmers;
+        }
+        // Index of the first symbol of the current run of symbols
+        let mut run_start = 0;
+        for (i, &symbol) in seq.as_bytes().iter().enumerate() {
+            if self.code(symbol).is_none() {
+                run_start = i + 1;
+            } else if i + 1 - run_start >= k {
+                kmers.push(seq[i + 1 - k..=i].to_string());
+            }
+        }
+        kmers
+    }
+
+    /// Reverse complement of a k-mer, leaving anything but the symbols as it is; None for an
+    /// alphabet without a complement.
+    pub fn reverse_complement(&self, kmer: &str) -> Option<String> {
+        let complement = self.complement.as_ref()?;
+        Some(
+            kmer.bytes()
+                .rev()
+                .map(|symbol| self.code(symbol).map_or(symbol, |code| complement[code as usize]) as char)
+                .collect(),
+        )
+    }
+
+    /// The lexicographically smaller of a k-mer and its reverse complement, or the k-mer itself
+    /// for an alphabet without a complement.
+    pub fn canonical(&self, kmer: &str) -> String {
+        match self.reverse_complement(kmer) {
+            Some(rc) if rc.as_str() < kmer => rc,
+            _ => kmer.to_string(),
+        }
+    }
+
+    /// A random sequence of `length` symbols, each equally likely: reproducible with a seed, as
+    /// generate_random_dna_sequence_seeded, and from the thread's generator without one.
+    pub fn random_sequence(&self, length: usize, seed: Option<u64>) -> String {
+        match seed {
+            Some(seed) => self.random_sequence_from(length, &mut StdRng::seed_from_u64(seed)),
+            None => self.random_sequence_from(length, &mut rand::thread_rng()),
+        }
+    }
+
+    fn random_sequence_from<R: Rng>(&self, length: usize, rng: &mut R) -> String {
+        let between = Uniform::from(0..self.size());
+        (0..length).map(|_| self.symbols[between.sample(rng)] as char).collect()
+    }
+}
@@ -0,0 +1,778 @@
+//! Composition, complexity, abundance and classification analyses built on k-mer counts.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::ops::RangeInclusive;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::kmer::{base_index, count_kmers, gc_content, generate_kmers, kmer_positions, reverse_complement};
+use crate::sketch::profile_distance;
+
+/// Minimum fraction of an allele's k-mers that must occur in the genome for an MLST call.
+pub const MLST_MIN_KMER_FRACTION: f64 = 0.9;
+
+/// k-mer based MLST typing: for each locus, call the allele whose k-mers are best covered by
+/// the genome's k-mers (either strand). Loci whose best allele covers fewer than
+/// MLST_MIN_KMER_FRACTION of its k-mers are reported as None.
+pub fn mlst_profile(
+    genome: &str,
+    alleles: &HashMap<String, Vec<(String, String)>>,
+    k: usize,
+) -> HashMap<String, Option<String>> {
+    let genome_kmers: HashSet<String> = kmer_positions(genome, k)
+        .into_keys()
+        .flat_map(|kmer| [reverse_complement(&kmer), kmer])
+        .collect();
+
+    alleles
+        .iter()
+        .map(|(locus, locus_alleles)| {
+            let mut best: Option<(&String, f64)> = None;
+            for (allele_name, allele_seq) in locus_alleles {
+                let allele_kmers = kmer_positions(allele_seq, k);
+                if allele_kmers.is_empty() {
+                    continue;
+                }
+                let present = allele_kmers.keys().filter(|kmer| genome_kmers.contains(*kmer)).count();
+                let fraction = present as f64 / allele_kmers.len() as f64;
+                if best.is_none_or(|(_, best_fraction)| fraction > best_fraction) {
+                    best = Some((allele_name, fraction));
+                }
+            }
+            let call = best
+                .filter(|&(_, fraction)| fraction >= MLST_MIN_KMER_FRACTION)
+                .map(|(allele_name, _)| allele_name.clone());
+            (locus.clone(), call)
+        })
+        .collect()
+}
+
+/// BUSCO-style completeness proxy: the fraction of marker genes whose k-mer sets are at least
+/// `min_fraction` present in the assembly (either strand). No markers gives 0.0.
+pub fn completeness_estimate(
+    assembly: &str,
+    marker_kmer_sets: &[(String, HashSet<String>)],
+    k: usize,
+    min_fraction: f64,
+) -> f64 {
+    if marker_kmer_sets.is_empty() {
+        return 0.0;
+    }
+    let assembly_kmers: HashSet<String> = kmer_positions(assembly, k)
+        .into_keys()
+        .flat_map(|kmer| [reverse_complement(&kmer), kmer])
+        .collect();
+
+    let complete = marker_kmer_sets
+        .iter()
+        .filter(|(_, markers)| {
+            !markers.is_empty() && {
+                let present = markers.iter().filter(|kmer| assembly_kmers.contains(*kmer)).count();
+                present as f64 / markers.len() as f64 >= min_fraction
+            }
+        })
+        .count();
+    complete as f64 / marker_kmer_sets.len() as f64
+}
+
+/// FastQ-Screen-style contamination report: for each (name, k-mer set) in the panel, the fraction
+/// of the sample's k-mer observations (counts of its length-k k-mers) that hit the contaminant on
+/// either strand. Sorted by fraction, highest first.
+pub fn screen_contaminants(sample: &HashMap<String, usize>, panel: &[(String, HashSet<String>)], k: usize) -> Vec<(String, f64)> {
+    let total: usize = sample.iter().filter(|(kmer, _)| kmer.len() == k).map(|(_, &count)| count).sum();
+    let mut report: Vec<(String, f64)> = panel
+        .iter()
+        .map(|(name, contaminant_kmers)| {
+            let hits: usize = sample
+                .iter()
+                .filter(|(kmer, _)| {
+                    kmer.len() == k
+                        && (contaminant_kmers.contains(*kmer) || contaminant_kmers.contains(&reverse_complement(kmer)))
+                })
+                .map(|(_, &count)| count)
+                .sum();
+            let fraction = if total == 0 { 0.0 } else { hits as f64 / total as f64 };
+            (name.clone(), fraction)
+        })
+        .collect();
+    report.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    report
+}
+
+/// Number of canonical tetranucleotides: 256 4-mers, with each of the 120 non-palindromic
+/// 4-mers merged with its reverse complement (16 are their own reverse complement).
+pub const CANONICAL_TETRANUCLEOTIDES: usize = 136;
+
+/// Compute tetranucleotide frequency (TNF) z-scores, the classic composition fingerprint for binning.
+/// Each 4-mer's observed count is compared with its expectation under a maximal-order Markov model,
+/// E(N1N2N3N4) = N(N1N2N3) * N(N2N3N4) / N(N2N3), following Teeling et al. (2004).
+/// As in MetaBAT and TETRA, both strands are counted so a 4-mer and its reverse complement share
+/// one entry; entries are the canonical 4-mers (the lexicographically smaller of each pair) in
+/// lexicographic order, AAAA, AAAC, ..., TTAA. Windows containing non-ACGT bases are skipped.
+pub fn tetranucleotide_zscores(seq: &str) -> [f64; CANONICAL_TETRANUCLEOTIDES] {
+    let mut counts4 = [0.0f64; 256];
+    let mut counts3 = [0.0f64; 64];
+    let mut counts2 = [0.0f64; 16];
+
+    let forward: Vec<Option<usize>> = seq.bytes().map(base_index).collect();
+    let reverse: Vec<Option<usize>> = forward.iter().rev().map(|base| base.map(|b| 3 - b)).collect();
+    for indices in [forward, reverse] {
+        for width in 2..=4 {
+            for window in indices.windows(width) {
+                if let Some(code) = window.iter().try_fold(0, |code, base| base.map(|b| code * 4 + b)) {
+                    match width {
+                        2 => counts2[code] += 1.0,
+                        3 => counts3[code] += 1.0,
+                        _ => counts4[code] += 1.0,
+                    }
+                }
+            }
+        }
+    }
+
+    let reverse_complement_code = |code: usize| (0..4).fold(0, |rc, i| rc * 4 + 3 - (code >> (2 * i)) % 4);
+    let mut zscores = [0.0; CANONICAL_TETRANUCLEOTIDES];
+    let canonical_codes = (0..256).filter(|&code| code <= reverse_complement_code(code));
+    for (zscore, code) in zscores.iter_mut().zip(canonical_codes) {
+        let prefix = counts3[code / 4];
+        let suffix = counts3[code % 64];
+        let middle = counts2[(code / 4) % 16];
+        if middle == 0.0 {
+            continue;
+        }
+        let expected = prefix * suffix / middle;
+        let variance = expected * (middle - prefix) * (middle - suffix) / (middle * middle);
+        if variance > 0.0 {
+            *zscore = (counts4[code] - expected) / variance.sqrt();
+        }
+    }
+    zscores
+}
+
+/// Linguistic complexity: for each k in 1..=max_k, the number of distinct k-mers divided by the
+/// most that could occur, min(4^k, len - k + 1), averaged over k. Values near 1 mean high complexity,
+/// values near 0 a repetitive sequence such as a homopolymer. Lengths of k longer than the sequence are skipped.
+pub fn linguistic_complexity(seq: &str, max_k: usize) -> f64 {
+    let ratios: Vec<f64> = (1..=max_k.min(seq.len()))
+        .map(|k| {
+            let distinct = generate_kmers(seq, k).into_iter().collect::<HashSet<_>>().len();
+            let possible = 4usize.checked_pow(k as u32).unwrap_or(usize::MAX).min(seq.len() - k + 1);
+            distinct as f64 / possible as f64
+        })
+        .collect();
+    if ratios.is_empty() {
+        return 0.0;
+    }
+    ratios.iter().sum::<f64>() / ratios.len() as f64
+}
+
+/// Fraction of k-mer positions in `seq` whose k-mer occurs exactly once in the sequence.
+pub fn unique_kmer_fraction(seq: &str, k: usize) -> f64 {
+    if k == 0 || k > seq.len() {
+        return 0.0;
+    }
+    let counts = count_kmers(generate_kmers(seq, k));
+    let positions: usize = counts.values().sum();
+    let unique = counts.values().filter(|&&count| count == 1).count();
+    unique as f64 / positions as f64
+}
+
+/// Unique-k-mer fraction for each k in the range; the curve reaches 1.0 once k exceeds
+/// the longest repeat, which shows the smallest k giving near-full uniqueness.
+pub fn uniqueness_vs_k(seq: &str, k_range: RangeInclusive<usize>) -> Vec<(usize, f64)> {
+    k_range.map(|k| (k, unique_kmer_fraction(seq, k))).collect()
+}
+
+/// Linguistic complexity (up to 3-mers) below which a k-length window counts as low-complexity.
+pub const MASK_COMPLEXITY_THRESHOLD: f64 = 0.5;
+
+/// Compute half-open [start, end) intervals of `seq` that should be masked: every k-length window
+/// that is low-complexity (homopolymers, short tandem repeats) or whose k-mer occurs more than
+/// once in the sequence (repeats). Overlapping and adjacent windows are merged.
+pub fn compute_mask(seq: &str, k: usize) -> Vec<(usize, usize)> {
+    if k == 0 || k > seq.len() {
+        return Vec::new();
+    }
+    let counts = count_kmers(generate_kmers(seq, k));
+
+    let mut intervals: Vec<(usize, usize)> = Vec::new();
+    for start in 0..=seq.len() - k {
+        let window = &seq[start..start + k];
+        let repetitive = counts[window] > 1;
+        if !repetitive && linguistic_complexity(window, 3) >= MASK_COMPLEXITY_THRESHOLD {
+            continue;
+        }
+        match intervals.last_mut() {
+            Some(last) if last.1 >= start => last.1 = start + k,
+            _ => intervals.push((start, start + k)),
+        }
+    }
+    intervals
+}
+
+/// How masked bases are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaskStyle {
+    /// Replace masked bases with 'N'.
+    Hard,
+    /// Lowercase masked bases, keeping their identity.
+    Soft,
+}
+
+/// Apply mask intervals (half-open byte offsets, as from compute_mask) to a sequence.
+/// Intervals are clipped to the sequence, and empty or inverted ones (start >= end) are ignored.
+pub fn apply_mask(seq: &str, intervals: &[(usize, usize)], style: MaskStyle) -> String {
+    let mut masked = vec![false; seq.len()];
+    for &(start, end) in intervals {
+        let end = end.min(seq.len());
+        if start >= end {
+            continue;
+        }
+        for flag in &mut masked[start..end] {
+            *flag = true;
+        }
+    }
+    seq.char_indices()
+        .map(|(offset, base)| match (masked[offset], style) {
+            (false, _) => base,
+            (true, MaskStyle::Hard) => 'N',
+            (true, MaskStyle::Soft) => base.to_ascii_lowercase(),
+        })
+        .collect()
+}
+
+/// Build a normalized k-mer abundance table across samples for downstream machine learning.
+/// The vocabulary keeps k-mers present in at least `min_prevalence` samples (sorted),
+/// and each sample's row is scaled to sum to 1 over that vocabulary.
+pub fn abundance_table(
+    samples: &[(String, HashMap<String, usize>)],
+    min_prevalence: usize,
+) -> (Vec<String>, Vec<String>, Vec<Vec<f64>>) {
+    let names: Vec<String> = samples.iter().map(|(name, _)| name.clone()).collect();
+
+    // Count in how many samples each k-mer occurs.
+    let mut prevalence: HashMap<&String, usize> = HashMap::new();
+    for (_, counts) in samples {
+        for (kmer, &count) in counts {
+            if count > 0 {
+                *prevalence.entry(kmer).or_insert(0) += 1;
+            }
+        }
+    }
+    let mut vocabulary: Vec<String> = prevalence
+        .into_iter()
+        .filter(|&(_, present_in)| present_in >= min_prevalence)
+        .map(|(kmer, _)| kmer.clone())
+        .collect();
+    vocabulary.sort();
+
+    let matrix = samples
+        .iter()
+        .map(|(_, counts)| {
+            let row: Vec<f64> = vocabulary.iter().map(|kmer| *counts.get(kmer).unwrap_or(&0) as f64).collect();
+            let total: f64 = row.iter().sum();
+            if total == 0.0 {
+                row
+            } else {
+                row.into_iter().map(|value| value / total).collect()
+            }
+        })
+        .collect();
+
+    (names, vocabulary, matrix)
+}
+
+/// Mean k-mer coverage of each contig, looking up each of its k-mers in the count table
+/// (k-mers missing from the table count as zero). Contigs near 2x or 0.5x the typical coverage
+/// suggest collapsed repeats or contamination. Contigs shorter than k get a mean of 0.0.
+pub fn contig_coverage_spectrum(contigs: &[String], kmer_counts: &HashMap<String, usize>, k: usize) -> Vec<(String, f64)> {
+    contigs
+        .iter()
+        .map(|contig| {
+            if k == 0 || contig.len() < k {
+                return (contig.clone(), 0.0);
+            }
+            let kmers = generate_kmers(contig, k);
+            let total: usize = kmers.iter().map(|kmer| *kmer_counts.get(kmer).unwrap_or(&0)).sum();
+            (contig.clone(), total as f64 / kmers.len() as f64)
+        })
+        .collect()
+}
+
+/// Metagenomic 2D binning: place each contig at (mean k-mer coverage, GC fraction), scale both
+/// axes to [0, 1], and cluster with seeded k-means (k-means++ initialization). Returns one bin
+/// label in 0..num_bins per contig.
+pub fn bin_by_coverage_gc(
+    contigs: &[String],
+    kmer_counts: &HashMap<String, usize>,
+    k: usize,
+    num_bins: usize,
+    seed: u64,
+) -> Vec<usize> {
+    if contigs.is_empty() || num_bins == 0 {
+        return vec![0; contigs.len()];
+    }
+    let mut points: Vec<[f64; 2]> = contig_coverage_spectrum(contigs, kmer_counts, k)
+        .into_iter()
+        .map(|(contig, coverage)| {
+            [coverage, gc_content(&contig)]
+        })
+        .collect();
+    for axis in 0..2 {
+        let min = points.iter().map(|p| p[axis]).fold(f64::INFINITY, f64::min);
+        let max = points.iter().map(|p| p[axis]).fold(f64::NEG_INFINITY, f64::max);
+        let range = if max > min { max - min } else { 1.0 };
+        for point in &mut points {
+            point[axis] = (point[axis] - min) / range;
+        }
+    }
+    let distance = |a: &[f64; 2], b: &[f64; 2]| (a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2);
+
+    // k-means++: each further centroid is drawn with probability proportional to its squared
+    // distance from the nearest centroid chosen so far.
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut centroids = vec![points[rng.gen_range(0..points.len())]];
+    while centroids.len() < num_bins.min(points.len()) {
+        let weights: Vec<f64> = points
+            .iter()
+            .map(|point| centroids.iter().map(|c| distance(point, c)).fold(f64::INFINITY, f64::min))
+            .collect();
+        let total: f64 = weights.iter().sum();
+        if total == 0.0 {
+            break;
+        }
+        let mut target = rng.gen::<f64>() * total;
+        let chosen = weights
+            .iter()
+            .position(|&weight| {
+                target -= weight;
+                target <= 0.0
+            })
+            .unwrap_or(points.len() - 1);
+        centroids.push(points[chosen]);
+    }
+
+    let mut labels = vec![0; points.len()];
+    for _ in 0..100 {
+        let assigned: Vec<usize> = points
+            .iter()
+            .map(|point| {
+                (0..centroids.len())
+                    .min_by(|&a, &b| distance(point, &centroids[a]).total_cmp(&distance(point, &centroids[b])))
+                    .unwrap_or(0)
+            })
+            .collect();
+        let converged = assigned == labels;
+        labels = assigned;
+        if converged {
+            break;
+        }
+        for (bin, centroid) in centroids.iter_mut().enumerate() {
+            let members: Vec<&[f64; 2]> = points.iter().zip(&labels).filter(|&(_, &l)| l == bin).map(|(p, _)| p).collect();
+            if !members.is_empty() {
+                let n = members.len() as f64;
+                *centroid = [
+                    members.iter().map(|p| p[0]).sum::<f64>() / n,
+                    members.iter().map(|p| p[1]).sum::<f64>() / n,
+                ];
+            }
+        }
+    }
+    labels
+}
+
+/// Mean k-mer count for each GC content level, as (GC fraction, mean coverage) sorted by GC.
+/// K-mers are binned by their exact GC fraction (k + 1 bins for k-mers of length k), so the
+/// curve shows whether GC-rich or AT-rich k-mers are systematically under- or over-covered.
+pub fn gc_coverage_bias(kmer_counts: &HashMap<String, usize>) -> Vec<(f64, f64)> {
+    // (GC bases, k-mer length) -> (total count, number of k-mers)
+    let mut bins: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+    for (kmer, &count) in kmer_counts {
+        if kmer.is_empty() {
+            continue;
+        }
+        let gc = kmer.bytes().filter(|b| matches!(b, b'G' | b'C' | b'g' | b'c')).count();
+        let bin = bins.entry((gc, kmer.len())).or_insert((0, 0));
+        bin.0 += count;
+        bin.1 += 1;
+    }
+
+    let mut bias: Vec<(f64, f64)> = bins
+        .into_iter()
+        .map(|((gc, len), (total, kmers))| (gc as f64 / len as f64, total as f64 / kmers as f64))
+        .collect();
+    bias.sort_by(|a, b| a.0.total_cmp(&b.0));
+    bias
+}
+
+/// The k-mer spectrum (frequency of frequencies): multiplicity -> number of distinct k-mers
+/// occurring that many times, in increasing order of multiplicity.
+pub fn compute_spectrum(kmer_counts: &HashMap<String, usize>) -> BTreeMap<usize, usize> {
+    let mut spectrum = BTreeMap::new();
+    for &count in kmer_counts.values() {
+        *spectrum.entry(count).or_insert(0) += 1;
+    }
+    spectrum
+}
+
+/// Genome size, coverage and error rate estimated from a k-mer spectrum; see [`estimate_genome`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenomeEstimate {
+    /// K-mer size the spectrum was counted with.
+    pub k: usize,
+    /// Multiplicity of the genomic peak, the typical number of times each genomic k-mer was seen.
+    pub kmer_coverage: usize,
+    /// Multiplicity of the trough between the error spike and the genomic peak; k-mers seen
+    /// fewer times than this are taken to be sequencing errors.
+    pub error_threshold: usize,
+    /// Estimated genome length in bases.
+    pub genome_size: usize,
+    /// Distinct k-mers below the error threshold.
+    pub error_kmers: usize,
+    /// Estimated per-base sequencing error rate.
+    pub error_rate: f64,
+}
+
+impl GenomeEstimate {
+    /// Per-base sequencing coverage for reads of `read_length` bases. Each read only yields
+    /// read_length - k + 1 k-mers, and only those free of errors land in the genomic peak, so
+    /// base coverage is higher than the k-mer coverage.
+    pub fn base_coverage(&self, read_length: usize) -> f64 {
+        if read_length < self.k {
+            return 0.0;
+        }
+        let error_free = (1.0 - self.error_rate).powi(self.k as i32);
+        self.kmer_coverage as f64 / error_free * read_length as f64 / (read_length - self.k + 1) as f64
+    }
+}
+
+impl fmt::Display for GenomeEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "K-mer coverage: {}x (error threshold {}x)", self.kmer_coverage, self.error_threshold)?;
+        writeln!(f, "Genome size: {} bp", self.genome_size)?;
+        write!(f, "Error rate: {:.3}% ({} error k-mers)", self.error_rate * 100.0, self.error_kmers)
+    }
+}
+
+/// GenomeScope-style estimate from a k-mer spectrum (see compute_spectrum) counted with k-mer
+/// size `k`. The error spike is followed down to its trough, the genomic peak is the most
+/// frequent multiplicity past it, and the genome size is the number of k-mer occurrences from
+/// the trough on divided by the peak multiplicity. The error rate is the fraction of k-mer
+/// occurrences below the trough, converted to a per-base rate since one error spoils k k-mers.
+/// Returns None when the spectrum has no peak apart from the error spike.
+pub fn estimate_genome(spectrum: &BTreeMap<usize, usize>, k: usize) -> Option<GenomeEstimate> {
+    let (trough, peak) = trough_and_peak(spectrum)?;
+    let max_multiplicity = *spectrum.keys().next_back()?;
+
+    let occurrences = |range: RangeInclusive<usize>| -> usize { spectrum.range(range).map(|(&m, &kmers)| m * kmers).sum() };
+    let error_occurrences = if trough > 1 { occurrences(1..=trough - 1) } else { 0 };
+    let total_occurrences = occurrences(1..=max_multiplicity);
+    let error_kmer_fraction = error_occurrences as f64 / total_occurrences as f64;
+
+    Some(GenomeEstimate {
+        k,
+        kmer_coverage: peak,
+        error_threshold: trough,
+        genome_size: ((total_occurrences - error_occurrences) as f64 / peak as f64).round() as usize,
+        error_kmers: spectrum.range(..trough).map(|(_, &kmers)| kmers).sum(),
+        error_rate: 1.0 - (1.0 - error_kmer_fraction).powf(1.0 / k as f64),
+    })
+}
+
+/// Multiplicity of the trough between the error spike and the genomic peak of a k-mer spectrum,
+/// as found by estimate_genome: k-mers seen fewer times than this are most likely sequencing
+/// errors. Returns None when the spectrum has no peak apart from the error spike.
+pub fn error_threshold(spectrum: &BTreeMap<usize, usize>) -> Option<usize> {
+    trough_and_peak(spectrum).map(|(trough, _)| trough)
+}
+
+// The error trough and genomic peak multiplicities of a spectrum
+fn trough_and_peak(spectrum: &BTreeMap<usize, usize>) -> Option<(usize, usize)> {
+    let distinct = |multiplicity: usize| spectrum.get(&multiplicity).copied().unwrap_or(0);
+    let max_multiplicity = *spectrum.keys().next_back()?;
+
+    // Walk down the error spike until the spectrum starts rising again
+    let descent_end = (1..max_multiplicity).find(|&m| distinct(m) < distinct(m + 1))?;
+    let (peak, _) = spectrum
+        .range(descent_end + 1..)
+        .fold((0, 0), |best, (&m, &kmers)| if kmers > best.1 { (m, kmers) } else { best });
+    // The lowest point between the spike and the peak separates errors from genomic k-mers
+    let trough = (descent_end..peak).min_by_key(|&m| distinct(m)).unwrap_or(descent_end);
+    Some((trough, peak))
+}
+
+/// Size and contiguity of a set of assembled contigs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AssemblyStats {
+    /// Number of contigs.
+    pub contigs: usize,
+    /// Sum of the contig lengths.
+    pub total_length: usize,
+    /// Length of the shortest contig among the longest ones that together cover half the
+    /// total length.
+    pub n50: usize,
+    /// Length of the longest contig.
+    pub longest: usize,
+}
+
+impl AssemblyStats {
+    /// Compute the statistics of contigs of the given lengths, in any order.
+    pub fn from_lengths(lengths: &[usize]) -> Self {
+        let mut sorted = lengths.to_vec();
+        sorted.sort_unstable_by(|a, b| b.cmp(a));
+        let total_length: usize = sorted.iter().sum();
+        let mut covered = 0;
+        let n50 = sorted
+            .iter()
+            .find(|&&length| {
+                covered += length;
+                2 * covered >= total_length
+            })
+            .copied()
+            .unwrap_or(0);
+        AssemblyStats { contigs: sorted.len(), total_length, n50, longest: sorted.first().copied().unwrap_or(0) }
+    }
+}
+
+impl fmt::Display for AssemblyStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Contigs: {}", self.contigs)?;
+        writeln!(f, "Total length: {} bp", self.total_length)?;
+        writeln!(f, "N50: {} bp", self.n50)?;
+        write!(f, "Longest contig: {} bp", self.longest)
+    }
+}
+
+/// A k-mer's counts in two samples, as diff_counts joins them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KmerDiff {
+    /// The k-mer.
+    pub kmer: String,
+    /// Its count in sample A, 0 if A lacks it.
+    pub count_a: usize,
+    /// Its count in sample B, 0 if B lacks it.
+    pub count_b: usize,
+    /// log2 of its frequency in B over its frequency in A, each frequency taken as the count
+    /// plus a pseudocount of 1 over the sample's total count; positive when B is enriched.
+    pub log2_fold_change: f64,
+}
+
+/// Join two count tables on k-mer, keeping the k-mers of either, in k-mer order. Frequencies
+/// are normalised by each sample's total so deeper sequencing alone doesn't look like
+/// enrichment, and the pseudocount keeps k-mers missing from one sample finite.
+pub fn diff_counts(counts_a: &HashMap<String, usize>, counts_b: &HashMap<String, usize>) -> Vec<KmerDiff> {
+    let total_a = counts_a.values().sum::<usize>().max(1) as f64;
+    let total_b = counts_b.values().sum::<usize>().max(1) as f64;
+    let kmers: BTreeSet<&String> = counts_a.keys().chain(counts_b.keys()).collect();
+    kmers
+        .into_iter()
+        .map(|kmer| {
+            let count_a = counts_a.get(kmer).copied().unwrap_or(0);
+            let count_b = counts_b.get(kmer).copied().unwrap_or(0);
+            let frequency_a = (count_a + 1) as f64 / total_a;
+            let frequency_b = (count_b + 1) as f64 / total_b;
+            KmerDiff { kmer: kmer.clone(), count_a, count_b, log2_fold_change: (frequency_b / frequency_a).log2() }
+        })
+        .collect()
+}
+
+/// How many k-mers of a diff both samples share and how many only one has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DiffSummary {
+    /// K-mers counted in both samples.
+    pub shared: usize,
+    /// K-mers counted only in sample A.
+    pub only_a: usize,
+    /// K-mers counted only in sample B.
+    pub only_b: usize,
+}
+
+impl DiffSummary {
+    /// Tally the rows of diff_counts.
+    pub fn of(diffs: &[KmerDiff]) -> Self {
+        let mut summary = DiffSummary::default();
+        for diff in diffs {
+            match (diff.count_a, diff.count_b) {
+                (0, _) => summary.only_b += 1,
+                (_, 0) => summary.only_a += 1,
+                _ => summary.shared += 1,
+            }
+        }
+        summary
+    }
+}
+
+impl fmt::Display for DiffSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Shared k-mers: {}", self.shared)?;
+        writeln!(f, "Only in A: {}", self.only_a)?;
+        write!(f, "Only in B: {}", self.only_b)
+    }
+}
+
+/// Number of k-mers seen exactly once (singletons, f1) and exactly twice (doubletons, f2).
+pub fn singleton_doubleton_counts(kmer_counts: &HashMap<String, usize>) -> (usize, usize) {
+    kmer_counts.values().fold((0, 0), |(f1, f2), &count| match count {
+        1 => (f1 + 1, f2),
+        2 => (f1, f2 + 1),
+        _ => (f1, f2),
+    })
+}
+
+/// Chao1 estimate of total k-mer richness including unseen k-mers: S_obs + f1^2 / (2 f2).
+/// When there are no doubletons the bias-corrected form S_obs + f1 (f1 - 1) / (2 (f2 + 1)) is used.
+pub fn chao1_estimate(kmer_counts: &HashMap<String, usize>) -> f64 {
+    let observed = kmer_counts.values().filter(|&&count| count > 0).count() as f64;
+    let (f1, f2) = singleton_doubleton_counts(kmer_counts);
+    let (f1, f2) = (f1 as f64, f2 as f64);
+    if f2 > 0.0 {
+        observed + f1 * f1 / (2.0 * f2)
+    } else {
+        observed + f1 * (f1 - 1.0).max(0.0) / (2.0 * (f2 + 1.0))
+    }
+}
+
+/// Whittaker (abundance-rank) data: (rank, count) pairs with ranks 1..n over counts sorted
+/// from most to least abundant.
+pub fn abundance_rank_data(kmer_counts: &HashMap<String, usize>) -> Vec<(usize, usize)> {
+    let mut counts: Vec<usize> = kmer_counts.values().copied().collect();
+    counts.sort_unstable_by(|a, b| b.cmp(a));
+    counts.into_iter().enumerate().map(|(index, count)| (index + 1, count)).collect()
+}
+
+/// Find positions where the k-mer composition of `seq` changes, such as isochore or element boundaries.
+/// At candidate positions every window / 4 bases, the windows just before and after are compared by
+/// profile_distance; positions whose distance is a local peak, exceeds the mean by two standard
+/// deviations, and is at least twice the median (noise) distance are reported, keeping only the
+/// strongest peak within any one window length.
+pub fn composition_changepoints(seq: &str, k: usize, window: usize) -> Vec<usize> {
+    if k == 0 || window < k || seq.len() < 2 * window {
+        return Vec::new();
+    }
+    let step = (window / 4).max(1);
+    let profile = |start: usize| count_kmers(generate_kmers(&seq[start..start + window], k));
+
+    let scores: Vec<(usize, f64)> = (window..=seq.len() - window)
+        .step_by(step)
+        .map(|position| (position, profile_distance(&profile(position - window), &profile(position))))
+        .collect();
+    let n = scores.len() as f64;
+    let mean = scores.iter().map(|&(_, d)| d).sum::<f64>() / n;
+    let sd = (scores.iter().map(|&(_, d)| (d - mean).powi(2)).sum::<f64>() / n).sqrt();
+    // The median distance estimates sampling noise between windows of the same composition.
+    let mut sorted: Vec<f64> = scores.iter().map(|&(_, d)| d).collect();
+    sorted.sort_by(f64::total_cmp);
+    let median = sorted[sorted.len() / 2];
+    let threshold = (mean + 2.0 * sd).max(2.0 * median);
+
+    let mut changepoints: Vec<(usize, f64)> = Vec::new();
+    for (i, &(position, distance)) in scores.iter().enumerate() {
+        let is_peak = (i == 0 || scores[i - 1].1 <= distance) && (i + 1 == scores.len() || scores[i + 1].1 <= distance);
+        if !is_peak || distance <= threshold {
+            continue;
+        }
+        match changepoints.last_mut() {
+            Some(last) if position - last.0 < window => {
+                if distance > last.1 {
+                    *last = (position, distance);
+                }
+            }
+            _ => changepoints.push((position, distance)),
+        }
+    }
+    changepoints.into_iter().map(|(position, _)| position).collect()
+}
+
+/// Per non-overlapping window of the sample, (window start, fraction of the window's k-mers found
+/// in neither orientation in the reference). High-novelty windows point at insertions or
+/// horizontally transferred elements; a trailing window shorter than k is skipped.
+pub fn novel_content_windows(sample: &str, reference_kmers: &HashSet<String>, k: usize, window: usize) -> Vec<(usize, f64)> {
+    if k == 0 || window < k {
+        return Vec::new();
+    }
+    (0..sample.len())
+        .step_by(window)
+        .filter_map(|start| {
+            let kmers = generate_kmers(&sample[start..(start + window).min(sample.len())], k);
+            if kmers.is_empty() {
+                return None;
+            }
+            let novel = kmers
+                .iter()
+                .filter(|kmer| !reference_kmers.contains(*kmer) && !reference_kmers.contains(&reverse_complement(kmer)))
+                .count();
+            Some((start, novel as f64 / kmers.len() as f64))
+        })
+        .collect()
+}
+
+/// Binary entropy in bits of a split with `positive` of `total` items in one class.
+pub fn binary_entropy(positive: usize, total: usize) -> f64 {
+    if total == 0 || positive == 0 || positive == total {
+        return 0.0;
+    }
+    let p = positive as f64 / total as f64;
+    -(p * p.log2() + (1.0 - p) * (1.0 - p).log2())
+}
+
+/// Rank k-mers by how informative their presence is about a binary sample label.
+/// For each k-mer seen in any sample the information gain is H(label) - H(label | present),
+/// i.e. the mutual information between presence and label. Sorted descending, ties by k-mer.
+pub fn kmer_information_gain(samples: &[(String, bool)], k: usize) -> Vec<(String, f64)> {
+    let total = samples.len();
+    let positives = samples.iter().filter(|(_, label)| *label).count();
+    let base_entropy = binary_entropy(positives, total);
+
+    // k-mer -> (samples containing it, positive samples containing it)
+    let mut presence: HashMap<String, (usize, usize)> = HashMap::new();
+    for (seq, label) in samples {
+        if k == 0 || seq.len() < k {
+            continue;
+        }
+        for kmer in generate_kmers(seq, k).into_iter().collect::<HashSet<_>>() {
+            let entry = presence.entry(kmer).or_insert((0, 0));
+            entry.0 += 1;
+            entry.1 += usize::from(*label);
+        }
+    }
+
+    let mut gains: Vec<(String, f64)> = presence
+        .into_iter()
+        .map(|(kmer, (with, with_positive))| {
+            let without = total - with;
+            let without_positive = positives - with_positive;
+            let conditional = (with as f64 * binary_entropy(with_positive, with)
+                + without as f64 * binary_entropy(without_positive, without))
+                / total as f64;
+            (kmer, base_entropy - conditional)
+        })
+        .collect();
+    gains.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    gains
+}
+
+/// Estimate compressibility as the conditional entropy (bits per base) of an order-`order` Markov model.
+/// Transition probabilities come from counting (order + 1)-mers and their order-length contexts.
+/// Lower values mean a more predictable, repetitive sequence; random DNA approaches 2 bits/base.
+pub fn kmer_context_entropy(seq: &str, order: usize) -> f64 {
+    if seq.len() <= order {
+        return 0.0;
+    }
+
+    let transitions = count_kmers(generate_kmers(seq, order + 1));
+    let total: usize = transitions.values().sum();
+
+    // Tally how often each context is followed by any base.
+    let mut contexts: HashMap<&str, usize> = HashMap::new();
+    for (kmer, count) in &transitions {
+        *contexts.entry(&kmer[..order]).or_insert(0) += count;
+    }
+
+    let mut entropy = 0.0;
+    for (kmer, &count) in &transitions {
+        let joint = count as f64 / total as f64;
+        let conditional = count as f64 / contexts[&kmer[..order]] as f64;
+        entropy -= joint * conditional.log2();
+    }
+    entropy
+}
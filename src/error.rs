@@ -0,0 +1,87 @@
+//! The crate's error type, returned by every fallible function that isn't a thin wrapper over
+//! `std::io`, so callers can tell bad parameters and bad input apart from I/O failures.
+
+use std::io;
+use plotters::drawing::DrawingAreaErrorKind;
+use thiserror::Error;
+
+/// What went wrong in a k-mer analysis function.
+#[derive(Debug, Error)]
+pub enum KmerError {
+    /// A k-mer size outside the range the function accepts.
+    #[error("k = {k} is out of range: {constraint}")]
+    InvalidK {
+        /// The k that was given.
+        k: usize,
+        /// The range k has to lie in, in words.
+        constraint: String,
+    },
+    /// A k-mer size that differs from the one a graph or table was built with.
+    #[error("k-mer size {k} does not match the k of {expected} it was built with")]
+    KMismatch {
+        /// The k that was given.
+        k: usize,
+        /// The k already in use.
+        expected: usize,
+    },
+    /// A character that isn't a base, with its 1-based position in the sequence.
+    #[error("invalid character {character:?} at position {position}")]
+    InvalidCharacter {
+        /// The offending character.
+        character: char,
+        /// 1-based position of the character.
+        position: usize,
+    },
+    /// An ambiguous base where only A/C/G/T are allowed, with its 1-based position.
+    #[error("ambiguous base '{base}' at position {position}")]
+    AmbiguousBase {
+        /// The base, uppercased.
+        base: char,
+        /// 1-based position of the base.
+        position: usize,
+    },
+    /// More samples than a colored graph has colors for.
+    #[error("{samples} samples given, but a colored graph tells at most {max} apart")]
+    TooManySamples {
+        /// Number of samples given.
+        samples: usize,
+        /// Most samples allowed.
+        max: usize,
+    },
+    /// A k-mer that can't be packed into 2 bits per base.
+    #[error("cannot encode k-mer '{0}': only up to 32 A/C/G/T bases are supported")]
+    InvalidKmer(String),
+    /// A custom alphabet that can't be used, and why.
+    #[error("invalid alphabet: {0}")]
+    InvalidAlphabet(String),
+    /// Batch inputs that can't be run, such as a malformed manifest or two samples of one name.
+    #[error("invalid batch input: {0}")]
+    InvalidBatch(String),
+    /// Nothing to draw; names what was missing.
+    #[error("no {0} to plot")]
+    NothingToPlot(&'static str),
+    /// A drawing backend failure.
+    #[error("plotting failed: {0}")]
+    Plot(String),
+    /// Reading or writing a file failed.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// Writing CSV failed.
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+    /// Writing JSON failed.
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+impl<E: std::error::Error + Send + Sync> From<DrawingAreaErrorKind<E>> for KmerError {
+    fn from(e: DrawingAreaErrorKind<E>) -> Self {
+        KmerError::Plot(e.to_string())
+    }
+}
+
+impl<W> From<csv::IntoInnerError<W>> for KmerError {
+    fn from(e: csv::IntoInnerError<W>) -> Self {
+        KmerError::Io(e.into_error())
+    }
+}
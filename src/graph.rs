@@ -0,0 +1,1296 @@
+//! The De Bruijn graph, its traversals and repairs, and overlap graphs.
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::ops::RangeInclusive;
+use petgraph::algo::{connected_components, toposort};
+use petgraph::graph::DiGraph;
+use petgraph::visit::{depth_first_search, DfsEvent, EdgeRef};
+use petgraph::Direction;
+use crate::alphabet::Alphabet;
+use crate::error::KmerError;
+use crate::kmer::{generate_kmers, Kmer, KmerNode};
+use crate::minimizer::minimizers;
+
+/// Build a read overlap graph for overlap-layout-consensus assembly, finding candidate overlaps
+/// through shared minimizers (as miniasm does). Each pair of reads sharing minimizers votes on
+/// the offset of one read within the other; the best-supported offset gives an edge from the
+/// read whose suffix overlaps to the read whose prefix it covers, weighted by the overlap length.
+/// Overlaps shorter than min_overlap, and pairs aligned at the same start, are not linked.
+pub fn build_overlap_graph(reads: &[String], k: usize, w: usize, min_overlap: usize) -> DiGraph<usize, usize> {
+    let mut index: HashMap<String, Vec<(usize, usize)>> = HashMap::new();
+    for (read_id, read) in reads.iter().enumerate() {
+        for (position, kmer) in minimizers(read, k, w) {
+            index.entry(kmer).or_default().push((read_id, position));
+        }
+    }
+
+    // (earlier read, later read) -> offset of the later read within the earlier -> votes.
+    let mut votes: HashMap<(usize, usize), HashMap<i64, usize>> = HashMap::new();
+    for hits in index.values() {
+        for (i, &(read_a, pos_a)) in hits.iter().enumerate() {
+            for &(read_b, pos_b) in &hits[i + 1..] {
+                if read_a != read_b {
+                    let offset = pos_a as i64 - pos_b as i64;
+                    *votes.entry((read_a, read_b)).or_default().entry(offset).or_insert(0) += 1;
+                }
+            }
+        }
+    }
+
+    let mut graph = DiGraph::new();
+    let nodes: Vec<_> = (0..reads.len()).map(|read_id| graph.add_node(read_id)).collect();
+    let mut pairs: Vec<_> = votes.into_iter().collect();
+    pairs.sort_by_key(|&((a, b), _)| (a, b));
+    for ((read_a, read_b), offsets) in pairs {
+        let (&offset, _) = offsets.iter().max_by_key(|&(&offset, &count)| (count, -offset.abs())).unwrap();
+        // A positive offset means read_b starts inside read_a: read_a's suffix overlaps read_b's prefix.
+        let (from, to, shift) = match offset.cmp(&0) {
+            std::cmp::Ordering::Greater => (read_a, read_b, offset as usize),
+            std::cmp::Ordering::Less => (read_b, read_a, (-offset) as usize),
+            std::cmp::Ordering::Equal => continue,
+        };
+        let overlap = reads[from].len().saturating_sub(shift).min(reads[to].len());
+        if overlap >= min_overlap {
+            graph.add_edge(nodes[from], nodes[to], overlap);
+        }
+    }
+    graph
+}
+
+/// In- and out-degree of every node in a De Bruijn graph, keyed by node.
+pub type DegreeMap<N = String> = HashMap<N, (usize, usize)>;
+
+/// Count the nodes in a degree map with in-degree > 1 or out-degree > 1.
+pub fn count_branching_nodes<N>(degrees: &DegreeMap<N>) -> usize {
+    degrees
+        .values()
+        .filter(|&&(in_degree, out_degree)| in_degree > 1 || out_degree > 1)
+        .count()
+}
+
+/// The samples an edge of a colored graph was seen in, as a bitset: bit i stands for the
+/// graph's i-th sample.
+pub type Colors = u64;
+
+/// Most samples a colored graph can tell apart, one bit of Colors each.
+pub const MAX_COLORS: usize = Colors::BITS as usize;
+
+/// Define the structure for a De Bruijn graph.
+/// Nodes are String (k-1)-mers by default; `DeBruijnGraph<Kmer>` stores them 2-bit packed.
+#[derive(Debug, Clone)]
+pub struct DeBruijnGraph<N = String> {
+    /// Use a HashMap to represent the graph.
+    /// Keys are a k-1-mer (a node),
+    /// Values map each adjacent k-1-mer to the multiplicity of the edge to it: how many times
+    /// its k-mer was seen, i.e. its coverage.
+    pub edges: HashMap<N, BTreeMap<N, usize>>,
+    /// The k-mer size the graph was built with (0 for a graph built from no k-mers).
+    pub k: usize,
+    /// Names of the samples of a colored graph (see from_samples), in color order; empty for a
+    /// graph of one sample.
+    pub samples: Vec<String>,
+    /// The colors of every edge of a colored graph, keyed by (node, successor); empty otherwise.
+    pub colors: HashMap<(N, N), Colors>,
+}
+
+/// Methods that only need node identity, shared by every k-mer representation.
+impl<N: KmerNode> DeBruijnGraph<N> {
+    /// Build a graph from k-mers of any KmerNode representation: each k-mer adds an edge from its
+    /// (k-1)-base prefix to its (k-1)-base suffix.
+    pub fn from_kmers(kmers: &[N], k: usize) -> Self {
+        let mut graph = DeBruijnGraph { edges: HashMap::new(), k: if kmers.is_empty() { 0 } else { k }, samples: Vec::new(), colors: HashMap::new() };
+        for kmer in kmers {
+            graph.add_edge(kmer.prefix(k), kmer.suffix(k), 1);
+        }
+        graph
+    }
+
+    /// Raise the multiplicity of the edge from `node` to `next` by `weight`, adding the edge
+    /// first if it is new. A weight of 0 adds nothing.
+    pub fn add_edge(&mut self, node: N, next: N, weight: usize) {
+        if weight > 0 {
+            *self.edges.entry(node).or_default().entry(next).or_insert(0) += weight;
+        }
+    }
+
+    /// Multiplicity of the edge from `node` to `next`, 0 if there is none.
+    pub fn edge_weight(&self, node: &N, next: &N) -> usize {
+        self.edges.get(node).and_then(|next_nodes| next_nodes.get(next)).copied().unwrap_or(0)
+    }
+
+    /// Collect every node in the graph (sources and targets of edges), sorted for determinism.
+    pub fn nodes(&self) -> Vec<N> {
+        let mut nodes: Vec<N> = self
+            .edges
+            .iter()
+            .flat_map(|(node, next_nodes)| std::iter::once(node).chain(next_nodes.keys()))
+            .cloned()
+            .collect();
+        nodes.sort();
+        nodes.dedup();
+        nodes
+    }
+
+    /// Map each directed edge to its multiplicity, the number of times its k-mer was seen.
+    pub fn edge_weights(&self) -> HashMap<(N, N), usize> {
+        self.edges
+            .iter()
+            .flat_map(|(node, next_nodes)| next_nodes.iter().map(move |(next, &weight)| ((node.clone(), next.clone()), weight)))
+            .collect()
+    }
+
+    /// Compute (in-degree, out-degree) for every node, counting each distinct edge once
+    /// so that repeated k-mers raise coverage rather than branching.
+    /// Analyses that need degrees take this map so it can be computed once and shared.
+    pub fn degrees(&self) -> DegreeMap<N> {
+        let mut degrees: DegreeMap<N> = HashMap::new();
+        for (node, next) in self.edge_weights().into_keys() {
+            degrees.entry(node).or_insert((0, 0)).1 += 1;
+            degrees.entry(next).or_insert((0, 0)).0 += 1;
+        }
+        degrees
+    }
+
+    /// Count nodes where the graph branches (in-degree > 1 or out-degree > 1).
+    pub fn branching_node_count(&self) -> usize {
+        count_branching_nodes(&self.degrees())
+    }
+
+    /// Fraction of nodes that branch; 0.0 for a simple path, higher for a more tangled graph.
+    pub fn complexity_ratio(&self) -> f64 {
+        let degrees = self.degrees();
+        if degrees.is_empty() {
+            return 0.0;
+        }
+        count_branching_nodes(&degrees) as f64 / degrees.len() as f64
+    }
+
+    /// Whether the graph is a single non-branching path visiting every edge once,
+    /// i.e. the sequence it came from can be reconstructed unambiguously.
+    pub fn is_simple_path(&self) -> bool {
+        let degrees = self.degrees();
+        let weights = self.edge_weights();
+        let sources = degrees.values().filter(|&&(in_degree, _)| in_degree == 0).count();
+        count_branching_nodes(&degrees) == 0
+            && sources == 1
+            && degrees.len() == weights.len() + 1
+            && weights.values().all(|&weight| weight == 1)
+    }
+
+    /// Map each node to the distinct nodes it has an edge to, in order.
+    pub fn successors(&self) -> HashMap<N, Vec<N>> {
+        self.edges.iter().map(|(node, next_nodes)| (node.clone(), next_nodes.keys().cloned().collect())).collect()
+    }
+
+    /// Map each node to the distinct nodes with an edge into it.
+    pub fn predecessors(&self) -> HashMap<N, Vec<N>> {
+        let mut predecessors: HashMap<N, Vec<N>> = HashMap::new();
+        let mut edges: Vec<(N, N)> = self.edge_weights().into_keys().collect();
+        edges.sort();
+        for (node, next) in edges {
+            predecessors.entry(next).or_default().push(node);
+        }
+        predecessors
+    }
+
+    /// Find dead-end paths of at most max_tip_len edges hanging off a branching node.
+    /// A tip starts at a node with no incoming (or no outgoing) edges and runs through
+    /// non-branching nodes until it joins a node that branches; the joining node itself is
+    /// not part of the tip. Isolated linear components are never reported as tips, and neither
+    /// is a dead end whose mean coverage is higher than that of every other branch at the join,
+    /// since that is more likely the true path than an error.
+    pub fn find_tips(&self, max_tip_len: usize) -> Vec<Vec<N>> {
+        self.find_tips_with_degrees(max_tip_len, &self.degrees())
+    }
+
+    /// find_tips using a precomputed degree map.
+    pub fn find_tips_with_degrees(&self, max_tip_len: usize, degrees: &DegreeMap<N>) -> Vec<Vec<N>> {
+        let predecessors = self.predecessors();
+        let successors = self.successors();
+
+        let mut starts: Vec<&N> = degrees.keys().collect();
+        starts.sort();
+
+        let mut tips = Vec::new();
+        for start in starts {
+            let (in_degree, out_degree) = degrees[start];
+            // Walk away from the dead end: forwards from a source, backwards from a sink.
+            // The other branches at the join lead into it from a source, out of it from a sink.
+            let (step, branches, forwards) = match (in_degree, out_degree) {
+                (0, 1) => (&successors, &predecessors, true),
+                (1, 0) => (&predecessors, &successors, false),
+                _ => continue,
+            };
+
+            let mut tip = vec![start.clone()];
+            let mut current = start;
+            loop {
+                let next = &step[current][0];
+                let (next_in, next_out) = degrees[next];
+                // The next node joins the rest of the graph if it branches on the side we arrive from.
+                let joins = if forwards { next_in > 1 } else { next_out > 1 };
+                if joins {
+                    if tip.len() <= max_tip_len && !self.outcovers_branches(&tip, next, forwards, &branches[next]) {
+                        tips.push(tip);
+                    }
+                    break;
+                }
+                if next_in != 1 || next_out != 1 || tip.len() >= max_tip_len || tip.contains(next) {
+                    break;
+                }
+                tip.push(next.clone());
+                current = next;
+            }
+        }
+        tips
+    }
+
+    // Whether a tip, walked away from its dead end until `join`, is better covered than every
+    // other branch at the join; `neighbours` are the nodes of the join's edges on the tip's side
+    fn outcovers_branches(&self, tip: &[N], join: &N, forwards: bool, neighbours: &[N]) -> bool {
+        let weight = |a: &N, b: &N| if forwards { self.edge_weight(a, b) } else { self.edge_weight(b, a) };
+        let last = &tip[tip.len() - 1];
+        let tip_weight: usize = tip.windows(2).map(|pair| weight(&pair[0], &pair[1])).sum::<usize>() + weight(last, join);
+        let tip_coverage = tip_weight as f64 / tip.len() as f64;
+        let best_branch = neighbours.iter().filter(|&node| node != last).map(|node| weight(node, join)).max().unwrap_or(0);
+        tip_coverage > best_branch as f64
+    }
+
+    /// Remove a set of nodes and every edge touching them.
+    pub fn remove_nodes(&mut self, nodes: &HashSet<N>) {
+        self.edges.retain(|node, _| !nodes.contains(node));
+        for next_nodes in self.edges.values_mut() {
+            next_nodes.retain(|next, _| !nodes.contains(next));
+        }
+        self.edges.retain(|_, next_nodes| !next_nodes.is_empty());
+        self.colors.retain(|(node, next), _| !nodes.contains(node) && !nodes.contains(next));
+    }
+
+    /// Whether the graph records which samples its edges came from.
+    pub fn is_colored(&self) -> bool {
+        !self.samples.is_empty()
+    }
+
+    /// Drop every edge seen fewer than min_coverage times, as most sequencing errors are; a
+    /// min_coverage of 2 drops the edges whose k-mer was seen once. Nodes left without edges go
+    /// with them.
+    pub fn remove_low_coverage_edges(&mut self, min_coverage: usize) -> SimplificationReport {
+        let (nodes_before, edges_before) = (self.node_count(), self.edge_count());
+        for next_nodes in self.edges.values_mut() {
+            next_nodes.retain(|_, weight| *weight >= min_coverage);
+        }
+        self.edges.retain(|_, next_nodes| !next_nodes.is_empty());
+        let edges = &self.edges;
+        self.colors.retain(|(node, next), _| edges.get(node).is_some_and(|next_nodes| next_nodes.contains_key(next)));
+        SimplificationReport { nodes_before, edges_before, nodes_after: self.node_count(), edges_after: self.edge_count() }
+    }
+
+    /// Colors of every node: the samples of any edge into or out of it.
+    pub fn node_colors(&self) -> HashMap<N, Colors> {
+        let mut node_colors: HashMap<N, Colors> = HashMap::new();
+        for ((node, next), &colors) in &self.colors {
+            *node_colors.entry(node.clone()).or_insert(0) |= colors;
+            *node_colors.entry(next.clone()).or_insert(0) |= colors;
+        }
+        node_colors
+    }
+
+    /// Names of the samples in a set of colors, in color order.
+    pub fn sample_names(&self, colors: Colors) -> Vec<&str> {
+        self.samples
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| colors & (1 << i) != 0)
+            .map(|(_, name)| name.as_str())
+            .collect()
+    }
+
+    /// For each sample, in color order, how many distinct edges it has and how many of them no
+    /// other sample shares.
+    pub fn sample_edge_counts(&self) -> Vec<(usize, usize)> {
+        (0..self.samples.len())
+            .map(|i| {
+                let bit = 1 << i;
+                let with = self.colors.values().filter(|&&colors| colors & bit != 0);
+                with.fold((0, 0), |(edges, unique), &colors| (edges + 1, unique + usize::from(colors == bit)))
+            })
+            .collect()
+    }
+
+    /// How many distinct edges are shared by exactly n samples, for each n that occurs: edges
+    /// of every sample make the core of a pan-genome, edges of one its sample-specific part.
+    pub fn color_histogram(&self) -> BTreeMap<usize, usize> {
+        let mut histogram = BTreeMap::new();
+        for colors in self.colors.values() {
+            *histogram.entry(colors.count_ones() as usize).or_insert(0) += 1;
+        }
+        histogram
+    }
+
+    /// Repeatedly remove tips until none are left or max_iterations passes have run,
+    /// since clipping one tip can expose another behind it. Returns the number of tips removed.
+    pub fn trim_tips(&mut self, max_tip_len: usize, max_iterations: usize) -> usize {
+        let mut removed = 0;
+        for _ in 0..max_iterations {
+            let tips = self.find_tips(max_tip_len);
+            if tips.is_empty() {
+                break;
+            }
+            removed += tips.len();
+            let nodes: HashSet<N> = tips.into_iter().flatten().collect();
+            self.remove_nodes(&nodes);
+        }
+        removed
+    }
+
+    /// Number of distinct nodes.
+    pub fn node_count(&self) -> usize {
+        self.nodes().len()
+    }
+
+    /// Number of distinct edges, however often each k-mer was seen.
+    pub fn edge_count(&self) -> usize {
+        self.edge_weights().len()
+    }
+
+    /// Clip dead-end branches of at most max_len nodes (see find_tips), repeating until no tip is
+    /// left since clipping one can expose another.
+    pub fn remove_tips(&mut self, max_len: usize) -> SimplificationReport {
+        let (nodes_before, edges_before) = (self.node_count(), self.edge_count());
+        // Every pass that finds a tip removes at least one node, bounding the passes.
+        self.trim_tips(max_len, nodes_before);
+        SimplificationReport { nodes_before, edges_before, nodes_after: self.node_count(), edges_after: self.edge_count() }
+    }
+
+    /// Collapse bubbles: parallel paths of equal length that leave the same branching node through
+    /// 1-in-1-out nodes and rejoin at the same node, as a sequencing error or SNP makes. The path
+    /// with the highest total edge weight is kept (ties go to the smallest nodes) and the interior
+    /// nodes of the others are removed. Repeats until no bubble is left.
+    pub fn pop_bubbles(&mut self) -> SimplificationReport {
+        let (nodes_before, edges_before) = (self.node_count(), self.edge_count());
+        loop {
+            let degrees = self.degrees();
+            let successors = self.successors();
+            let weights = self.edge_weights();
+
+            let mut sources: Vec<&N> = successors.iter().filter(|(_, next)| next.len() > 1).map(|(node, _)| node).collect();
+            sources.sort();
+            let mut popped = HashSet::new();
+            for source in sources {
+                // (rejoining node, path length) -> (coverage, interior nodes) of the best branch so far.
+                let mut best: HashMap<(&N, usize), (usize, Vec<N>)> = HashMap::new();
+                for first in &successors[source] {
+                    let mut coverage = weights[&(source.clone(), first.clone())];
+                    let mut interior = Vec::new();
+                    let mut current = first;
+                    while degrees[current] == (1, 1) && current != source && interior.len() < degrees.len() {
+                        let next = &successors[current][0];
+                        coverage += weights[&(current.clone(), next.clone())];
+                        interior.push(current.clone());
+                        current = next;
+                    }
+                    if interior.is_empty() {
+                        continue;
+                    }
+                    let branch = (coverage, interior);
+                    match best.get_mut(&(current, branch.1.len())) {
+                        Some(kept) => {
+                            // Keep the higher coverage, then the smaller nodes; pop the other.
+                            let replace = branch.0 > kept.0 || (branch.0 == kept.0 && branch.1 < kept.1);
+                            let loser = if replace { std::mem::replace(kept, branch) } else { branch };
+                            popped.extend(loser.1);
+                        }
+                        None => {
+                            best.insert((current, branch.1.len()), branch);
+                        }
+                    }
+                }
+            }
+            if popped.is_empty() {
+                break;
+            }
+            self.remove_nodes(&popped);
+        }
+        SimplificationReport { nodes_before, edges_before, nodes_after: self.node_count(), edges_after: self.edge_count() }
+    }
+}
+
+/// A graph over 2-bit packed k-mers, whose nodes are the packed (k-1)-mers.
+impl DeBruijnGraph<Kmer> {
+    /// Decode every node back to bases, for the String-only traversals and writers.
+    pub fn decode(&self) -> DeBruijnGraph {
+        let decode = |node: &Kmer| node.to_string(self.k - 1);
+        let edges = self
+            .edges
+            .iter()
+            .map(|(node, next_nodes)| (decode(node), next_nodes.iter().map(|(next, &weight)| (decode(next), weight)).collect()))
+            .collect();
+        let colors = self.colors.iter().map(|((node, next), &colors)| ((decode(node), decode(next)), colors)).collect();
+        DeBruijnGraph { edges, k: self.k, samples: self.samples.clone(), colors }
+    }
+}
+
+// Implement methods for the DeBruijnGraph structure.
+impl DeBruijnGraph {
+    /// Constructor method to create a new De Bruijn graph from a list of k-mers.
+    pub fn new(kmers: &[String]) -> Self {
+        let k = kmers.first().map_or(0, |kmer| kmer.len());
+        let mut graph = DeBruijnGraph { edges: HashMap::new(), k, samples: Vec::new(), colors: HashMap::new() };
+
+        // Iterate over each k-mer in the given list.
+        for kmer in kmers {
+            // Split the k-mer into two parts:
+            // - The first part (node) is all but the last character.
+            // - The second part (next) is all but the first character.
+            // This split creates an overlap between the k-1-mers.
+            let node = &kmer[..kmer.len() - 1];
+            let next = &kmer[1..];
+
+            // Insert the node into the HashMap if it doesn't exist,
+            // and count one more occurrence of the edge to the adjacent node.
+            graph.add_edge(node.to_string(), next.to_string(), 1);
+        }
+
+        // Return the new De Bruijn graph with these edges.
+        graph
+    }
+
+    /// Build one colored graph of several named samples, each a list of records, as
+    /// from_sequences builds the graph of one: every edge keeps the multiplicity of all samples
+    /// together, and its colors record which samples it came from. Errors with more than
+    /// MAX_COLORS samples.
+    pub fn from_samples(samples: &[(String, Vec<String>)], k: usize, alphabet: &Alphabet) -> Result<Self, KmerError> {
+        if samples.len() > MAX_COLORS {
+            return Err(KmerError::TooManySamples { samples: samples.len(), max: MAX_COLORS });
+        }
+        let names = samples.iter().map(|(name, _)| name.clone()).collect();
+        let mut graph = DeBruijnGraph { edges: HashMap::new(), k, samples: names, colors: HashMap::new() };
+        for (i, (_, sequences)) in samples.iter().enumerate() {
+            for kmer in sequences.iter().flat_map(|sequence| alphabet.kmers(sequence, k)) {
+                let (node, next) = (kmer[..k - 1].to_string(), kmer[1..].to_string());
+                *graph.colors.entry((node.clone(), next.clone())).or_insert(0) |= 1 << i;
+                graph.add_edge(node, next, 1);
+            }
+        }
+        if graph.edges.is_empty() {
+            graph.k = 0;
+        }
+        Ok(graph)
+    }
+
+    /// Build the graph of the k-mers of every record, skipping those that span anything but the
+    /// alphabet's symbols, so no edge crosses a record boundary or an ambiguous symbol.
+    pub fn from_sequences(sequences: &[String], k: usize, alphabet: &Alphabet) -> Self {
+        let kmers: Vec<String> = sequences.iter().flat_map(|sequence| alphabet.kmers(sequence, k)).collect();
+        DeBruijnGraph::new(&kmers)
+    }
+
+    /// Build the graph of a k-mer count table: the same graph as new() over every occurrence,
+    /// with each k-mer's count as the weight of its edge.
+    pub fn from_counts(kmer_counts: &HashMap<String, usize>) -> Self {
+        let k = kmer_counts.keys().next().map_or(0, String::len);
+        let mut graph = DeBruijnGraph { edges: HashMap::new(), k, samples: Vec::new(), colors: HashMap::new() };
+        for (kmer, &count) in kmer_counts {
+            graph.add_edge(kmer[..kmer.len() - 1].to_string(), kmer[1..].to_string(), count);
+        }
+        graph
+    }
+
+    /// Insert the k-mers of a new sequence into the existing graph without rebuilding it,
+    /// adding nodes and edges (or raising the weight of existing edges) as reads arrive.
+    /// An empty graph adopts the given k; otherwise k must match the graph's k.
+    pub fn add_sequence(&mut self, seq: &str, k: usize) -> Result<(), KmerError> {
+        if k < 2 {
+            return Err(KmerError::InvalidK { k, constraint: "must be at least 2 to build a De Bruijn graph".to_string() });
+        }
+        if self.k == 0 {
+            self.k = k;
+        } else if self.k != k {
+            return Err(KmerError::KMismatch { k, expected: self.k });
+        }
+        if seq.len() < k {
+            return Ok(());
+        }
+        for i in 0..=seq.len() - k {
+            let kmer = &seq[i..i + k];
+            self.add_edge(kmer[..k - 1].to_string(), kmer[1..].to_string(), 1);
+        }
+        Ok(())
+    }
+
+    /// Resolve collapsed repeats by threading reads through the graph, and return the contigs found.
+    /// Each read records, for every node where the graph fans out, which edge the read used to
+    /// enter the current repeat (the last edge into a node with in-degree > 1) and which successor
+    /// it left by. Walks from every source node then follow unbranched edges directly and, at a
+    /// fan-out, take the successor that the reads uniquely support for the current entry edge.
+    /// Each edge is used at most as many times as its coverage so a walk always terminates.
+    pub fn thread_reads(&self, reads: &[String]) -> Vec<String> {
+        let degrees = self.degrees();
+        let successors = self.successors();
+        let Some(node_len) = degrees.keys().next().map(|node| node.len()) else {
+            return Vec::new();
+        };
+
+        // (entry edge, fan-out node) -> successors seen in reads.
+        type Entry = Option<(String, String)>;
+        let mut threads: HashMap<(Entry, String), HashSet<String>> = HashMap::new();
+        for read in reads {
+            if read.len() < node_len {
+                continue;
+            }
+            let path: Vec<&str> = (0..=read.len() - node_len).map(|i| &read[i..i + node_len]).collect();
+            let mut entry: Entry = None;
+            for step in path.windows(2) {
+                let (from, to) = (step[0], step[1]);
+                let (Some(&(_, out_degree)), Some(&(in_degree, _))) = (degrees.get(from), degrees.get(to)) else {
+                    entry = None;
+                    continue;
+                };
+                if out_degree > 1 {
+                    threads.entry((entry.clone(), from.to_string())).or_default().insert(to.to_string());
+                }
+                if in_degree > 1 {
+                    entry = Some((from.to_string(), to.to_string()));
+                }
+            }
+        }
+
+        let mut starts: Vec<&String> = degrees
+            .iter()
+            .filter(|(_, &(in_degree, _))| in_degree == 0)
+            .map(|(node, _)| node)
+            .collect();
+        if starts.is_empty() {
+            starts = degrees.keys().min().into_iter().collect();
+        }
+        starts.sort();
+
+        let mut remaining = self.edge_weights();
+        let mut contigs = Vec::new();
+        for start in starts {
+            let mut contig = start.clone();
+            let mut current = start.clone();
+            let mut entry: Entry = None;
+            loop {
+                let candidates: Vec<&String> = successors
+                    .get(&current)
+                    .map(|next_nodes| {
+                        next_nodes
+                            .iter()
+                            .filter(|next| remaining.get(&(current.clone(), (*next).clone())).is_some_and(|&w| w > 0))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let next = match candidates.as_slice() {
+                    [] => break,
+                    [only] => (*only).clone(),
+                    _ => {
+                        let supported: Vec<&&String> = threads
+                            .get(&(entry.clone(), current.clone()))
+                            .map(|seen| candidates.iter().filter(|next| seen.contains(**next)).collect())
+                            .unwrap_or_default();
+                        match supported.as_slice() {
+                            [only] => (**only).clone(),
+                            _ => break,
+                        }
+                    }
+                };
+
+                if let Some(weight) = remaining.get_mut(&(current.clone(), next.clone())) {
+                    *weight -= 1;
+                }
+                if degrees[&next].0 > 1 {
+                    entry = Some((current.clone(), next.clone()));
+                }
+                contig.push_str(&next[node_len - 1..]);
+                current = next;
+            }
+            contigs.push(contig);
+        }
+        contigs
+    }
+
+    /// Find connected regions of the graph made up only of edges with weight below min_weight.
+    /// Each region is returned as its nodes in walk order, starting from nodes with no
+    /// incoming low-weight edge, so under-covered side paths read from start to end.
+    pub fn low_coverage_regions(&self, min_weight: usize) -> Vec<Vec<String>> {
+        let mut low_edges: Vec<(String, String)> = self
+            .edge_weights()
+            .into_iter()
+            .filter(|(_, weight)| *weight < min_weight)
+            .map(|(edge, _)| edge)
+            .collect();
+        low_edges.sort();
+
+        // Adjacency in both directions so regions are weakly connected components.
+        let mut forward: HashMap<&str, Vec<&str>> = HashMap::new();
+        let mut neighbours: HashMap<&str, Vec<&str>> = HashMap::new();
+        let mut has_incoming = HashSet::new();
+        for (from, to) in &low_edges {
+            forward.entry(from).or_default().push(to);
+            neighbours.entry(from).or_default().push(to);
+            neighbours.entry(to).or_default().push(from);
+            has_incoming.insert(to.as_str());
+        }
+
+        let mut starts: Vec<&str> = neighbours.keys().copied().collect();
+        // Prefer region entry points, then fall back to any node (for cycles).
+        starts.sort_by_key(|node| (has_incoming.contains(node), *node));
+
+        let mut visited = HashSet::new();
+        let mut regions = Vec::new();
+        for start in starts {
+            if visited.contains(start) {
+                continue;
+            }
+            // Walk forward edges first to keep path order, then sweep the rest of the component.
+            let mut region = Vec::new();
+            let mut stack = vec![start];
+            while let Some(node) = stack.pop() {
+                if !visited.insert(node) {
+                    continue;
+                }
+                region.push(node.to_string());
+                for next in neighbours[node].iter().rev() {
+                    if !visited.contains(next) && !forward.get(node).is_some_and(|f| f.contains(next)) {
+                        stack.push(next);
+                    }
+                }
+                if let Some(next_nodes) = forward.get(node) {
+                    for next in next_nodes.iter().rev() {
+                        if !visited.contains(next) {
+                            stack.push(next);
+                        }
+                    }
+                }
+            }
+            regions.push(region);
+        }
+        regions
+    }
+
+    /// Compute a 2D force-directed layout (Fruchterman-Reingold) for the graph.
+    /// Nodes start evenly spaced on a circle so the result is deterministic,
+    /// and the number of iterations is capped for large graphs since each one is O(n^2).
+    pub fn layout(&self) -> HashMap<String, (f64, f64)> {
+        let nodes = self.nodes();
+        let n = nodes.len();
+        let mut positions: Vec<(f64, f64)> = (0..n)
+            .map(|i| {
+                let angle = 2.0 * std::f64::consts::PI * i as f64 / n as f64;
+                (angle.cos(), angle.sin())
+            })
+            .collect();
+        if n < 2 {
+            return nodes.into_iter().zip(positions).collect();
+        }
+
+        let index: HashMap<&String, usize> = nodes.iter().enumerate().map(|(i, node)| (node, i)).collect();
+        let mut links = Vec::new();
+        for (node, next_nodes) in &self.edges {
+            for next in next_nodes.keys() {
+                let (from, to) = (index[node], index[next]);
+                if from != to {
+                    links.push((from, to));
+                }
+            }
+        }
+
+        // Ideal edge length for a layout spread over a 2x2 square.
+        let ideal = (4.0 / n as f64).sqrt();
+        let iterations = if n > 1000 { 50 } else { 300 };
+        let mut temperature = 0.2;
+        let cooling = temperature / iterations as f64;
+
+        for _ in 0..iterations {
+            let mut displacement = vec![(0.0, 0.0); n];
+
+            // Repulsive forces between every pair of nodes.
+            for i in 0..n {
+                for j in (i + 1)..n {
+                    let dx = positions[i].0 - positions[j].0;
+                    let dy = positions[i].1 - positions[j].1;
+                    let distance = (dx * dx + dy * dy).sqrt().max(1e-9);
+                    let force = ideal * ideal / distance;
+                    displacement[i].0 += dx / distance * force;
+                    displacement[i].1 += dy / distance * force;
+                    displacement[j].0 -= dx / distance * force;
+                    displacement[j].1 -= dy / distance * force;
+                }
+            }
+
+            // Attractive forces along edges.
+            for &(from, to) in &links {
+                let dx = positions[from].0 - positions[to].0;
+                let dy = positions[from].1 - positions[to].1;
+                let distance = (dx * dx + dy * dy).sqrt().max(1e-9);
+                let force = distance * distance / ideal;
+                displacement[from].0 -= dx / distance * force;
+                displacement[from].1 -= dy / distance * force;
+                displacement[to].0 += dx / distance * force;
+                displacement[to].1 += dy / distance * force;
+            }
+
+            // Move each node, limited by the current temperature.
+            for (position, (dx, dy)) in positions.iter_mut().zip(displacement) {
+                let length = (dx * dx + dy * dy).sqrt().max(1e-9);
+                let step = length.min(temperature);
+                position.0 += dx / length * step;
+                position.1 += dy / length * step;
+            }
+            temperature -= cooling;
+        }
+
+        nodes.into_iter().zip(positions).collect()
+    }
+
+    /// Shortest walk (fewest edges) from the `start` k-mer to the `end` k-mer, found by BFS over
+    /// the graph and spelled out as a sequence beginning with `start` and ending with `end`.
+    /// None if either k-mer is not an edge of the graph or `end` is unreachable from `start`.
+    pub fn shortest_reconstruction(&self, start: &str, end: &str) -> Option<String> {
+        let successors = self.successors();
+        let has_edge = |kmer: &str| {
+            kmer.len() >= 2
+                && successors
+                    .get(&kmer[..kmer.len() - 1])
+                    .is_some_and(|next_nodes| next_nodes.iter().any(|next| next == &kmer[1..]))
+        };
+        if !has_edge(start) || !has_edge(end) {
+            return None;
+        }
+        if start == end {
+            return Some(start.to_string());
+        }
+
+        // BFS from the node that `start` leads into to the node that `end` leaves from.
+        let source = &start[1..];
+        let target = &end[..end.len() - 1];
+        let mut parents: HashMap<&str, &str> = HashMap::new();
+        let mut visited: HashSet<&str> = HashSet::from([source]);
+        let mut queue = VecDeque::from([source]);
+        while let Some(node) = queue.pop_front() {
+            if node == target {
+                break;
+            }
+            for next in successors.get(node).into_iter().flatten() {
+                if visited.insert(next) {
+                    parents.insert(next, node);
+                    queue.push_back(next);
+                }
+            }
+        }
+        if !visited.contains(target) {
+            return None;
+        }
+
+        let mut path = vec![target];
+        while let Some(&parent) = parents.get(path[path.len() - 1]) {
+            path.push(parent);
+        }
+        path.reverse();
+        let mut sequence = start.to_string();
+        for node in &path[1..] {
+            sequence.push_str(&node[node.len() - 1..]);
+        }
+        sequence.push_str(&end[end.len() - 1..]);
+        Some(sequence)
+    }
+
+    /// Reconstruct the sequence spelled by an Eulerian path that uses every edge (with
+    /// multiplicity) exactly once, walked with Hierholzer's algorithm. None if the degree balance
+    /// rules out such a path or the edges are not all reachable from the start node.
+    pub fn eulerian_path(&self) -> Option<String> {
+        let mut balance: HashMap<&str, i64> = HashMap::new();
+        let mut edge_count = 0;
+        for (node, next_nodes) in &self.edges {
+            for (next, &weight) in next_nodes {
+                *balance.entry(node.as_str()).or_insert(0) += weight as i64;
+                *balance.entry(next.as_str()).or_insert(0) -= weight as i64;
+                edge_count += weight;
+            }
+        }
+        if edge_count == 0 {
+            return None;
+        }
+        let starts: Vec<&str> = balance.iter().filter(|&(_, &b)| b == 1).map(|(&node, _)| node).collect();
+        let ends = balance.values().filter(|&&b| b == -1).count();
+        if balance.values().any(|b| b.abs() > 1) || starts.len() > 1 || starts.len() != ends {
+            return None;
+        }
+        let start = match starts.first() {
+            Some(&node) => node,
+            None => self.edges.iter().filter(|(_, next)| !next.is_empty()).map(|(node, _)| node.as_str()).min()?,
+        };
+
+        // Remaining out-edges per node, once per unit of weight, consumed from the back.
+        let mut remaining: HashMap<&str, Vec<&str>> = self
+            .edges
+            .iter()
+            .map(|(node, next_nodes)| {
+                let next = next_nodes.iter().rev().flat_map(|(next, &weight)| std::iter::repeat_n(next.as_str(), weight));
+                (node.as_str(), next.collect())
+            })
+            .collect();
+        let mut stack = vec![start];
+        let mut path = Vec::new();
+        while let Some(&node) = stack.last() {
+            match remaining.get_mut(node).and_then(|next| next.pop()) {
+                Some(next) => stack.push(next),
+                None => path.push(stack.pop()?),
+            }
+        }
+        if path.len() != edge_count + 1 {
+            return None;
+        }
+        path.reverse();
+        Some(spell_path(&path))
+    }
+
+    /// Assemble the graph: the single sequence spelled by an Eulerian path through every edge,
+    /// which reconstructs the original sequence when its k-mers are unambiguous, or else the
+    /// contigs of its maximal non-branching paths. A circular sequence gives an Eulerian circuit,
+    /// spelled starting and ending with the same (k-1)-mer.
+    pub fn assemble(&self) -> Vec<String> {
+        match self.eulerian_path() {
+            Some(sequence) => vec![sequence],
+            None => self.contigs(),
+        }
+    }
+
+    /// Collapse the graph into contigs: maximal non-branching paths (every interior node has
+    /// in-degree 1 and out-degree 1, derived from the out-edge lists), each merged into a string
+    /// by overlapping consecutive (k-1)-mers. Branching nodes start and end contigs.
+    pub fn contigs(&self) -> Vec<String> {
+        let mut edges: Vec<(String, String)> = self.edge_weights().into_keys().collect();
+        edges.sort();
+        maximal_non_branching_paths(&edges)
+            .iter()
+            .map(|path| spell_path(path))
+            .collect()
+    }
+
+    /// Walk contigs through branches by coverage, as greedy assemblers do: each contig starts
+    /// from the best-covered edge not used yet (ties go to the smallest) and is extended forwards,
+    /// then backwards, along the best-covered unused edge at every node, so an erroneous or
+    /// repeated branch doesn't end it the way it ends a unitig. Every edge is used once. Each
+    /// contig comes with its mean edge weight, in the order the contigs were started.
+    pub fn greedy_contigs(&self) -> Vec<(String, f64)> {
+        let predecessors = self.predecessors();
+        let mut edges: Vec<((String, String), usize)> = self.edge_weights().into_iter().collect();
+        edges.sort_by(|(a, weight_a), (b, weight_b)| weight_b.cmp(weight_a).then_with(|| a.cmp(b)));
+        // The best edge of a set of candidates: highest weight, then smallest node
+        let best = |candidates: Vec<(&String, usize)>| {
+            candidates
+                .into_iter()
+                .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+                .map(|(node, weight)| (node.clone(), weight))
+        };
+
+        let mut used: HashSet<(String, String)> = HashSet::new();
+        let mut contigs = Vec::new();
+        for (edge, weight) in edges {
+            if !used.insert(edge.clone()) {
+                continue;
+            }
+            let (node, next) = edge;
+            let mut path = VecDeque::from([node, next]);
+            let mut coverage = weight;
+            loop {
+                let current = path[path.len() - 1].clone();
+                let candidates = self.edges.get(&current).into_iter().flatten();
+                let unused = candidates.filter(|&(next, _)| !used.contains(&(current.clone(), next.clone())));
+                let Some((next, weight)) = best(unused.map(|(next, &weight)| (next, weight)).collect()) else {
+                    break;
+                };
+                used.insert((current, next.clone()));
+                coverage += weight;
+                path.push_back(next);
+            }
+            loop {
+                let current = path[0].clone();
+                let candidates = predecessors.get(&current).into_iter().flatten();
+                let unused = candidates.filter(|&previous| !used.contains(&(previous.clone(), current.clone())));
+                let Some((previous, weight)) = best(unused.map(|previous| (previous, self.edge_weight(previous, &current))).collect()) else {
+                    break;
+                };
+                used.insert((previous.clone(), current));
+                coverage += weight;
+                path.push_front(previous);
+            }
+            let path: Vec<String> = path.into();
+            contigs.push((spell_path(&path), coverage as f64 / (path.len() - 1) as f64));
+        }
+        contigs
+    }
+
+    /// Merge every maximal non-branching path into a single unitig node. The counts in the
+    /// report are of the unitig graph's segments and links.
+    pub fn collapse_unitigs(&self) -> (UnitigGraph, SimplificationReport) {
+        let weights = self.edge_weights();
+        let mut edges: Vec<(String, String)> = weights.keys().cloned().collect();
+        edges.sort();
+        let paths = maximal_non_branching_paths(&edges);
+
+        let segments = paths.iter().map(|path| spell_path(path)).collect();
+        let coverage = paths
+            .iter()
+            .map(|path| {
+                let total: usize = path.windows(2).map(|pair| weights[&(pair[0].clone(), pair[1].clone())]).sum();
+                total as f64 / (path.len() - 1) as f64
+            })
+            .collect();
+        // A unitig links to every unitig that starts at the (k-1)-mer it ends in.
+        let mut starts: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (i, path) in paths.iter().enumerate() {
+            starts.entry(path[0].as_str()).or_default().push(i);
+        }
+        let links = paths
+            .iter()
+            .enumerate()
+            .flat_map(|(i, path)| {
+                let next = starts.get(path[path.len() - 1].as_str()).map(Vec::as_slice).unwrap_or_default();
+                next.iter().map(move |&j| (i, j))
+            })
+            .collect();
+
+        let unitigs = UnitigGraph { k: self.k, segments, coverage, links };
+        let report = SimplificationReport {
+            nodes_before: self.node_count(),
+            edges_before: weights.len(),
+            nodes_after: unitigs.segments.len(),
+            edges_after: unitigs.links.len(),
+        };
+        (unitigs, report)
+    }
+
+    /// Print each node and its successors with their edge weights, one node per line in
+    /// lexicographic order.
+    pub fn display(&self) {
+        let mut nodes: Vec<(&String, &BTreeMap<String, usize>)> = self.edges.iter().collect();
+        nodes.sort_unstable();
+        // Iterate over each node and its adjacent nodes in the graph.
+        for (node, next_nodes) in nodes {
+            // Print the current node and its connected nodes.
+            println!("{} -> {:?}", node, next_nodes);
+        }
+    }
+}
+
+/// Node and edge counts of a graph before and after a simplification pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimplificationReport {
+    /// Distinct nodes before the pass.
+    pub nodes_before: usize,
+    /// Distinct edges before the pass.
+    pub edges_before: usize,
+    /// Distinct nodes after the pass.
+    pub nodes_after: usize,
+    /// Distinct edges after the pass.
+    pub edges_after: usize,
+}
+
+impl fmt::Display for SimplificationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "nodes {} -> {}, edges {} -> {}",
+            self.nodes_before, self.nodes_after, self.edges_before, self.edges_after
+        )
+    }
+}
+
+/// A De Bruijn graph with its non-branching paths merged: each segment is a unitig's sequence,
+/// and a link (from, to) means segment `to` starts with the (k-1)-mer segment `from` ends in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnitigGraph {
+    /// The k-mer size of the graph the unitigs came from; linked segments overlap by k-1 bases.
+    pub k: usize,
+    /// The unitig sequences.
+    pub segments: Vec<String>,
+    /// Mean edge weight along each unitig, i.e. its k-mer coverage.
+    pub coverage: Vec<f64>,
+    /// (from, to) indices into `segments`.
+    pub links: Vec<(usize, usize)>,
+}
+
+/// Spell the sequence of a walk through (k-1)-mer nodes: the first node, then the last base of
+/// each following node.
+pub fn spell_path<S: AsRef<str>>(path: &[S]) -> String {
+    let mut sequence = path.first().map(|node| node.as_ref().to_string()).unwrap_or_default();
+    for node in path.iter().skip(1).map(|node| node.as_ref()) {
+        sequence.push_str(&node[node.len() - 1..]);
+    }
+    sequence
+}
+
+/// Split a set of distinct directed edges into maximal non-branching paths.
+/// Paths start and end at nodes that don't have exactly one incoming and one outgoing edge;
+/// isolated cycles of 1-in-1-out nodes are returned starting from their smallest node.
+pub fn maximal_non_branching_paths(edges: &[(String, String)]) -> Vec<Vec<String>> {
+    let mut successors: HashMap<&str, Vec<&str>> = HashMap::new();
+    let mut degrees: HashMap<&str, (usize, usize)> = HashMap::new();
+    for (from, to) in edges {
+        successors.entry(from).or_default().push(to);
+        degrees.entry(from).or_insert((0, 0)).1 += 1;
+        degrees.entry(to).or_insert((0, 0)).0 += 1;
+    }
+    for next_nodes in successors.values_mut() {
+        next_nodes.sort();
+    }
+    let is_simple = |node: &str| degrees[node] == (1, 1);
+
+    let mut nodes: Vec<&str> = degrees.keys().copied().collect();
+    nodes.sort();
+
+    let mut paths = Vec::new();
+    let mut used = HashSet::new();
+    for &node in &nodes {
+        if is_simple(node) {
+            continue;
+        }
+        for &next in successors.get(node).map(|n| n.as_slice()).unwrap_or_default() {
+            let mut path = vec![node.to_string()];
+            let mut current = next;
+            used.insert((node, next));
+            path.push(current.to_string());
+            while is_simple(current) {
+                let following = successors[current][0];
+                used.insert((current, following));
+                current = following;
+                path.push(current.to_string());
+            }
+            paths.push(path);
+        }
+    }
+
+    // Whatever is left is made of isolated cycles.
+    for &node in &nodes {
+        let next = match successors.get(node) {
+            Some(next_nodes) if is_simple(node) && !used.contains(&(node, next_nodes[0])) => next_nodes[0],
+            _ => continue,
+        };
+        let mut path = vec![node.to_string()];
+        let mut current = node;
+        let mut following = next;
+        while used.insert((current, following)) {
+            path.push(following.to_string());
+            current = following;
+            following = successors[current][0];
+        }
+        paths.push(path);
+    }
+    paths
+}
+
+/// Whether a variant's path is only in the sample graph or only in the reference graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VariantKind {
+    /// Present in the sample but not the reference: a novel junction, insertion or translocation.
+    Novel,
+    /// Present in the reference but not the sample: a possible deletion.
+    Missing,
+}
+
+/// A path of edges found in only one of two compared graphs, with the flanking nodes
+/// shared by both graphs that anchor it (None when the path runs off the end of the graph).
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphVariant {
+    /// Which graph the path is found in.
+    pub kind: VariantKind,
+    /// The nodes of the differing path, in order.
+    pub path: Vec<String>,
+    /// Shared node the path leaves from.
+    pub left_flank: Option<String>,
+    /// Shared node the path rejoins.
+    pub right_flank: Option<String>,
+}
+
+/// Find structural differences between a reference and a sample De Bruijn graph.
+/// Edges found in only one graph are grouped into maximal non-branching paths, and each path is
+/// reported with the endpoints that also exist in the other graph as its flanks.
+pub fn compare_graphs_for_variants(ref_graph: &DeBruijnGraph, sample_graph: &DeBruijnGraph) -> Vec<GraphVariant> {
+    let mut variants = Vec::new();
+    for (kind, graph, other) in [
+        (VariantKind::Novel, sample_graph, ref_graph),
+        (VariantKind::Missing, ref_graph, sample_graph),
+    ] {
+        let other_edges = other.edge_weights();
+        let other_nodes: HashSet<String> = other.nodes().into_iter().collect();
+        let mut only_here: Vec<(String, String)> =
+            graph.edge_weights().into_keys().filter(|edge| !other_edges.contains_key(edge)).collect();
+        only_here.sort();
+
+        for path in maximal_non_branching_paths(&only_here) {
+            let flank = |node: Option<&String>| node.filter(|node| other_nodes.contains(*node)).cloned();
+            variants.push(GraphVariant {
+                kind,
+                left_flank: flank(path.first()),
+                right_flank: flank(path.last()),
+                path,
+            });
+        }
+    }
+    variants
+}
+
+/// The smallest k in the range whose De Bruijn graph of `seq` is a single simple path,
+/// the point at which assembly becomes trivial. Returns None if no k in the range works.
+pub fn min_assemblable_k(seq: &str, k_range: RangeInclusive<usize>) -> Option<usize> {
+    k_range
+        .filter(|&k| k >= 2 && k <= seq.len())
+        .find(|&k| DeBruijnGraph::new(&generate_kmers(seq, k)).is_simple_path())
+}
+
+/// Edge weights (coverage) along a path of nodes; missing edges have weight 0.
+pub fn path_coverage_profile(path: &[String], graph: &DeBruijnGraph) -> Vec<usize> {
+    let weights = graph.edge_weights();
+    path.windows(2)
+        .map(|step| *weights.get(&(step[0].clone(), step[1].clone())).unwrap_or(&0))
+        .collect()
+}
+
+/// Flag positions in a coverage profile where coverage jumps by more than `jump_ratio` relative
+/// to the previous edge, in either direction, which suggests a misassembly. Position i refers to
+/// the jump between `profile[i - 1]` and `profile[i]`; zero coverage is treated as 1 for the ratio.
+pub fn detect_misassembly(profile: &[usize], jump_ratio: f64) -> Vec<usize> {
+    (1..profile.len())
+        .filter(|&i| {
+            let (low, high) = if profile[i] < profile[i - 1] {
+                (profile[i], profile[i - 1])
+            } else {
+                (profile[i - 1], profile[i])
+            };
+            high as f64 / low.max(1) as f64 > jump_ratio
+        })
+        .collect()
+}
+
+/// Build a consensus De Bruijn graph keeping only the edges found in at least `min_support`
+/// of the input graphs, so nodes and edges that only some assemblies produce drop out.
+/// Each surviving edge is kept once, with the smallest multiplicity seen among the graphs containing it.
+pub fn consensus_graph(graphs: &[DeBruijnGraph], min_support: usize) -> DeBruijnGraph {
+    let mut support: HashMap<(String, String), (usize, usize)> = HashMap::new();
+    for graph in graphs {
+        for (edge, weight) in graph.edge_weights() {
+            let entry = support.entry(edge).or_insert((0, usize::MAX));
+            entry.0 += 1;
+            entry.1 = entry.1.min(weight);
+        }
+    }
+
+    let k = graphs.first().map_or(0, |graph| graph.k);
+    let mut consensus = DeBruijnGraph { edges: HashMap::new(), k, samples: Vec::new(), colors: HashMap::new() };
+    for ((node, next), (count, weight)) in support {
+        if count >= min_support.max(1) {
+            consensus.add_edge(node, next, weight);
+        }
+    }
+    consensus
+}
+
+/// Convert to a directed petgraph graph: each (k-1)-mer node points at its successor, and
+/// repeated k-mers give one edge weighted by multiplicity instead of parallel edges.
+pub fn create_petgraph(de_bruijn_graph: &DeBruijnGraph) -> DiGraph<String, usize> {
+    let mut graph = DiGraph::<String, usize>::new();
+
+    let mut index_map = std::collections::HashMap::new();
+
+    let mut edges: Vec<((String, String), usize)> = de_bruijn_graph.edge_weights().into_iter().collect();
+    edges.sort();
+    for ((node, next), weight) in edges {
+        let node_index = *index_map.entry(node.clone()).or_insert_with(|| graph.add_node(node));
+        let next_index = *index_map.entry(next.clone()).or_insert_with(|| graph.add_node(next));
+        graph.add_edge(node_index, next_index, weight);
+    }
+
+    graph
+}
+
+/// Structural summary of a graph, for judging a choice of k at a glance: a k that is too small
+/// tangles the graph into few components with many branching nodes, one that is too large
+/// shatters it into many short components.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphMetrics {
+    /// Number of nodes.
+    pub nodes: usize,
+    /// Number of distinct edges.
+    pub edges: usize,
+    /// Number of nodes with each in-degree.
+    pub in_degrees: BTreeMap<usize, usize>,
+    /// Number of nodes with each out-degree.
+    pub out_degrees: BTreeMap<usize, usize>,
+    /// Number of weakly connected components.
+    pub components: usize,
+    /// Nodes with in-degree > 1 or out-degree > 1.
+    pub branching_nodes: usize,
+    /// Edges along the longest simple path found; see graph_metrics.
+    pub longest_path: usize,
+    /// Whether the graph has no cycles, in which case longest_path is exact.
+    pub acyclic: bool,
+}
+
+impl fmt::Display for GraphMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let distribution = |degrees: &BTreeMap<usize, usize>| {
+            degrees.iter().map(|(degree, nodes)| format!("{}:{}", degree, nodes)).collect::<Vec<_>>().join(" ")
+        };
+        writeln!(f, "Nodes: {}", self.nodes)?;
+        writeln!(f, "Edges: {}", self.edges)?;
+        writeln!(f, "In-degrees (degree:nodes): {}", distribution(&self.in_degrees))?;
+        writeln!(f, "Out-degrees (degree:nodes): {}", distribution(&self.out_degrees))?;
+        writeln!(f, "Weakly connected components: {}", self.components)?;
+        writeln!(f, "Branching nodes: {}", self.branching_nodes)?;
+        let bound = if self.acyclic { "" } else { " (at least; the graph has cycles)" };
+        write!(f, "Longest simple path: {} edges{}", self.longest_path, bound)
+    }
+}
+
+/// Analyse a graph built by create_petgraph. The longest simple path is NP-hard to find once
+/// there are cycles, so it is computed on the acyclic graph left after dropping the back edges
+/// of a depth-first search: exact when the graph is acyclic, and otherwise the length of a real
+/// simple path that bounds the longest one from below.
+pub fn graph_metrics<N, E>(graph: &DiGraph<N, E>) -> GraphMetrics {
+    let mut in_degrees = BTreeMap::new();
+    let mut out_degrees = BTreeMap::new();
+    let mut branching_nodes = 0;
+    for node in graph.node_indices() {
+        let in_degree = graph.neighbors_directed(node, Direction::Incoming).count();
+        let out_degree = graph.neighbors_directed(node, Direction::Outgoing).count();
+        *in_degrees.entry(in_degree).or_insert(0) += 1;
+        *out_degrees.entry(out_degree).or_insert(0) += 1;
+        if in_degree > 1 || out_degree > 1 {
+            branching_nodes += 1;
+        }
+    }
+
+    let mut back_edges = HashSet::new();
+    depth_first_search(graph, graph.node_indices(), |event| {
+        if let DfsEvent::BackEdge(from, to) = event {
+            back_edges.insert((from, to));
+        }
+    });
+    let dag = graph.filter_map(|_, _| Some(()), |edge, _| {
+        let (from, to) = graph.edge_endpoints(edge)?;
+        (!back_edges.contains(&(from, to))).then_some(())
+    });
+    // Longest path ending at each node, in topological order
+    let order = toposort(&dag, None).unwrap_or_else(|_| unreachable!("dropping back edges leaves a DAG"));
+    let mut longest = vec![0; dag.node_count()];
+    for node in order {
+        for edge in dag.edges(node) {
+            longest[edge.target().index()] = longest[edge.target().index()].max(longest[node.index()] + 1);
+        }
+    }
+
+    GraphMetrics {
+        nodes: graph.node_count(),
+        edges: graph.edge_count(),
+        in_degrees,
+        out_degrees,
+        components: connected_components(graph),
+        branching_nodes,
+        longest_path: longest.into_iter().max().unwrap_or(0),
+        acyclic: back_edges.is_empty(),
+    }
+}
@@ -0,0 +1,364 @@
+//! Exact-match and static k-mer indexes: suffix arrays, the BWT and FM-index, and minimal
+//! perfect hashing.
+
+use std::collections::HashMap;
+use crate::kmer::seeded_hash;
+
+/// Build the suffix array of `text` by prefix doubling: suffixes are ranked by their
+/// first 2^i characters, doubling i until every rank is distinct, in O(n log^2 n).
+pub fn suffix_array(text: &[u8]) -> Vec<usize> {
+    let n = text.len();
+    let mut sa: Vec<usize> = (0..n).collect();
+    let mut rank: Vec<usize> = text.iter().map(|&b| b as usize).collect();
+    let mut next_rank = vec![0; n];
+    let mut width = 1;
+    if n <= 1 {
+        return sa;
+    }
+    loop {
+        // Sort key: rank of the first half, then rank of the second half (shorter suffixes first).
+        let key = |i: usize| (rank[i], if i + width < n { rank[i + width] + 1 } else { 0 });
+        sa.sort_by_key(|&i| key(i));
+        next_rank[sa[0]] = 0;
+        for pair in 1..n {
+            let bump = usize::from(key(sa[pair - 1]) != key(sa[pair]));
+            next_rank[sa[pair]] = next_rank[sa[pair - 1]] + bump;
+        }
+        std::mem::swap(&mut rank, &mut next_rank);
+        if rank[sa[n - 1]] == n - 1 {
+            break;
+        }
+        width *= 2;
+    }
+    sa
+}
+
+/// Compute the Burrows-Wheeler transform of `seq` with a '$' terminator appended.
+/// Returns the transformed string (including the '$') and the row of the sorted
+/// rotation matrix holding the original sequence, which `inverse_bwt` starts from.
+pub fn bwt(seq: &str) -> (String, usize) {
+    let mut text = seq.as_bytes().to_vec();
+    text.push(b'$');
+    let sa = suffix_array(&text);
+
+    let n = text.len();
+    let transformed = sa.iter().map(|&i| text[(i + n - 1) % n] as char).collect();
+    let index = sa.iter().position(|&i| i == 0).unwrap_or(0);
+    (transformed, index)
+}
+
+/// Recover the original sequence from its Burrows-Wheeler transform by walking the
+/// last-to-first mapping backwards from the original rotation's row.
+pub fn inverse_bwt(transformed: &str, index: usize) -> String {
+    let last: Vec<u8> = transformed.bytes().collect();
+    let n = last.len();
+    if n <= 1 {
+        return String::new();
+    }
+
+    // The first column is the sorted last column; ties keep their relative order.
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by_key(|&i| (last[i], i));
+    let mut lf = vec![0; n];
+    for (row, &i) in order.iter().enumerate() {
+        lf[i] = row;
+    }
+
+    // The original rotation ends in '$'; one step back is the rotation ending in its last base.
+    let mut reversed = Vec::with_capacity(n - 1);
+    let mut row = lf[index];
+    for _ in 0..n - 1 {
+        reversed.push(last[row]);
+        row = lf[row];
+    }
+    reversed.reverse();
+    String::from_utf8(reversed).unwrap_or_default()
+}
+
+/// Spacing of rank checkpoints and suffix array samples in the FM-index.
+pub const FM_INDEX_SAMPLE_RATE: usize = 32;
+
+/// FM-index for exact substring search: the BWT of the text (with a '$' terminator), the C array
+/// of counts of smaller characters, rank checkpoints every FM_INDEX_SAMPLE_RATE rows, and suffix
+/// array entries sampled at text positions that are multiples of FM_INDEX_SAMPLE_RATE.
+pub struct FmIndex {
+    bwt: Vec<u8>,
+    // Index into `counts`/`checkpoints` rows for each byte value, or None if absent from the text.
+    symbols: [Option<usize>; 256],
+    // counts[c] = number of text characters smaller than symbol c.
+    counts: Vec<usize>,
+    // checkpoints[i][c] = occurrences of symbol c in bwt[..i * FM_INDEX_SAMPLE_RATE].
+    checkpoints: Vec<Vec<usize>>,
+    // Sorted-suffix row -> text position, for sampled rows only.
+    sampled_positions: HashMap<usize, usize>,
+}
+
+impl FmIndex {
+    /// Build the index of `seq`, which must not contain '$'.
+    pub fn build(seq: &str) -> Self {
+        let mut text = seq.as_bytes().to_vec();
+        text.push(b'$');
+        let sa = suffix_array(&text);
+        let n = text.len();
+        let bwt: Vec<u8> = sa.iter().map(|&i| text[(i + n - 1) % n]).collect();
+
+        let mut alphabet: Vec<u8> = text.clone();
+        alphabet.sort_unstable();
+        alphabet.dedup();
+        let mut symbols = [None; 256];
+        for (index, &byte) in alphabet.iter().enumerate() {
+            symbols[byte as usize] = Some(index);
+        }
+
+        let mut counts = vec![0; alphabet.len()];
+        for &byte in &text {
+            if let Some(index) = symbols[byte as usize] {
+                counts[index] += 1;
+            }
+        }
+        let mut smaller = 0;
+        for count in counts.iter_mut() {
+            let here = *count;
+            *count = smaller;
+            smaller += here;
+        }
+
+        let mut checkpoints = Vec::with_capacity(n / FM_INDEX_SAMPLE_RATE + 1);
+        let mut running = vec![0; alphabet.len()];
+        for (row, &byte) in bwt.iter().enumerate() {
+            if row % FM_INDEX_SAMPLE_RATE == 0 {
+                checkpoints.push(running.clone());
+            }
+            if let Some(index) = symbols[byte as usize] {
+                running[index] += 1;
+            }
+        }
+        checkpoints.push(running);
+
+        let sampled_positions = sa
+            .iter()
+            .enumerate()
+            .filter(|&(_, &position)| position % FM_INDEX_SAMPLE_RATE == 0)
+            .map(|(row, &position)| (row, position))
+            .collect();
+
+        FmIndex { bwt, symbols, counts, checkpoints, sampled_positions }
+    }
+
+    /// Occurrences of symbol index `c` in bwt[..row].
+    pub fn rank(&self, c: usize, row: usize) -> usize {
+        let checkpoint = row / FM_INDEX_SAMPLE_RATE;
+        let start = checkpoint * FM_INDEX_SAMPLE_RATE;
+        let scanned = self.bwt[start..row]
+            .iter()
+            .filter(|&&byte| self.symbols[byte as usize] == Some(c))
+            .count();
+        self.checkpoints[checkpoint][c] + scanned
+    }
+
+    /// Backward search for the range of sorted-suffix rows starting with `pattern`.
+    pub fn search(&self, pattern: &str) -> (usize, usize) {
+        let (mut low, mut high) = (0, self.bwt.len());
+        for byte in pattern.bytes().rev() {
+            let Some(c) = self.symbols[byte as usize] else {
+                return (0, 0);
+            };
+            low = self.counts[c] + self.rank(c, low);
+            high = self.counts[c] + self.rank(c, high);
+            if low >= high {
+                return (0, 0);
+            }
+        }
+        (low, high)
+    }
+
+    /// Number of occurrences of `pattern` in the text, in O(|pattern|) rank queries.
+    pub fn count(&self, pattern: &str) -> usize {
+        let (low, high) = self.search(pattern);
+        high - low
+    }
+
+    /// Start positions of every occurrence of `pattern`, sorted ascending. Each row is walked
+    /// backwards with the last-to-first mapping until it reaches a sampled suffix array entry.
+    pub fn locate(&self, pattern: &str) -> Vec<usize> {
+        let (low, high) = self.search(pattern);
+        let mut positions: Vec<usize> = (low..high)
+            .map(|mut row| {
+                let mut steps = 0;
+                loop {
+                    if let Some(&position) = self.sampled_positions.get(&row) {
+                        return position + steps;
+                    }
+                    let c = self.symbols[self.bwt[row] as usize].unwrap();
+                    row = self.counts[c] + self.rank(c, row);
+                    steps += 1;
+                }
+            })
+            .collect();
+        positions.sort_unstable();
+        positions
+    }
+}
+
+/// Bit-array size per key at each level of the minimal perfect hash; larger is faster to build
+/// but uses more space.
+pub const MPHF_GAMMA: f64 = 2.0;
+
+/// Levels tried before the remaining colliding keys are stored explicitly.
+pub const MPHF_MAX_LEVELS: u64 = 32;
+
+/// BBHash-style minimal perfect hash over a fixed k-mer set. At each level every remaining key
+/// hashes into a bit array; keys landing alone keep their bit and the rest move to the next
+/// level. A key's index is the number of set bits before its own across all levels, so members
+/// map to distinct values in [0, n) without storing the keys. A k-mer outside the set may still
+/// map to some index.
+pub struct MphfIndex {
+    // Bit array words of every level, concatenated.
+    bits: Vec<u64>,
+    // (first word, word count) of each level within `bits`.
+    levels: Vec<(usize, usize)>,
+    // ranks[i] = set bits in bits[..i].
+    ranks: Vec<u32>,
+    // Keys that still collided after MPHF_MAX_LEVELS levels, indexed after all bit-array keys.
+    fallback: HashMap<String, usize>,
+}
+
+impl MphfIndex {
+    /// Build the hash over the distinct k-mers in `kmers`.
+    pub fn build(kmers: &[String]) -> Self {
+        let mut remaining: Vec<&str> = kmers.iter().map(String::as_str).collect();
+        remaining.sort_unstable();
+        remaining.dedup();
+        let mut bits = Vec::new();
+        let mut levels = Vec::new();
+        let mut level = 0;
+        while !remaining.is_empty() && level < MPHF_MAX_LEVELS {
+            let level_bits = ((remaining.len() as f64 * MPHF_GAMMA).ceil() as usize).max(64);
+            let words = level_bits.div_ceil(64);
+            let mut seen = vec![0u64; words];
+            let mut collided = vec![0u64; words];
+            for kmer in &remaining {
+                let bit = (seeded_hash(kmer, level) % (words as u64 * 64)) as usize;
+                let mask = 1 << (bit % 64);
+                if seen[bit / 64] & mask != 0 {
+                    collided[bit / 64] |= mask;
+                }
+                seen[bit / 64] |= mask;
+            }
+            for (word, collision) in seen.iter_mut().zip(&collided) {
+                *word &= !collision;
+            }
+            remaining.retain(|kmer| {
+                let bit = (seeded_hash(kmer, level) % (words as u64 * 64)) as usize;
+                seen[bit / 64] & (1 << (bit % 64)) == 0
+            });
+            levels.push((bits.len(), words));
+            bits.extend(seen);
+            level += 1;
+        }
+
+        let mut ranks = Vec::with_capacity(bits.len() + 1);
+        let mut total = 0;
+        for word in &bits {
+            ranks.push(total);
+            total += word.count_ones();
+        }
+        ranks.push(total);
+        let fallback = remaining
+            .into_iter()
+            .enumerate()
+            .map(|(i, kmer)| (kmer.to_string(), total as usize + i))
+            .collect();
+        MphfIndex { bits, levels, ranks, fallback }
+    }
+
+    /// The k-mer's index in [0, len), or None if it hashes to no set bit. Only meaningful for
+    /// k-mers in the set the index was built from.
+    pub fn lookup(&self, kmer: &str) -> Option<usize> {
+        for (level, &(first_word, words)) in self.levels.iter().enumerate() {
+            let bit = (seeded_hash(kmer, level as u64) % (words as u64 * 64)) as usize;
+            let word = first_word + bit / 64;
+            let offset = bit % 64;
+            if self.bits[word] & (1 << offset) != 0 {
+                let below = self.bits[word] & ((1u64 << offset) - 1);
+                return Some(self.ranks[word] as usize + below.count_ones() as usize);
+            }
+        }
+        self.fallback.get(kmer).copied()
+    }
+
+    /// Number of distinct k-mers indexed.
+    pub fn len(&self) -> usize {
+        self.ranks.last().copied().unwrap_or(0) as usize + self.fallback.len()
+    }
+
+    /// Whether the index holds no k-mers.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Approximate heap size of the index in bytes.
+    pub fn size_in_bytes(&self) -> usize {
+        self.bits.len() * 8
+            + self.ranks.len() * 4
+            + self.levels.len() * 16
+            + self.fallback.keys().map(|kmer| kmer.len() + 32).sum::<usize>()
+    }
+}
+
+/// Where a k-mer occurs: which of the indexed sequences, and the offset within it, both 0-based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    /// Index of the sequence in the slice the index was built from.
+    pub sequence: usize,
+    /// Offset of the k-mer's first base within that sequence.
+    pub offset: usize,
+}
+
+/// Positional k-mer index over a set of sequences: every occurrence of every k-mer, in sequence
+/// order. K-mers spanning a base outside A/C/G/T are left out, as in counting.
+#[derive(Debug, Clone, Default)]
+pub struct KmerIndex {
+    k: usize,
+    positions: HashMap<String, Vec<Position>>,
+}
+
+impl KmerIndex {
+    /// Index the k-mers of each (sanitized) sequence.
+    pub fn build(sequences: &[String], k: usize) -> Self {
+        let mut positions: HashMap<String, Vec<Position>> = HashMap::new();
+        for (sequence, dna_sequence) in sequences.iter().enumerate() {
+            if k == 0 || k > dna_sequence.len() {
+                continue;
+            }
+            for offset in 0..=dna_sequence.len() - k {
+                let kmer = &dna_sequence[offset..offset + k];
+                if kmer.bytes().all(|base| matches!(base, b'A' | b'C' | b'G' | b'T')) {
+                    positions.entry(kmer.to_string()).or_default().push(Position { sequence, offset });
+                }
+            }
+        }
+        KmerIndex { k, positions }
+    }
+
+    /// The k-mer size the index was built with.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Every occurrence of `kmer` (case-insensitively), by sequence and then offset; empty if it
+    /// never occurs or isn't k bases long.
+    pub fn locate(&self, kmer: &str) -> Vec<Position> {
+        self.positions.get(&kmer.to_ascii_uppercase()).cloned().unwrap_or_default()
+    }
+
+    /// Number of distinct k-mers indexed.
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Whether the index holds no k-mers.
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+}
@@ -0,0 +1,184 @@
+//! Batch runs over many samples: finding them in a directory, a file-name glob or a TSV
+//! manifest, and writing the summary table that compares them.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::io::BufRead;
+use csv::Writer;
+use crate::error::KmerError;
+use super::gzip::{open_input, OutputFile};
+
+/// File extensions, after any `.gz`, that a directory's sequence files are recognised by.
+pub const SEQUENCE_EXTENSIONS: [&str; 8] = ["fa", "fasta", "fna", "ffn", "fq", "fastq", "seq", "txt"];
+
+/// One input of a batch run: a name for its outputs and the sequence file it is read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchSample {
+    /// Sample name, which names its output subdirectory.
+    pub name: String,
+    /// Sequence file (FASTA, FASTQ or raw text, gzipped or not).
+    pub path: PathBuf,
+}
+
+impl BatchSample {
+    /// A sample named after its file: the file name without `.gz` and a sequence extension.
+    pub fn from_path(path: PathBuf) -> Self {
+        let file_name = path.file_name().map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+        let name = sequence_stem(&file_name).unwrap_or_else(|| file_name.strip_suffix(".gz").unwrap_or(&file_name));
+        BatchSample { name: name.to_string(), path }
+    }
+}
+
+/// The samples of a directory, every file in it with a sequence extension, or of a glob such as
+/// `reads/*.fa`, whose last path component may hold `*` (any run of characters) and `?` (any one
+/// character). Hidden files and subdirectories are left out; samples come in file name order.
+/// Errors if nothing matches or two files would give the same sample name.
+pub fn batch_samples(pattern: &str) -> Result<Vec<BatchSample>, KmerError> {
+    let path = Path::new(pattern);
+    let (dir, file_pattern) = if path.is_dir() {
+        (path, None)
+    } else {
+        let file_pattern = path.file_name().map(|name| name.to_string_lossy().into_owned());
+        (path.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new(".")), file_pattern)
+    };
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        if file_name.starts_with('.') || !entry.file_type()?.is_file() {
+            continue;
+        }
+        let matches = match &file_pattern {
+            Some(file_pattern) => glob_match(file_pattern, &file_name),
+            None => sequence_stem(&file_name).is_some(),
+        };
+        if matches {
+            paths.push(entry.path());
+        }
+    }
+    if paths.is_empty() {
+        return Err(KmerError::InvalidBatch(format!("no sequence files match {}", pattern)));
+    }
+    paths.sort();
+    unique_names(paths.into_iter().map(BatchSample::from_path).collect())
+}
+
+/// The samples of a tab-separated manifest (gzipped if its name ends in `.gz`): one sample per
+/// line, as a path or as a name and a path. Relative paths are taken from the manifest's
+/// directory. Blank lines, `#` comments and a header line starting with `sample` are skipped.
+pub fn read_batch_manifest(file_name: &str) -> Result<Vec<BatchSample>, KmerError> {
+    let base = Path::new(file_name).parent().unwrap_or(Path::new(""));
+    let mut samples = Vec::new();
+    for (i, line) in open_input(file_name)?.lines().enumerate() {
+        let line = line?;
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.starts_with('#') || (i == 0 && line.to_ascii_lowercase().starts_with("sample")) {
+            continue;
+        }
+        let fields: Vec<&str> = line.split('\t').map(str::trim).collect();
+        let sample = match fields[..] {
+            [path] if !path.is_empty() => BatchSample::from_path(base.join(path)),
+            [name, path] if !name.is_empty() && !path.is_empty() => BatchSample { name: name.to_string(), path: base.join(path) },
+            _ => {
+                return Err(KmerError::InvalidBatch(format!(
+                    "line {} of {} should be a path, or a name and a path separated by a tab",
+                    i + 1,
+                    file_name
+                )))
+            }
+        };
+        samples.push(sample);
+    }
+    if samples.is_empty() {
+        return Err(KmerError::InvalidBatch(format!("{} lists no samples", file_name)));
+    }
+    unique_names(samples)
+}
+
+/// Whether a file name matches a glob of `*` (any run of characters, none included) and `?`
+/// (exactly one character); every other character matches itself.
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` and the name position it was tried at, to backtrack to
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            // Let the `*` swallow one more character
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+// A file name without its sequence extension and any .gz after it, or None if it has none
+fn sequence_stem(file_name: &str) -> Option<&str> {
+    let name = file_name.strip_suffix(".gz").unwrap_or(file_name);
+    let (stem, extension) = name.rsplit_once('.')?;
+    Some(stem).filter(|stem| !stem.is_empty() && SEQUENCE_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()))
+}
+
+// The samples as they are, unless two share a name and so an output directory
+fn unique_names(samples: Vec<BatchSample>) -> Result<Vec<BatchSample>, KmerError> {
+    let mut seen = HashSet::new();
+    if let Some(sample) = samples.iter().find(|sample| !seen.insert(sample.name.as_str())) {
+        return Err(KmerError::InvalidBatch(format!("two samples are named {}", sample.name)));
+    }
+    Ok(samples)
+}
+
+/// One row of a batch run's summary: the sample's sequence and k-mer count sizes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SampleSummary {
+    /// Sample name.
+    pub sample: String,
+    /// Sequence file the sample was read from.
+    pub input: PathBuf,
+    /// Number of records read.
+    pub records: usize,
+    /// Total length of the records, in bases.
+    pub length: usize,
+    /// Fraction of G and C bases, between 0 and 1.
+    pub gc_content: f64,
+    /// Number of different k-mers.
+    pub distinct: usize,
+    /// Number of k-mers seen exactly once.
+    pub unique: usize,
+    /// Number of k-mer occurrences.
+    pub total: usize,
+}
+
+/// Function to write one row per sample of a batch run to a CSV file
+pub fn write_batch_summary_to_csv(summaries: &[SampleSummary], file_name: &str) -> Result<(), KmerError> {
+    let mut wtr = Writer::from_writer(OutputFile::create(file_name)?);
+
+    wtr.write_record(["Sample", "Input", "Records", "Length", "GCContent", "Distinct", "Unique", "Total"])?;
+    for summary in summaries {
+        wtr.write_record([
+            summary.sample.clone(),
+            summary.input.to_string_lossy().into_owned(),
+            summary.records.to_string(),
+            summary.length.to_string(),
+            format!("{:.4}", summary.gc_content),
+            summary.distinct.to_string(),
+            summary.unique.to_string(),
+            summary.total.to_string(),
+        ])?;
+    }
+
+    wtr.into_inner()?.finish()?;
+    Ok(())
+}
+
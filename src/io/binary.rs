@@ -0,0 +1,174 @@
+//! Compact binary save/load of count tables and De Bruijn graphs, so later steps can reuse
+//! them instead of counting again. Both are written through OutputFile and read through
+//! open_input, so a `.gz` name compresses them too.
+//!
+//! File layout: the magic bytes "KMERIDX1", a kind byte (`C` for counts, `W` for a graph), k and
+//! the number of entries as little-endian u64s, then the entries. Sequences are packed four
+//! bases to a byte (2 bits per base, A=0 C=1 G=2 T=3) and counts are LEB128 varints. A count
+//! entry is a packed k-mer and its count; a graph entry is a packed (k-1)-mer node, its number
+//! of distinct successors and each packed successor followed by its edge weight. Graphs saved
+//! before edge weights were stored (kind `G`, each successor repeated once per unit of weight)
+//! still load.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, BufRead, Read, Write};
+use crate::graph::DeBruijnGraph;
+use crate::kmer::base_index;
+use super::gzip::{open_input, OutputFile};
+
+/// Magic bytes at the start of every saved index file.
+pub const INDEX_MAGIC: &[u8; 8] = b"KMERIDX1";
+
+/// What a saved index file holds; see load_index.
+#[derive(Debug, Clone)]
+pub enum SavedIndex {
+    /// A k-mer count table and its k.
+    Counts {
+        /// K-mer size.
+        k: usize,
+        /// Count of each k-mer.
+        counts: HashMap<String, usize>,
+    },
+    /// A De Bruijn graph.
+    Graph(DeBruijnGraph),
+}
+
+/// Save a k-mer count table counted with k-mer size `k`. Every k-mer must be k bases of ACGT.
+pub fn save_kmer_counts(kmer_counts: &HashMap<String, usize>, k: usize, file_name: &str) -> io::Result<()> {
+    let mut file = OutputFile::create(file_name)?;
+    write_header(&mut file, b'C', k, kmer_counts.len())?;
+    let mut entries: Vec<(&String, &usize)> = kmer_counts.iter().collect();
+    entries.sort();
+    for (kmer, &count) in entries {
+        write_bases(&mut file, kmer, k)?;
+        write_varint(&mut file, count as u64)?;
+    }
+    file.finish()
+}
+
+/// Save a De Bruijn graph with its edge weights. Every node must be k-1 bases of ACGT. The
+/// sample colors of a colored graph are not saved.
+pub fn save_graph(graph: &DeBruijnGraph, file_name: &str) -> io::Result<()> {
+    let mut file = OutputFile::create(file_name)?;
+    write_header(&mut file, b'W', graph.k, graph.edges.len())?;
+    let node_len = graph.k.saturating_sub(1);
+    let mut nodes: Vec<(&String, &BTreeMap<String, usize>)> = graph.edges.iter().collect();
+    nodes.sort();
+    for (node, successors) in nodes {
+        write_bases(&mut file, node, node_len)?;
+        write_varint(&mut file, successors.len() as u64)?;
+        for (successor, &weight) in successors {
+            write_bases(&mut file, successor, node_len)?;
+            write_varint(&mut file, weight as u64)?;
+        }
+    }
+    file.finish()
+}
+
+/// Whether the file starts like a saved index, so callers can tell one from a sequence file.
+pub fn is_saved_index(file_name: &str) -> bool {
+    let mut magic = [0u8; 8];
+    open_input(file_name).and_then(|mut file| file.read_exact(&mut magic)).is_ok() && &magic == INDEX_MAGIC
+}
+
+/// Load a file written by save_kmer_counts or save_graph.
+pub fn load_index(file_name: &str) -> io::Result<SavedIndex> {
+    let mut file = open_input(file_name)?;
+    let mut magic = [0u8; 8];
+    file.read_exact(&mut magic).map_err(|_| invalid_index())?;
+    if &magic != INDEX_MAGIC {
+        return Err(invalid_index());
+    }
+    let mut kind = [0u8; 1];
+    file.read_exact(&mut kind)?;
+    let k = read_u64(&mut file)? as usize;
+    let entries = read_u64(&mut file)?;
+
+    match kind[0] {
+        b'C' => {
+            let mut counts = HashMap::new();
+            for _ in 0..entries {
+                let kmer = read_bases(&mut file, k)?;
+                counts.insert(kmer, read_varint(&mut file)? as usize);
+            }
+            Ok(SavedIndex::Counts { k, counts })
+        }
+        kind @ (b'W' | b'G') => {
+            let node_len = k.saturating_sub(1);
+            let mut graph = DeBruijnGraph { edges: HashMap::new(), k, samples: Vec::new(), colors: HashMap::new() };
+            for _ in 0..entries {
+                let node = read_bases(&mut file, node_len)?;
+                for _ in 0..read_varint(&mut file)? {
+                    let successor = read_bases(&mut file, node_len)?;
+                    let weight = if kind == b'W' { read_varint(&mut file)? as usize } else { 1 };
+                    graph.add_edge(node.clone(), successor, weight);
+                }
+            }
+            Ok(SavedIndex::Graph(graph))
+        }
+        _ => Err(invalid_index()),
+    }
+}
+
+fn invalid_index() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "not a saved k-mer index file")
+}
+
+fn write_header(file: &mut impl Write, kind: u8, k: usize, entries: usize) -> io::Result<()> {
+    file.write_all(INDEX_MAGIC)?;
+    file.write_all(&[kind])?;
+    file.write_all(&(k as u64).to_le_bytes())?;
+    file.write_all(&(entries as u64).to_le_bytes())
+}
+
+fn read_u64(file: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    file.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+// Pack `len` ACGT bases four to a byte, the first base in the lowest bits
+fn write_bases(file: &mut impl Write, sequence: &str, len: usize) -> io::Result<()> {
+    if sequence.len() != len {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("'{}' is not {} bases long", sequence, len)));
+    }
+    let mut packed = vec![0u8; len.div_ceil(4)];
+    for (i, base) in sequence.bytes().enumerate() {
+        let Some(code) = base_index(base) else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("'{}' has a base other than ACGT", sequence)));
+        };
+        packed[i / 4] |= (code as u8) << (2 * (i % 4));
+    }
+    file.write_all(&packed)
+}
+
+fn read_bases(file: &mut impl BufRead, len: usize) -> io::Result<String> {
+    let mut packed = vec![0u8; len.div_ceil(4)];
+    file.read_exact(&mut packed)?;
+    Ok((0..len).map(|i| b"ACGT"[((packed[i / 4] >> (2 * (i % 4))) & 3) as usize] as char).collect())
+}
+
+// LEB128: seven bits per byte, lowest first, with the high bit set on all but the last byte
+fn write_varint(file: &mut impl Write, mut value: u64) -> io::Result<()> {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            return file.write_all(&[byte]);
+        }
+        file.write_all(&[byte | 0x80])?;
+    }
+}
+
+fn read_varint(file: &mut impl Read) -> io::Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0u8; 1];
+        file.read_exact(&mut byte)?;
+        value |= ((byte[0] & 0x7f) as u64) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid_index())
+}
@@ -0,0 +1,217 @@
+//! Counting k-mers through temporary files, for inputs whose k-mers don't fit in memory. A first
+//! pass scatters the 2-bit code of every k-mer into bucket files by a prefix of its hash, so each
+//! k-mer lands in exactly one bucket; a second pass sorts and counts each bucket on its own,
+//! splitting again any bucket larger than the memory limit, and the counted runs are merged into
+//! one stream in k-mer order.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::error::KmerError;
+use super::for_each_packed_kmer;
+use super::gzip::is_gzip_name;
+
+/// Most bucket files one pass writes at once; each holds a DISK_BUCKET_BUFFER write buffer.
+pub const MAX_DISK_BUCKETS: usize = 256;
+
+/// Write buffer of each bucket file, in bytes.
+pub const DISK_BUCKET_BUFFER: usize = 1 << 16;
+
+/// How many times a bucket that is still too large is split again before it is sorted anyway,
+/// as happens when a single k-mer alone outgrows the memory limit.
+pub const MAX_DISK_SPLITS: u32 = 4;
+
+/// Where count_kmers_on_disk puts its temporary files and how much memory it may use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskCountOptions {
+    /// Directory for the temporary files: a fresh subdirectory is made in it and removed again.
+    pub tmp_dir: PathBuf,
+    /// Bytes of k-mer codes (8 per k-mer occurrence) a bucket may hold when it is sorted.
+    pub memory_limit: usize,
+}
+
+/// K-mer codes and their counts in increasing code order, which is lexicographic k-mer order,
+/// merged from the counted buckets of count_kmers_on_disk. The temporary files are removed when
+/// it is dropped.
+pub struct SortedKmerCounts {
+    runs: Vec<BufReader<File>>,
+    // The next code and count of every run that isn't exhausted, smallest code first
+    heads: BinaryHeap<Reverse<(u64, u64, usize)>>,
+    _dir: TempDir,
+}
+
+impl Iterator for SortedKmerCounts {
+    type Item = io::Result<(u64, usize)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Buckets never share a k-mer, so no two runs hold the same code
+        let Reverse((code, count, run)) = self.heads.pop()?;
+        match read_pair(&mut self.runs[run]) {
+            Ok(Some((next_code, next_count))) => self.heads.push(Reverse((next_code, next_count, run))),
+            Ok(None) => {}
+            Err(e) => return Some(Err(e)),
+        }
+        Some(Ok((code, count as usize)))
+    }
+}
+
+/// Count the 2-bit packed k-mers (k <= 32) of a raw or FASTA file, gzipped or not, as
+/// count_kmers_packed_streaming does, but through bucket files in `options.tmp_dir` so memory
+/// stays near `options.memory_limit` however many distinct k-mers there are. The input is read
+/// once; the returned iterator streams the counts in k-mer order, for decode_kmer.
+pub fn count_kmers_on_disk(
+    file_name: &str,
+    k: usize,
+    canonical: bool,
+    options: &DiskCountOptions,
+) -> Result<SortedKmerCounts, KmerError> {
+    // Every input byte gives at most one 8-byte code; gzip packs roughly four bases per byte
+    let input_size = fs::metadata(file_name)?.len() as usize;
+    let codes_size = input_size.saturating_mul(if is_gzip_name(file_name) { 32 } else { 8 });
+    let dir = TempDir::create(&options.tmp_dir)?;
+    let mut buckets = Buckets::create(&dir.0, "bucket", bucket_count(codes_size, options.memory_limit), 0)?;
+    for_each_packed_kmer(file_name, k, canonical, |code| buckets.push(code))?;
+
+    let mut run_paths = Vec::new();
+    for path in buckets.finish()? {
+        count_bucket(&path, 0, options.memory_limit, &mut run_paths)?;
+    }
+    let mut runs = Vec::with_capacity(run_paths.len());
+    let mut heads = BinaryHeap::with_capacity(run_paths.len());
+    for path in run_paths {
+        let mut run = BufReader::new(File::open(path)?);
+        if let Some((code, count)) = read_pair(&mut run)? {
+            heads.push(Reverse((code, count, runs.len())));
+        }
+        runs.push(run);
+    }
+    Ok(SortedKmerCounts { runs, heads, _dir: dir })
+}
+
+// Enough buckets, a power of two, for `size` bytes of codes to split into buckets of about half
+// the memory limit each
+fn bucket_count(size: usize, memory_limit: usize) -> usize {
+    (size / (memory_limit / 2).max(1) + 1).next_power_of_two().min(MAX_DISK_BUCKETS)
+}
+
+// Sort and count the codes of one bucket file into a run file of (code, count) pairs, or split
+// it into smaller buckets first if it holds more than the memory limit
+fn count_bucket(path: &Path, level: u32, memory_limit: usize, runs: &mut Vec<PathBuf>) -> Result<(), KmerError> {
+    let size = fs::metadata(path)?.len() as usize;
+    if size == 0 {
+        fs::remove_file(path)?;
+        return Ok(());
+    }
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let name = path.file_name().map_or_else(|| "bucket".into(), |name| name.to_string_lossy().into_owned());
+    if size > memory_limit && level < MAX_DISK_SPLITS {
+        let mut buckets = Buckets::create(dir, &name, bucket_count(size, memory_limit).max(2), level + 1)?;
+        let mut reader = BufReader::new(File::open(path)?);
+        while let Some(code) = read_code(&mut reader)? {
+            buckets.push(code)?;
+        }
+        fs::remove_file(path)?;
+        for bucket in buckets.finish()? {
+            count_bucket(&bucket, level + 1, memory_limit, runs)?;
+        }
+        return Ok(());
+    }
+
+    let mut codes = Vec::with_capacity(size / 8);
+    let mut reader = BufReader::new(File::open(path)?);
+    while let Some(code) = read_code(&mut reader)? {
+        codes.push(code);
+    }
+    fs::remove_file(path)?;
+    codes.sort_unstable();
+    let run_path = dir.join(format!("{}.run", name));
+    let mut run = BufWriter::new(File::create(&run_path)?);
+    for group in codes.chunk_by(|a, b| a == b) {
+        run.write_all(&group[0].to_le_bytes())?;
+        run.write_all(&(group.len() as u64).to_le_bytes())?;
+    }
+    run.flush()?;
+    runs.push(run_path);
+    Ok(())
+}
+
+// Read the next little-endian u64 of a bucket or run file, or None at its end
+fn read_code(reader: &mut impl Read) -> io::Result<Option<u64>> {
+    let mut bytes = [0u8; 8];
+    match reader.read_exact(&mut bytes) {
+        Ok(()) => Ok(Some(u64::from_le_bytes(bytes))),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// Read the next (code, count) pair of a run file, or None at its end
+fn read_pair(reader: &mut impl Read) -> io::Result<Option<(u64, u64)>> {
+    let Some(code) = read_code(reader)? else {
+        return Ok(None);
+    };
+    let count = read_code(reader)?.ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "run file ends inside a count"))?;
+    Ok(Some((code, count)))
+}
+
+// The bucket files of one pass, each code going to the bucket its hash prefix picks; every
+// level hashes differently, so a bucket splits evenly when it is scattered again
+struct Buckets {
+    paths: Vec<PathBuf>,
+    writers: Vec<BufWriter<File>>,
+    level: u32,
+}
+
+impl Buckets {
+    fn create(dir: &Path, name: &str, count: usize, level: u32) -> io::Result<Self> {
+        let paths: Vec<PathBuf> = (0..count).map(|i| dir.join(format!("{}-{}", name, i))).collect();
+        let writers = paths
+            .iter()
+            .map(|path| Ok(BufWriter::with_capacity(DISK_BUCKET_BUFFER, File::create(path)?)))
+            .collect::<io::Result<_>>()?;
+        Ok(Buckets { paths, writers, level })
+    }
+
+    fn push(&mut self, code: u64) -> io::Result<()> {
+        let bits = self.writers.len().trailing_zeros();
+        let bucket = if bits == 0 { 0 } else { (mix(code, self.level) >> (64 - bits)) as usize };
+        self.writers[bucket].write_all(&code.to_le_bytes())
+    }
+
+    fn finish(self) -> io::Result<Vec<PathBuf>> {
+        for mut writer in self.writers {
+            writer.flush()?;
+        }
+        Ok(self.paths)
+    }
+}
+
+// SplitMix64 finalizer of a code, seeded by the split level
+fn mix(code: u64, level: u32) -> u64 {
+    let mut x = code.wrapping_add((level as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+// A uniquely named directory for one run's temporary files, removed with everything in it when
+// dropped
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn create(parent: &Path) -> io::Result<Self> {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_nanos());
+        let path = parent.join(format!("kmer-analysis-{}-{}", std::process::id(), nanos));
+        fs::create_dir_all(&path)?;
+        Ok(TempDir(path))
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,306 @@
+//! FASTA / FASTQ parsing. `records` streams one record at a time from any buffered reader,
+//! detecting the format from the first non-empty line; the helpers below collect sequences or
+//! FASTQ records and count k-mers record by record, so k-mers never span two records.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use crate::kmer::{canonical_kmer, generate_kmers_filtered, sanitize_sequence};
+use super::gzip::{open_input, OutputFile};
+
+/// The layout of a sequence file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceFormat {
+    /// '>' header lines, each followed by one or more sequence lines.
+    Fasta,
+    /// '@' header, sequence line(s), '+' separator, quality line(s).
+    Fastq,
+    /// Bare sequence; line breaks are removed and the whole input is one record.
+    Raw,
+}
+
+/// One record of a sequence file. `id` is the first word of the header (empty for raw input);
+/// `quality` is only present for FASTQ.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SequenceRecord {
+    /// First word of the header line.
+    pub id: String,
+    /// The bases, with line breaks removed.
+    pub sequence: String,
+    /// The quality string, for FASTQ records.
+    pub quality: Option<String>,
+}
+
+impl SequenceRecord {
+    /// Convert a FASTQ record into a FastqRecord; None for FASTA or raw records.
+    pub fn into_fastq(self) -> Option<FastqRecord> {
+        let quality = self.quality?;
+        Some(FastqRecord { id: self.id, sequence: self.sequence, quality })
+    }
+}
+
+/// Iterator over the records of a FASTA, FASTQ or raw input. Lines are trimmed and blank lines
+/// ignored; truncated records at the end of the input are returned as far as they go.
+pub struct Records<R> {
+    reader: R,
+    format: SequenceFormat,
+    // A line read ahead of the current record (the next FASTA header).
+    peeked: Option<String>,
+    done: bool,
+}
+
+/// Start reading records, detecting the format from the first non-empty line.
+pub fn records<R: BufRead>(reader: R) -> io::Result<Records<R>> {
+    let mut records = Records { reader, format: SequenceFormat::Raw, peeked: None, done: false };
+    match records.next_line()? {
+        Some(line) => {
+            records.format = match line.as_bytes()[0] {
+                b'>' => SequenceFormat::Fasta,
+                b'@' => SequenceFormat::Fastq,
+                _ => SequenceFormat::Raw,
+            };
+            records.peeked = Some(line);
+        }
+        None => records.done = true,
+    }
+    Ok(records)
+}
+
+/// Open a sequence file, gzipped or not (see open_input), and stream its records.
+pub fn open_records(file_name: &str) -> io::Result<Records<Box<dyn BufRead>>> {
+    records(open_input(file_name)?)
+}
+
+impl<R: BufRead> Records<R> {
+    /// The format detected from the first line.
+    pub fn format(&self) -> SequenceFormat {
+        self.format
+    }
+
+    // The next non-empty trimmed line, taking the read-ahead line first.
+    fn next_line(&mut self) -> io::Result<Option<String>> {
+        if let Some(line) = self.peeked.take() {
+            return Ok(Some(line));
+        }
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            let trimmed = line.trim();
+            if !trimmed.is_empty() {
+                return Ok(Some(trimmed.to_string()));
+            }
+        }
+    }
+
+    fn header_id(header: &str) -> String {
+        header[1..].split_whitespace().next().unwrap_or("").to_string()
+    }
+
+    fn next_record(&mut self) -> io::Result<Option<SequenceRecord>> {
+        match self.format {
+            SequenceFormat::Fasta => {
+                let Some(header) = self.next_line()? else {
+                    return Ok(None);
+                };
+                let mut sequence = String::new();
+                while let Some(line) = self.next_line()? {
+                    if line.starts_with('>') {
+                        self.peeked = Some(line);
+                        break;
+                    }
+                    sequence.push_str(&line);
+                }
+                Ok(Some(SequenceRecord { id: Self::header_id(&header), sequence, quality: None }))
+            }
+            SequenceFormat::Fastq => {
+                // Skip anything before the next '@' header.
+                let header = loop {
+                    match self.next_line()? {
+                        Some(line) if line.starts_with('@') => break line,
+                        Some(_) => continue,
+                        None => return Ok(None),
+                    }
+                };
+                let mut sequence = String::new();
+                let mut separator_seen = false;
+                while let Some(line) = self.next_line()? {
+                    if line.starts_with('+') {
+                        separator_seen = true;
+                        break;
+                    }
+                    sequence.push_str(&line);
+                }
+                // Quality lines until they cover the whole sequence; counting length matters
+                // because a quality string may itself start with '@'.
+                let mut quality = String::new();
+                while separator_seen && quality.len() < sequence.len() {
+                    match self.next_line()? {
+                        Some(line) => quality.push_str(&line),
+                        None => break,
+                    }
+                }
+                Ok(Some(SequenceRecord { id: Self::header_id(&header), sequence, quality: Some(quality) }))
+            }
+            SequenceFormat::Raw => {
+                let mut sequence = String::new();
+                while let Some(line) = self.next_line()? {
+                    sequence.push_str(&line);
+                }
+                self.done = true;
+                Ok(Some(SequenceRecord { id: String::new(), sequence, quality: None }))
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Records<R> {
+    type Item = io::Result<SequenceRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let record = self.next_record().transpose();
+        if !matches!(record, Some(Ok(_))) {
+            self.done = true;
+        }
+        record
+    }
+}
+
+/// Read every sequence in a FASTA, FASTQ or raw file, one String per record.
+/// Empty records are dropped.
+pub fn read_sequences(file_name: &str) -> io::Result<Vec<String>> {
+    let mut sequences = Vec::new();
+    for record in open_records(file_name)? {
+        let record = record?;
+        if !record.sequence.is_empty() {
+            sequences.push(record.sequence);
+        }
+    }
+    Ok(sequences)
+}
+
+/// Parse sequences from an in-memory string, detecting the format as `records` does: '>' for
+/// FASTA (multi-line records are joined), '@' for FASTQ (the '+' line and quality string are
+/// skipped), anything else is raw sequence with line breaks removed. Empty records are dropped.
+pub fn parse_sequences(contents: &str) -> Vec<String> {
+    let Ok(parsed) = records(contents.as_bytes()) else {
+        return Vec::new();
+    };
+    parsed
+        .filter_map(Result::ok)
+        .map(|record| record.sequence)
+        .filter(|sequence| !sequence.is_empty())
+        .collect()
+}
+
+/// Read a FASTQ file into FastqRecords. Fails with InvalidData if the file isn't FASTQ.
+pub fn read_fastq(file_name: &str) -> io::Result<Vec<FastqRecord>> {
+    let parsed = open_records(file_name)?;
+    if parsed.format() != SequenceFormat::Fastq {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} is not a FASTQ file", file_name)));
+    }
+    parsed
+        .map(|record| record.map(|record| record.into_fastq().unwrap_or_else(|| unreachable!("FASTQ records carry quality"))))
+        .collect()
+}
+
+/// Write FASTQ records, four lines each, to a file (gzipped if its name ends in `.gz`).
+pub fn write_fastq(records: &[FastqRecord], file_name: &str) -> io::Result<()> {
+    let mut file = OutputFile::create(file_name)?;
+    for record in records {
+        writeln!(file, "@{}\n{}\n+\n{}", record.id, record.sequence, record.quality)?;
+    }
+    file.finish()
+}
+
+/// Bases per sequence line in FASTA output.
+pub const FASTA_LINE_WIDTH: usize = 60;
+
+/// Write (id, sequence) records as FASTA to a file (gzipped if its name ends in `.gz`), wrapping
+/// sequences at FASTA_LINE_WIDTH bases. The id is everything after the '>', so it may carry a
+/// description after a space.
+pub fn write_fasta(records: &[(String, String)], file_name: &str) -> io::Result<()> {
+    let mut file = OutputFile::create(file_name)?;
+    for (id, sequence) in records {
+        writeln!(file, ">{}", id)?;
+        for line in sequence.as_bytes().chunks(FASTA_LINE_WIDTH) {
+            file.write_all(line)?;
+            writeln!(file)?;
+        }
+    }
+    file.finish()
+}
+
+/// Count k-mers in every record of a sequence file, one record at a time. Each record is
+/// sanitized and k-mers spanning non-ACGT characters are skipped, as in the main pipeline.
+/// With `canonical` set, each k-mer is counted under canonical_kmer, merging the two strands.
+pub fn count_kmers_in_file(file_name: &str, k: usize, canonical: bool) -> io::Result<HashMap<String, usize>> {
+    let mut counts = HashMap::new();
+    for record in open_records(file_name)? {
+        for kmer in generate_kmers_filtered(&sanitize_sequence(&record?.sequence), k) {
+            let kmer = if canonical { canonical_kmer(&kmer) } else { kmer };
+            *counts.entry(kmer).or_insert(0) += 1;
+        }
+    }
+    Ok(counts)
+}
+
+/// A single FASTQ record: identifier, bases, and the ASCII-encoded quality string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FastqRecord {
+    /// First word of the header line.
+    pub id: String,
+    /// The bases.
+    pub sequence: String,
+    /// One quality character per base.
+    pub quality: String,
+}
+
+/// The ASCII offset used to encode Phred quality scores.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QualityEncoding {
+    /// Sanger / Illumina 1.8+, quality characters start at '!'.
+    Phred33,
+    /// Older Illumina 1.3-1.7, quality characters start at '@'.
+    Phred64,
+}
+
+impl QualityEncoding {
+    /// The ASCII value of quality score 0.
+    pub fn offset(self) -> u8 {
+        match self {
+            QualityEncoding::Phred33 => 33,
+            QualityEncoding::Phred64 => 64,
+        }
+    }
+}
+
+impl FastqRecord {
+    /// Decode the quality string into Phred scores using the given encoding.
+    /// Characters below the offset decode to 0 rather than underflowing.
+    pub fn phred_scores(&self, encoding: QualityEncoding) -> Vec<u8> {
+        self.quality.bytes().map(|b| b.saturating_sub(encoding.offset())).collect()
+    }
+}
+
+/// Infer the quality encoding from the range of quality characters across records.
+/// Anything below ';' can only be Phred+33, and anything above 'J' only appears in Phred+64.
+/// Ambiguous or empty input defaults to Phred+33, the modern standard.
+pub fn detect_quality_encoding(records: &[FastqRecord]) -> QualityEncoding {
+    let mut min = u8::MAX;
+    let mut max = u8::MIN;
+    for byte in records.iter().flat_map(|record| record.quality.bytes()) {
+        min = min.min(byte);
+        max = max.max(byte);
+    }
+
+    if min >= b';' && max > b'J' {
+        QualityEncoding::Phred64
+    } else {
+        QualityEncoding::Phred33
+    }
+}
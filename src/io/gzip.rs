@@ -0,0 +1,82 @@
+//! Transparent gzip support. Inputs are decompressed whenever they start with the gzip magic
+//! bytes, whatever their name, and outputs are compressed when their name ends in `.gz`.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use flate2::bufread::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use super::STREAMING_BUFFER_SIZE;
+
+/// The two bytes every gzip member starts with.
+pub const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Open a file for buffered reading, decompressing it if it is gzipped. Every member is read,
+/// so bgzip files (which are a series of gzip members) decode in full.
+pub fn open_input(file_name: &str) -> io::Result<Box<dyn BufRead>> {
+    let mut reader = BufReader::with_capacity(STREAMING_BUFFER_SIZE, File::open(file_name)?);
+    if reader.fill_buf()?.starts_with(&GZIP_MAGIC) {
+        Ok(Box::new(BufReader::with_capacity(STREAMING_BUFFER_SIZE, MultiGzDecoder::new(reader))))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+/// Whether an output of this name is written gzipped.
+pub fn is_gzip_name(file_name: &str) -> bool {
+    file_name.ends_with(".gz")
+}
+
+/// A buffered output file, gzipped when its name ends in `.gz`. Call `finish` once everything
+/// is written: it flushes the buffer and writes the gzip trailer, reporting any error that
+/// dropping the file would swallow.
+#[derive(Debug)]
+pub enum OutputFile {
+    /// Written as is.
+    Plain(BufWriter<File>),
+    /// Compressed on the way out.
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+impl OutputFile {
+    /// Create (or truncate) the file.
+    pub fn create(file_name: &str) -> io::Result<Self> {
+        let file = BufWriter::new(File::create(file_name)?);
+        if is_gzip_name(file_name) {
+            Ok(OutputFile::Gzip(GzEncoder::new(file, Compression::default())))
+        } else {
+            Ok(OutputFile::Plain(file))
+        }
+    }
+
+    /// Flush everything to disk, completing the gzip stream if there is one.
+    pub fn finish(self) -> io::Result<()> {
+        match self {
+            OutputFile::Plain(mut file) => file.flush(),
+            OutputFile::Gzip(encoder) => encoder.finish()?.flush(),
+        }
+    }
+}
+
+impl Write for OutputFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            OutputFile::Plain(file) => file.write(buf),
+            OutputFile::Gzip(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            OutputFile::Plain(file) => file.flush(),
+            OutputFile::Gzip(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Write `contents` to a file in one go, gzipped when its name ends in `.gz`.
+pub fn write_output(file_name: &str, contents: &[u8]) -> io::Result<()> {
+    let mut file = OutputFile::create(file_name)?;
+    file.write_all(contents)?;
+    file.finish()
+}
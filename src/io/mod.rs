@@ -0,0 +1,656 @@
+//! Reading sequences and writing results: FASTA/FASTQ parsing (in `fasta`), transparent gzip
+//! (in `gzip`), saved count tables and graphs (in `binary`), counting through temporary files
+//! (in `disk`), batch inputs and summaries (in `batch`), CSV/JSON/WIG writers, the on-disk k-mer
+//! database and run manifests.
+
+pub mod batch;
+pub mod binary;
+pub mod disk;
+pub mod fasta;
+pub mod gzip;
+
+use std::fs;
+use std::io;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufRead, Read, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use csv::Writer;
+use petgraph::graph::DiGraph;
+use petgraph::dot::Dot;
+use serde::Serialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use crate::analysis::KmerDiff;
+use crate::error::KmerError;
+use crate::kmer::{
+    base_index, canonical_kmer, check_k, decode_kmer, encode_kmer, generate_kmers_filtered, sanitize_sequence, sorted_counts, CountOrder,
+    Kmer, KmerSummary,
+};
+use crate::graph::{Colors, DeBruijnGraph, GraphMetrics};
+use crate::stats::WindowStats;
+
+pub use fasta::{detect_quality_encoding, parse_sequences, read_sequences, write_fasta, write_fastq, FastqRecord, QualityEncoding};
+pub use batch::{batch_samples, read_batch_manifest, write_batch_summary_to_csv, BatchSample, SampleSummary};
+pub use binary::{is_saved_index, load_index, save_graph, save_kmer_counts, SavedIndex};
+pub use disk::{count_kmers_on_disk, DiskCountOptions, SortedKmerCounts};
+pub use gzip::{open_input, write_output, OutputFile};
+
+/// Function to write the generated DNA sequence to a file
+pub fn write_to_file(contents: &str, file_name: &str) -> io::Result<()> {
+    // Create or open the file, gzipped if its name ends in .gz, and write the DNA sequence to it
+    write_output(file_name, contents.as_bytes())
+}
+
+/// Function to read the DNA sequence from a file. FASTA/FASTQ records are parsed and joined,
+/// so headers and quality strings never end up in the sequence.
+pub fn read_dna_sequence_from_file(file_name: &str) -> io::Result<String> {
+    Ok(read_sequences(file_name)?.concat())
+}
+
+/// Default read size for count_kmers_streaming.
+pub const STREAMING_BUFFER_SIZE: usize = 1 << 20;
+
+/// Count k-mers in a raw or FASTA file, gzipped or not, without loading it: the file is read
+/// in fixed-size buffers and the last k-1 bases of each are carried into the next, so k-mers
+/// spanning a buffer boundary are still counted. Bases are sanitized as in main and k-mers
+/// spanning non-ACGT characters are skipped; FASTA headers end the previous record.
+pub fn count_kmers_streaming(file_name: &str, k: usize) -> io::Result<HashMap<String, usize>> {
+    count_kmers_streaming_with_buffer(file_name, k, STREAMING_BUFFER_SIZE)
+}
+
+/// count_kmers_streaming with an explicit read size in bytes.
+pub fn count_kmers_streaming_with_buffer(file_name: &str, k: usize, buffer_size: usize) -> io::Result<HashMap<String, usize>> {
+    let mut counts = HashMap::new();
+    if k == 0 {
+        return Ok(counts);
+    }
+    let mut file = open_input(file_name)?;
+    let mut buffer = vec![0u8; buffer_size.max(1)];
+    // Bases not yet consumed: the carried k-1 bases followed by the current buffer's bases.
+    let mut pending = String::new();
+    let mut in_header = false;
+    let mut at_line_start = true;
+
+    let flush = |pending: &mut String, counts: &mut HashMap<String, usize>, keep_tail: bool| {
+        for kmer in generate_kmers_filtered(&sanitize_sequence(pending), k) {
+            *counts.entry(kmer).or_insert(0) += 1;
+        }
+        let keep = if keep_tail { (k - 1).min(pending.len()) } else { 0 };
+        pending.drain(..pending.len() - keep);
+    };
+
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        for &byte in &buffer[..read] {
+            if at_line_start && byte == b'>' {
+                flush(&mut pending, &mut counts, false);
+                in_header = true;
+            }
+            at_line_start = byte == b'\n';
+            if at_line_start {
+                in_header = false;
+            } else if !in_header && !byte.is_ascii_whitespace() {
+                // Non-ASCII bytes would be masked anyway; pushing them as N keeps every char
+                // one byte long, so the tail kept by flush never splits a char.
+                pending.push(if byte.is_ascii() { byte as char } else { 'N' });
+            }
+        }
+        flush(&mut pending, &mut counts, true);
+    }
+    flush(&mut pending, &mut counts, false);
+    Ok(counts)
+}
+
+/// Count 2-bit packed k-mers (k <= 32) in a raw or FASTA file, gzipped or not, in bounded
+/// memory: the file is read through a STREAMING_BUFFER_SIZE buffer and each base is shifted
+/// into a rolling u64 code, so no k-mer is ever allocated as a String and memory grows only
+/// with the number of distinct k-mers. Non-ACGT characters and FASTA headers restart the
+/// window. With `canonical` set, codes are counted under Kmer::canonical. Decode the keys with
+/// decode_kmer.
+pub fn count_kmers_packed_streaming(file_name: &str, k: usize, canonical: bool) -> Result<HashMap<u64, usize>, KmerError> {
+    let mut counts = HashMap::new();
+    for_each_packed_kmer(file_name, k, canonical, |code| {
+        *counts.entry(code).or_insert(0) += 1;
+        Ok(())
+    })?;
+    Ok(counts)
+}
+
+/// Call `visit` with the 2-bit code of every k-mer of a raw or FASTA file, gzipped or not, in
+/// file order, scanning it as count_kmers_packed_streaming does (k <= 32, canonical codes with
+/// `canonical` set). Stops at the first error `visit` returns.
+pub fn for_each_packed_kmer(
+    file_name: &str,
+    k: usize,
+    canonical: bool,
+    mut visit: impl FnMut(u64) -> io::Result<()>,
+) -> Result<(), KmerError> {
+    check_k(k, 32)?;
+    let mut reader = open_input(file_name)?;
+    let mask = if k == 32 { u64::MAX } else { (1u64 << (2 * k)) - 1 };
+    let mut code = 0u64;
+    // Number of consecutive valid bases ending at the current position.
+    let mut valid = 0;
+    let mut in_header = false;
+    let mut at_line_start = true;
+
+    loop {
+        let buffer = reader.fill_buf()?;
+        if buffer.is_empty() {
+            break;
+        }
+        for &byte in buffer {
+            if at_line_start && byte == b'>' {
+                in_header = true;
+                valid = 0;
+            }
+            at_line_start = byte == b'\n';
+            if at_line_start {
+                in_header = false;
+            } else if in_header || byte.is_ascii_whitespace() {
+                continue;
+            } else if let Some(base) = base_index(byte) {
+                code = ((code << 2) | base as u64) & mask;
+                valid += 1;
+                if valid >= k {
+                    visit(if canonical { Kmer(code).canonical(k).0 } else { code })?;
+                }
+            } else {
+                valid = 0;
+            }
+        }
+        let consumed = buffer.len();
+        reader.consume(consumed);
+    }
+    Ok(())
+}
+
+/// Function to write k-mer counts to a CSV file, in `order`
+pub fn write_kmer_counts_to_csv(kmer_counts: &HashMap<String, usize>, order: CountOrder, file_name: &str) -> Result<(), KmerError> {
+    let mut wtr = Writer::from_writer(OutputFile::create(file_name)?);
+
+    // Write header
+    wtr.write_record(["K-mer", "Count"])?;
+
+    // Write k-mer data
+    for (kmer, count) in sorted_counts(kmer_counts, order) {
+        wtr.write_record([kmer, &count.to_string()])?;
+    }
+
+    wtr.into_inner()?.finish()?;
+    Ok(())
+}
+
+/// Function to write k-mer counts as tab-separated k-mer and count columns under a header row,
+/// in `order`
+pub fn write_kmer_counts_to_tsv(kmer_counts: &HashMap<String, usize>, order: CountOrder, file_name: &str) -> io::Result<()> {
+    let mut file = OutputFile::create(file_name)?;
+    writeln!(file, "K-mer\tCount")?;
+    for (kmer, count) in sorted_counts(kmer_counts, order) {
+        writeln!(file, "{}\t{}", kmer, count)?;
+    }
+    file.finish()
+}
+
+/// Function to write k-mer counts in the FASTA-style layout of `jellyfish dump`: a `>count`
+/// header line followed by the k-mer, in `order`
+pub fn write_kmer_counts_jellyfish(kmer_counts: &HashMap<String, usize>, order: CountOrder, file_name: &str) -> io::Result<()> {
+    let mut file = OutputFile::create(file_name)?;
+    for (kmer, count) in sorted_counts(kmer_counts, order) {
+        writeln!(file, ">{}\n{}", count, kmer)?;
+    }
+    file.finish()
+}
+
+/// Function to write k-mer counts as a KMC text dump (`kmc_tools transform ... dump`): one
+/// `kmer<TAB>count` line per k-mer, in `order`, with no header
+pub fn write_kmer_counts_kmc(kmer_counts: &HashMap<String, usize>, order: CountOrder, file_name: &str) -> io::Result<()> {
+    let mut file = OutputFile::create(file_name)?;
+    for (kmer, count) in sorted_counts(kmer_counts, order) {
+        writeln!(file, "{}\t{}", kmer, count)?;
+    }
+    file.finish()
+}
+
+/// Function to write one row of distinct, unique and total k-mer counts per k to a CSV file
+pub fn write_kmer_summary_to_csv(summaries: &[KmerSummary], file_name: &str) -> Result<(), KmerError> {
+    let mut wtr = Writer::from_writer(OutputFile::create(file_name)?);
+
+    wtr.write_record(["K", "Distinct", "Unique", "Total"])?;
+    for summary in summaries {
+        wtr.write_record([summary.k, summary.distinct, summary.unique, summary.total].map(|n| n.to_string()))?;
+    }
+
+    wtr.into_inner()?.finish()?;
+    Ok(())
+}
+
+/// Function to write a labelled square matrix, such as pairwise sketch distances, to a CSV
+/// file: a header row of labels, then one row per label starting with the label itself
+pub fn write_matrix_to_csv(labels: &[String], matrix: &[Vec<f64>], file_name: &str) -> Result<(), KmerError> {
+    let mut wtr = Writer::from_writer(OutputFile::create(file_name)?);
+
+    wtr.write_record(std::iter::once("").chain(labels.iter().map(String::as_str)))?;
+    for (label, row) in labels.iter().zip(matrix) {
+        wtr.write_record(std::iter::once(label.clone()).chain(row.iter().map(|value| format!("{:.6}", value))))?;
+    }
+
+    wtr.into_inner()?.finish()?;
+    Ok(())
+}
+
+/// Function to write the sliding-window statistics of each record to a CSV file, one row per
+/// window with its 1-based record number, 0-based start and exclusive end
+pub fn write_window_stats_to_csv(records: &[Vec<WindowStats>], file_name: &str) -> Result<(), KmerError> {
+    let mut wtr = Writer::from_writer(OutputFile::create(file_name)?);
+
+    wtr.write_record(["Record", "Start", "End", "GC", "Entropy", "Other"])?;
+    for (record, windows) in records.iter().enumerate() {
+        for window in windows {
+            wtr.write_record([
+                (record + 1).to_string(),
+                window.start.to_string(),
+                (window.start + window.stats.length).to_string(),
+                format!("{:.4}", window.stats.gc_content()),
+                format!("{:.4}", window.stats.entropy()),
+                window.stats.other.to_string(),
+            ])?;
+        }
+    }
+
+    wtr.into_inner()?.finish()?;
+    Ok(())
+}
+
+/// Function to write packed k-mer counts to a CSV file in `order`, decoding each code back to
+/// its k-mer
+pub fn write_packed_kmer_counts_to_csv(
+    kmer_counts: &HashMap<u64, usize>,
+    k: usize,
+    order: CountOrder,
+    file_name: &str,
+) -> Result<(), KmerError> {
+    let mut wtr = Writer::from_writer(OutputFile::create(file_name)?);
+
+    wtr.write_record(["K-mer", "Count"])?;
+    for (&code, count) in sorted_counts(kmer_counts, order) {
+        wtr.write_record([&decode_kmer(code, k), &count.to_string()])?;
+    }
+
+    wtr.into_inner()?.finish()?;
+    Ok(())
+}
+
+/// write_packed_kmer_counts_to_csv for counts streamed in the order to write them, such as the
+/// SortedKmerCounts of count_kmers_on_disk, so the table is never held in memory.
+pub fn write_sorted_kmer_counts_to_csv(
+    kmer_counts: impl IntoIterator<Item = io::Result<(u64, usize)>>,
+    k: usize,
+    file_name: &str,
+) -> Result<(), KmerError> {
+    let mut wtr = Writer::from_writer(OutputFile::create(file_name)?);
+
+    wtr.write_record(["K-mer", "Count"])?;
+    for entry in kmer_counts {
+        let (code, count) = entry?;
+        wtr.write_record([&decode_kmer(code, k), &count.to_string()])?;
+    }
+
+    wtr.into_inner()?.finish()?;
+    Ok(())
+}
+
+/// Write the rows of diff_counts to a CSV file: each k-mer, its count in both samples and the
+/// log2 fold change of B over A.
+pub fn write_kmer_diff_to_csv(diffs: &[KmerDiff], file_name: &str) -> Result<(), KmerError> {
+    let mut wtr = Writer::from_writer(OutputFile::create(file_name)?);
+
+    wtr.write_record(["K-mer", "CountA", "CountB", "Log2FoldChange"])?;
+    for diff in diffs {
+        wtr.write_record([
+            diff.kmer.clone(),
+            diff.count_a.to_string(),
+            diff.count_b.to_string(),
+            format!("{:.4}", diff.log2_fold_change),
+        ])?;
+    }
+
+    wtr.into_inner()?.finish()?;
+    Ok(())
+}
+
+/// Function to write per-record k-mer counts to a long (tidy) CSV with one row per record and k-mer,
+/// sorted by record id and then k-mer so the output is deterministic
+pub fn write_counts_long_csv(records: &[(String, HashMap<String, usize>)], file_name: &str) -> Result<(), KmerError> {
+    let mut wtr = Writer::from_writer(OutputFile::create(file_name)?);
+
+    // Write header
+    wtr.write_record(["record_id", "kmer", "count"])?;
+
+    let mut rows: Vec<(&String, &String, usize)> = records
+        .iter()
+        .flat_map(|(record_id, counts)| counts.iter().map(move |(kmer, &count)| (record_id, kmer, count)))
+        .collect();
+    rows.sort();
+
+    for (record_id, kmer, count) in rows {
+        wtr.write_record([record_id, kmer, &count.to_string()])?;
+    }
+
+    wtr.into_inner()?.finish()?;
+    Ok(())
+}
+
+/// Write the graph to a CSV file: each node, in lexicographic order, with its successors joined
+/// by ", " and the weights of the edges to them, in the same order.
+pub fn write_graph_to_csv(graph: &DeBruijnGraph, file_name: &str) -> Result<(), KmerError> {
+    let mut wtr = Writer::from_writer(OutputFile::create(file_name)?);
+
+    // Write header
+    wtr.write_record(["Node", "Connected Nodes", "Weights"])?;
+
+    // Write graph data
+    let sorted: BTreeMap<&String, &BTreeMap<String, usize>> = graph.edges.iter().collect();
+    for (node, edges) in sorted {
+        let connected_nodes = edges.keys().map(String::as_str).collect::<Vec<_>>().join(", ");
+        let weights = edges.values().map(usize::to_string).collect::<Vec<_>>().join(", ");
+        wtr.write_record([node, &connected_nodes, &weights])?;
+    }
+
+    wtr.into_inner()?.finish()?;
+    Ok(())
+}
+
+/// Function to write graph metrics to a two-column Metric,Value CSV file, with one row per
+/// degree of each distribution
+pub fn write_graph_metrics_to_csv(metrics: &GraphMetrics, file_name: &str) -> Result<(), KmerError> {
+    let mut wtr = Writer::from_writer(OutputFile::create(file_name)?);
+
+    wtr.write_record(["Metric", "Value"])?;
+    let scalars = [
+        ("nodes", metrics.nodes.to_string()),
+        ("edges", metrics.edges.to_string()),
+        ("components", metrics.components.to_string()),
+        ("branching_nodes", metrics.branching_nodes.to_string()),
+        ("longest_path", metrics.longest_path.to_string()),
+        ("acyclic", metrics.acyclic.to_string()),
+    ];
+    for (metric, value) in scalars {
+        wtr.write_record([metric, &value])?;
+    }
+    for (direction, degrees) in [("in_degree", &metrics.in_degrees), ("out_degree", &metrics.out_degrees)] {
+        for (degree, nodes) in degrees {
+            wtr.write_record([format!("{}_{}", direction, degree), nodes.to_string()])?;
+        }
+    }
+
+    wtr.into_inner()?.finish()?;
+    Ok(())
+}
+
+/// Function to write graph metrics to a JSON object, with each degree distribution as an array
+/// of { "degree", "nodes" } objects
+pub fn write_graph_metrics_to_json(metrics: &GraphMetrics, file_name: &str) -> Result<(), KmerError> {
+    let distribution = |degrees: &BTreeMap<usize, usize>| -> Vec<serde_json::Value> {
+        degrees.iter().map(|(degree, nodes)| json!({ "degree": degree, "nodes": nodes })).collect()
+    };
+    let summary = json!({
+        "nodes": metrics.nodes,
+        "edges": metrics.edges,
+        "in_degrees": distribution(&metrics.in_degrees),
+        "out_degrees": distribution(&metrics.out_degrees),
+        "components": metrics.components,
+        "branching_nodes": metrics.branching_nodes,
+        "longest_path": metrics.longest_path,
+        "acyclic": metrics.acyclic,
+    });
+    write_output(file_name, serde_json::to_string_pretty(&summary)?.as_bytes())?;
+    Ok(())
+}
+
+/// Function to write k-mer counts to a JSON object mapping each k-mer to its count
+pub fn write_kmer_counts_to_json(kmer_counts: &HashMap<String, usize>, file_name: &str) -> Result<(), KmerError> {
+    let sorted: BTreeMap<&String, &usize> = kmer_counts.iter().collect();
+    write_output(file_name, serde_json::to_string_pretty(&sorted)?.as_bytes())?;
+    Ok(())
+}
+
+// One node of the De Bruijn graph as written to JSON.
+#[derive(Serialize)]
+struct GraphNodeJson<'a> {
+    node: &'a str,
+    edges: Vec<&'a str>,
+    weights: Vec<usize>,
+}
+
+/// Function to write the De Bruijn graph to JSON as an array of { "node", "edges", "weights" }
+/// objects, the weights of the edges in the order of their successors
+pub fn write_graph_to_json(graph: &DeBruijnGraph, file_name: &str) -> Result<(), KmerError> {
+    let mut nodes: Vec<GraphNodeJson> = graph
+        .edges
+        .iter()
+        .map(|(node, edges)| GraphNodeJson {
+            node,
+            edges: edges.keys().map(String::as_str).collect(),
+            weights: edges.values().copied().collect(),
+        })
+        .collect();
+    nodes.sort_by(|a, b| a.node.cmp(b.node));
+    write_output(file_name, serde_json::to_string_pretty(&nodes)?.as_bytes())?;
+    Ok(())
+}
+
+/// Write the graph as GFA 1.0 for Bandage and other assembly graph viewers: one segment per
+/// unitig (see DeBruijnGraph::collapse_unitigs), numbered from 1, with its length and total
+/// k-mer count in LN and KC tags, and one link per unitig junction overlapping by k-1 bases.
+/// Segments of a colored graph also get an SM tag listing the samples any of its k-mers came
+/// from, comma-separated.
+pub fn write_graph_to_gfa(graph: &DeBruijnGraph, file_name: &str) -> io::Result<()> {
+    let (unitigs, _) = graph.collapse_unitigs();
+    let mut file = OutputFile::create(file_name)?;
+    writeln!(file, "H\tVN:Z:1.0")?;
+    for (i, (segment, coverage)) in unitigs.segments.iter().zip(&unitigs.coverage).enumerate() {
+        // The unitig holds len - k + 1 k-mers, each seen `coverage` times on average.
+        let kmer_count = (coverage * (segment.len() + 1 - unitigs.k) as f64).round() as usize;
+        write!(file, "S\t{}\t{}\tLN:i:{}\tKC:i:{}", i + 1, segment, segment.len(), kmer_count)?;
+        if graph.is_colored() {
+            let k = unitigs.k;
+            let colors = (0..=segment.len() - k)
+                .filter_map(|j| graph.colors.get(&(segment[j..j + k - 1].to_string(), segment[j + 1..j + k].to_string())))
+                .fold(0, |all, colors| all | colors);
+            write!(file, "\tSM:Z:{}", graph.sample_names(colors).join(","))?;
+        }
+        writeln!(file)?;
+    }
+    for &(from, to) in &unitigs.links {
+        writeln!(file, "L\t{}\t+\t{}\t+\t{}M", from + 1, to + 1, unitigs.k - 1)?;
+    }
+    file.finish()
+}
+
+/// Function to write a per-base coverage vector as a fixedStep WIG track (1-based, step 1),
+/// which loads directly into IGV or the UCSC genome browser.
+pub fn write_wig(chrom: &str, coverage: &[usize], file_name: &str) -> io::Result<()> {
+    let mut file = OutputFile::create(file_name)?;
+    writeln!(file, "track type=wiggle_0 name=\"{}\"", chrom)?;
+    writeln!(file, "fixedStep chrom={} start=1 step=1", chrom)?;
+    for depth in coverage {
+        writeln!(file, "{}", depth)?;
+    }
+    file.finish()
+}
+
+/// A compact on-disk index of canonical k-mers, stored as sorted 2-bit encoded u64s.
+/// File layout: the magic bytes "KMERDB01", then k and the number of k-mers as little-endian u64s,
+/// followed by the sorted codes. Lookups binary-search the memory-mapped file directly.
+pub struct KmerDatabase {
+    k: usize,
+    len: usize,
+    map: memmap2::Mmap,
+}
+
+/// Magic bytes at the start of every KmerDatabase file.
+pub const KMER_DATABASE_MAGIC: &[u8; 8] = b"KMERDB01";
+
+/// Size in bytes of the magic, k and count header.
+pub const KMER_DATABASE_HEADER: usize = 24;
+
+impl KmerDatabase {
+    /// Build a database of the canonical k-mers in `sequences`, write it to `file_name`, and open it.
+    /// K-mers containing non-ACGT characters are skipped; k must be between 1 and 32.
+    pub fn build(sequences: &[String], k: usize, file_name: &str) -> Result<Self, KmerError> {
+        check_k(k, 32)?;
+        let mut codes: Vec<u64> = sequences
+            .iter()
+            .filter(|seq| seq.len() >= k)
+            .flat_map(|seq| (0..=seq.len() - k).map(move |i| &seq[i..i + k]))
+            .filter_map(|kmer| encode_kmer(&canonical_kmer(&kmer.to_ascii_uppercase())))
+            .collect();
+        codes.sort_unstable();
+        codes.dedup();
+
+        let mut file = io::BufWriter::new(File::create(file_name)?);
+        file.write_all(KMER_DATABASE_MAGIC)?;
+        file.write_all(&(k as u64).to_le_bytes())?;
+        file.write_all(&(codes.len() as u64).to_le_bytes())?;
+        for code in &codes {
+            file.write_all(&code.to_le_bytes())?;
+        }
+        file.flush()?;
+        drop(file);
+
+        Ok(Self::open(file_name)?)
+    }
+
+    /// Open an existing database file by memory-mapping it.
+    pub fn open(file_name: &str) -> io::Result<Self> {
+        let file = File::open(file_name)?;
+        // Safety: the file is only read, and the database format isn't modified in place.
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "not a k-mer database file");
+        if map.len() < KMER_DATABASE_HEADER || &map[..8] != KMER_DATABASE_MAGIC {
+            return Err(invalid());
+        }
+        let read_u64 = |offset: usize| u64::from_le_bytes(map[offset..offset + 8].try_into().unwrap());
+        let k = read_u64(8) as usize;
+        let len = read_u64(16) as usize;
+        // The count comes from the file, so a corrupt one mustn't overflow the expected size.
+        let expected = len.checked_mul(8).and_then(|bytes| bytes.checked_add(KMER_DATABASE_HEADER));
+        if expected != Some(map.len()) {
+            return Err(invalid());
+        }
+        Ok(KmerDatabase { k, len, map })
+    }
+
+    /// The encoded k-mer at `index` in sorted order.
+    pub fn code_at(&self, index: usize) -> u64 {
+        let offset = KMER_DATABASE_HEADER + index * 8;
+        u64::from_le_bytes(self.map[offset..offset + 8].try_into().unwrap())
+    }
+
+    /// Whether the k-mer (on either strand) is in the database.
+    pub fn lookup(&self, kmer: &str) -> bool {
+        if kmer.len() != self.k {
+            return false;
+        }
+        let Some(target) = encode_kmer(&canonical_kmer(&kmer.to_ascii_uppercase())) else {
+            return false;
+        };
+        let (mut low, mut high) = (0, self.len);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match self.code_at(mid).cmp(&target) {
+                std::cmp::Ordering::Equal => return true,
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+            }
+        }
+        false
+    }
+}
+
+/// The parameters of one analysis run, recorded in the manifest.
+#[derive(Debug, Clone, Default)]
+pub struct AnalysisParams {
+    /// K-mer size.
+    pub k: usize,
+    /// Seed of the random generator, if one was used.
+    pub seed: Option<u64>,
+    /// Length of the generated sequence, when no input was given.
+    pub length: Option<usize>,
+    /// Path of the input sequence file.
+    pub input: Option<String>,
+    /// Lower count bound applied to the output, if any.
+    pub min_count: Option<usize>,
+    /// Upper count bound applied to the output, if any.
+    pub max_count: Option<usize>,
+}
+
+/// Write a JSON manifest of a run: its parameters, the crate version, a Unix timestamp and each
+/// output file's size and SHA-256 checksum, so any result can be traced to its invocation.
+/// The manifest is gzipped when its name ends in `.gz`.
+pub fn write_manifest(params: &AnalysisParams, outputs: &[PathBuf], file_name: &str) -> Result<(), KmerError> {
+    let mut files = Vec::new();
+    for path in outputs {
+        let contents = fs::read(path)?;
+        let checksum: String = Sha256::digest(&contents).iter().map(|byte| format!("{:02x}", byte)).collect();
+        files.push(json!({
+            "path": path.display().to_string(),
+            "size": contents.len(),
+            "sha256": checksum,
+        }));
+    }
+    let manifest = json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "timestamp": SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+        "parameters": {
+            "k": params.k,
+            "seed": params.seed,
+            "length": params.length,
+            "input": params.input,
+            "min_count": params.min_count,
+            "max_count": params.max_count,
+        },
+        "outputs": files,
+    });
+    write_output(file_name, serde_json::to_string_pretty(&manifest)?.as_bytes())?;
+    Ok(())
+}
+
+/// Save the graph in DOT format; edges are drawn as arrows labelled with their weight.
+pub fn save_graph_dot(graph: &DiGraph<String, usize>, file_name: &str) -> Result<(), std::io::Error> {
+    let dot = Dot::with_config(&graph, &[]);
+    let mut file = OutputFile::create(file_name)?;
+    writeln!(file, "{:?}", dot)?;
+    file.finish()
+}
+
+/// Save a colored graph in DOT format, like save_graph_dot, with every node labelled by the
+/// samples of its edges and every edge by its weight and samples. Nodes and edges also carry a
+/// `samples` attribute listing the sample names, comma-separated, for scripts to read.
+pub fn write_colored_graph_to_dot(graph: &DeBruijnGraph, file_name: &str) -> io::Result<()> {
+    let node_colors = graph.node_colors();
+    let nodes = graph.nodes();
+    let index: HashMap<&String, usize> = nodes.iter().enumerate().map(|(i, node)| (node, i)).collect();
+    let samples = |colors: Option<&Colors>| graph.sample_names(colors.copied().unwrap_or(0)).join(",").replace('\\', "\\\\").replace('"', "\\\"");
+    let mut edges: Vec<((String, String), usize)> = graph.edge_weights().into_iter().collect();
+    edges.sort();
+
+    let mut file = OutputFile::create(file_name)?;
+    writeln!(file, "digraph {{")?;
+    for (i, node) in nodes.iter().enumerate() {
+        let samples = samples(node_colors.get(node));
+        writeln!(file, "    {} [ label = \"{}\\n{}\" samples = \"{}\" ]", i, node, samples, samples)?;
+    }
+    for ((node, next), weight) in edges {
+        let samples = samples(graph.colors.get(&(node.clone(), next.clone())));
+        writeln!(file, "    {} -> {} [ label = \"{} {}\" samples = \"{}\" ]", index[&node], index[&next], weight, samples, samples)?;
+    }
+    writeln!(file, "}}")?;
+    file.finish()
+}
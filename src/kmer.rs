@@ -0,0 +1,790 @@
+//! K-mer generation, counting, encoding and alphabet handling.
+
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{BuildHasherDefault, Hash, Hasher};
+use std::str::FromStr;
+use rayon::prelude::*;
+use crate::alphabet::Alphabet;
+use crate::error::KmerError;
+use crate::io::{FastqRecord, detect_quality_encoding};
+use crate::nthash::NtHash;
+
+/// Generating K-mers
+/// writing a function to generate k-mers from a given DNA sequence
+/// A k of zero or longer than the sequence yields no k-mers rather than underflowing.
+pub fn generate_kmers(dna_sequence: &str, k: usize) -> Vec<String> {
+    let mut kmers = Vec::new();
+    if k == 0 || k > dna_sequence.len() {
+        return kmers;
+    }
+    for i in 0..=dna_sequence.len() - k {
+        kmers.push(dna_sequence[i..i + k].to_string());
+    }
+    kmers
+}
+
+/// Check that a k-mer size lies between 1 and `max`, typically the length of the longest
+/// sequence it will be applied to.
+pub fn check_k(k: usize, max: usize) -> Result<(), KmerError> {
+    if k == 0 || k > max {
+        return Err(KmerError::InvalidK { k, constraint: format!("must be between 1 and {}", max) });
+    }
+    Ok(())
+}
+
+/// generate_kmers for callers that want a bad k reported rather than silently yielding nothing:
+/// errors unless k is between 1 and the sequence length.
+pub fn try_generate_kmers(dna_sequence: &str, k: usize) -> Result<Vec<String>, KmerError> {
+    check_k(k, dna_sequence.len())?;
+    Ok(generate_kmers(dna_sequence, k))
+}
+
+/// Fraction of G and C among the A/C/G/T bases of a sequence, case-insensitively; N and other
+/// characters are ignored. A sequence without any A/C/G/T gives 0.0.
+pub fn gc_content(seq: &str) -> f64 {
+    let composition = base_composition(seq);
+    let count = |base| *composition.get(&base).unwrap_or(&0);
+    let acgt = count('A') + count('C') + count('G') + count('T');
+    if acgt == 0 {
+        return 0.0;
+    }
+    (count('G') + count('C')) as f64 / acgt as f64
+}
+
+/// Counts of A, C, G, T and N (case-insensitive) in a sequence; any other non-whitespace
+/// character is counted under '?'.
+pub fn base_composition(seq: &str) -> HashMap<char, usize> {
+    let mut composition = HashMap::new();
+    for c in seq.chars().filter(|c| !c.is_whitespace()) {
+        let key = match c.to_ascii_uppercase() {
+            base @ ('A' | 'C' | 'G' | 'T' | 'N') => base,
+            _ => '?',
+        };
+        *composition.entry(key).or_insert(0) += 1;
+    }
+    composition
+}
+
+/// How sanitize_sequence_with treats characters other than A/C/G/T.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidBasePolicy {
+    /// Replace them with 'N' so k-mers spanning them can be skipped.
+    MaskAsN,
+    /// Remove them, joining the flanking bases.
+    Drop,
+}
+
+/// Uppercase a sequence, strip whitespace, and turn anything outside A/C/G/T into 'N', keeping
+/// positions intact so generate_kmers_filtered skips k-mers that span an ambiguous base.
+pub fn sanitize_sequence(seq: &str) -> String {
+    sanitize_sequence_with(seq, InvalidBasePolicy::MaskAsN)
+}
+
+/// sanitize_sequence with a choice of what happens to non-ACGT characters.
+pub fn sanitize_sequence_with(seq: &str, policy: InvalidBasePolicy) -> String {
+    seq.chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_ascii_uppercase())
+        .filter_map(|c| match (c, policy) {
+            ('A' | 'C' | 'G' | 'T', _) => Some(c),
+            (_, InvalidBasePolicy::MaskAsN) => Some('N'),
+            (_, InvalidBasePolicy::Drop) => None,
+        })
+        .collect()
+}
+
+/// What validate_sequence does with ambiguous bases: 'N' and the other IUPAC codes
+/// (R, Y, S, W, K, M, B, D, H, V).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmbiguousBasePolicy {
+    /// Keep them as 'N' so generate_kmers_filtered skips the k-mers spanning them.
+    Skip,
+    /// Reject the sequence.
+    Error,
+}
+
+/// Strict counterpart of sanitize_sequence: uppercase the sequence and strip whitespace
+/// (including trailing newlines), turn ambiguous bases into 'N' or reject them according to
+/// `policy`, and reject any other character. Errors name the character and its 1-based
+/// position in `seq`.
+pub fn validate_sequence(seq: &str, policy: AmbiguousBasePolicy) -> Result<String, KmerError> {
+    Alphabet::dna().validate(seq, policy)
+}
+
+/// Like generate_kmers, but never emits a k-mer containing a character outside {A,C,G,T}
+/// (case-sensitive, so sanitize first to keep soft-masked bases).
+pub fn generate_kmers_filtered(dna_sequence: &str, k: usize) -> Vec<String> {
+    let mut kmers = Vec::new();
+    if k == 0 || k > dna_sequence.len() {
+        return kmers;
+    }
+    let bytes = dna_sequence.as_bytes();
+    // Index of the first valid base of the current run of valid bases.
+    let mut run_start = 0;
+    for (i, base) in bytes.iter().enumerate() {
+        if !matches!(base, b'A' | b'C' | b'G' | b'T') {
+            run_start = i + 1;
+        } else if i + 1 - run_start >= k {
+            kmers.push(dna_sequence[i + 1 - k..=i].to_string());
+        }
+    }
+    kmers
+}
+
+/// Split a sequence into slices of `chunk_size` that overlap by k-1 bases,
+/// so every k-mer lies entirely within at least one chunk and chunks can be counted independently.
+/// Chunks shorter than k could hold no k-mer, so chunk_size is raised to at least k.
+pub fn chunk_sequence(seq: &str, chunk_size: usize, k: usize) -> Vec<&str> {
+    let overlap = k.saturating_sub(1);
+    let size = chunk_size.max(k).max(1);
+    let step = size - overlap;
+
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < seq.len() {
+        let end = (start + size).min(seq.len());
+        chunks.push(&seq[start..end]);
+        if end == seq.len() {
+            break;
+        }
+        start += step;
+    }
+    chunks
+}
+
+/// Counting K-mers Using Hashing
+/// use a HashMap to count the frequency of each k-mer, as Strings or packed Kmers
+pub fn count_kmers<K: Eq + Hash>(kmers: Vec<K>) -> HashMap<K, usize> {
+    let mut kmer_counts = HashMap::new();
+    for kmer in kmers {
+        *kmer_counts.entry(kmer).or_insert(0) += 1;
+    }
+    kmer_counts
+}
+
+/// Drop the k-mers counted fewer than `min_count` or more than `max_count` times, returning how
+/// many were dropped. Filtering out rare k-mers before building a graph keeps most sequencing
+/// errors out of it; an upper bound drops repeats.
+pub fn filter_counts<K: Eq + Hash>(kmer_counts: &mut HashMap<K, usize>, min_count: usize, max_count: Option<usize>) -> usize {
+    let before = kmer_counts.len();
+    kmer_counts.retain(|_, &mut count| count >= min_count && max_count.is_none_or(|max| count <= max));
+    before - kmer_counts.len()
+}
+
+/// The order in which k-mer counts are written and displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CountOrder {
+    /// Lexicographically by k-mer.
+    #[default]
+    Kmer,
+    /// By count descending, with ties broken lexicographically.
+    Count,
+}
+
+/// The entries of a count table in `order`, so output is the same from run to run whatever the
+/// HashMap's iteration order. Packed codes sort in the same order as the k-mers they encode.
+pub fn sorted_counts<K: Ord>(kmer_counts: &HashMap<K, usize>, order: CountOrder) -> Vec<(&K, usize)> {
+    let mut sorted: Vec<(&K, usize)> = kmer_counts.iter().map(|(kmer, &count)| (kmer, count)).collect();
+    match order {
+        CountOrder::Kmer => sorted.sort_unstable(),
+        CountOrder::Count => sorted.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0))),
+    }
+    sorted
+}
+
+/// The n most frequent k-mers, by count descending with ties broken lexicographically.
+pub fn top_kmers(kmer_counts: &HashMap<String, usize>, n: usize) -> Vec<(String, usize)> {
+    let mut ranked = sorted_counts(kmer_counts, CountOrder::Count);
+    ranked.truncate(n);
+    ranked.into_iter().map(|(kmer, count)| (kmer.clone(), count)).collect()
+}
+
+/// Size of one k-mer count table, for comparing runs at different k.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KmerSummary {
+    /// K-mer size the table was counted with.
+    pub k: usize,
+    /// Number of different k-mers.
+    pub distinct: usize,
+    /// Number of k-mers seen exactly once.
+    pub unique: usize,
+    /// Number of k-mer occurrences, the sum of all counts.
+    pub total: usize,
+}
+
+impl KmerSummary {
+    /// Summarize a count table counted with k-mer size `k`.
+    pub fn from_counts(k: usize, kmer_counts: &HashMap<String, usize>) -> Self {
+        KmerSummary {
+            k,
+            distinct: kmer_counts.len(),
+            unique: kmer_counts.values().filter(|&&count| count == 1).count(),
+            total: kmer_counts.values().sum(),
+        }
+    }
+}
+
+/// Count k-mers straight from the sequence across threads: each rayon worker folds its share of
+/// start positions into its own HashMap, and the maps are merged at the end. No Vec of every
+/// k-mer is materialized, and the result equals count_kmers(generate_kmers(seq, k)).
+pub fn count_kmers_parallel(dna_sequence: &str, k: usize) -> HashMap<String, usize> {
+    if k == 0 || k > dna_sequence.len() {
+        return HashMap::new();
+    }
+    (0..=dna_sequence.len() - k)
+        .into_par_iter()
+        .fold(HashMap::new, |mut counts: HashMap<String, usize>, i| {
+            let kmer = &dna_sequence[i..i + k];
+            match counts.get_mut(kmer) {
+                Some(count) => *count += 1,
+                None => {
+                    counts.insert(kmer.to_string(), 1);
+                }
+            }
+            counts
+        })
+        .reduce(HashMap::new, |mut merged, counts| {
+            for (kmer, count) in counts {
+                *merged.entry(kmer).or_insert(0) += count;
+            }
+            merged
+        })
+}
+
+/// Bases per chunk handed to a rayon worker by count_records_parallel.
+pub const PARALLEL_CHUNK_SIZE: usize = 1 << 16;
+
+/// Count k-mers across the records of a file in parallel, as the main pipeline counts them:
+/// k-mers spanning non-ACGT characters are skipped and none cross a record boundary. Each record
+/// is split by chunk_sequence into PARALLEL_CHUNK_SIZE-base chunks overlapping by k-1 bases, so
+/// every k-mer falls in exactly one chunk; workers count chunks into local HashMaps, which are
+/// then merged. With `canonical` set, k-mers are counted under canonical_kmer.
+pub fn count_records_parallel(sequences: &[String], k: usize, canonical: bool) -> HashMap<String, usize> {
+    count_records_parallel_in(sequences, k, &Alphabet::dna(), canonical)
+}
+
+/// count_records_parallel for sequences of any alphabet: k-mers spanning anything but its
+/// symbols are skipped, and `canonical` counts them under Alphabet::canonical, which leaves
+/// k-mers of an alphabet without a complement as they are. DNA k-mers are keyed by their NtHash
+/// rolling hashes while counting, so each window is hashed in O(1) and only distinct k-mers
+/// are copied into Strings.
+pub fn count_records_parallel_in(sequences: &[String], k: usize, alphabet: &Alphabet, canonical: bool) -> HashMap<String, usize> {
+    if k == 0 {
+        return HashMap::new();
+    }
+    let chunks: Vec<&str> = sequences
+        .iter()
+        .flat_map(|sequence| chunk_sequence(sequence, PARALLEL_CHUNK_SIZE, k))
+        .collect();
+    if *alphabet == Alphabet::dna() {
+        return count_hashed_chunks(chunks, k, canonical);
+    }
+    chunks
+        .into_par_iter()
+        .fold(HashMap::new, |mut counts: HashMap<String, usize>, chunk| {
+            for kmer in alphabet.kmers(chunk, k) {
+                let kmer = if canonical { alphabet.canonical(&kmer) } else { kmer };
+                *counts.entry(kmer).or_insert(0) += 1;
+            }
+            counts
+        })
+        .reduce(HashMap::new, |mut merged, counts| {
+            for (kmer, count) in counts {
+                *merged.entry(kmer).or_insert(0) += count;
+            }
+            merged
+        })
+}
+
+// A k-mer of a sequence keyed by its NtHash, so hash maps never rehash its bytes; under
+// `canonical` it also equals its reverse complement, which NtHash gives the same hash
+struct HashedKmer<'a> {
+    hash: u64,
+    kmer: &'a str,
+    canonical: bool,
+}
+
+impl Hash for HashedKmer<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+impl PartialEq for HashedKmer<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash
+            && (self.kmer == other.kmer
+                || self.canonical
+                    && self.kmer.len() == other.kmer.len()
+                    && self.kmer.bytes().zip(other.kmer.bytes().rev()).all(|(a, b)| complement_base(a) == b))
+    }
+}
+
+impl Eq for HashedKmer<'_> {}
+
+// Hasher handing on the hash a HashedKmer writes
+#[derive(Default)]
+struct PassThroughHasher(u64);
+
+impl Hasher for PassThroughHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = self.0.rotate_left(8) ^ byte as u64;
+        }
+    }
+
+    fn write_u64(&mut self, hash: u64) {
+        self.0 = hash;
+    }
+}
+
+type HashedCounts<'a> = HashMap<HashedKmer<'a>, usize, BuildHasherDefault<PassThroughHasher>>;
+
+// count_records_parallel_in for DNA: rolling-hash the k-mers of each chunk, then copy out the
+// distinct ones, under canonical_kmer with `canonical`
+fn count_hashed_chunks(chunks: Vec<&str>, k: usize, canonical: bool) -> HashMap<String, usize> {
+    chunks
+        .into_par_iter()
+        .fold(HashedCounts::default, |mut counts, chunk| {
+            for (start, hash) in NtHash::new(chunk, k, canonical) {
+                *counts.entry(HashedKmer { hash, kmer: &chunk[start..start + k], canonical }).or_insert(0) += 1;
+            }
+            counts
+        })
+        .reduce(HashedCounts::default, |mut merged, counts| {
+            for (kmer, count) in counts {
+                *merged.entry(kmer).or_insert(0) += count;
+            }
+            merged
+        })
+        .into_iter()
+        .map(|(key, count)| (if canonical { canonical_kmer(key.kmer) } else { key.kmer.to_string() }, count))
+        .collect()
+}
+
+// Complement of an uppercase base, other bytes unchanged
+fn complement_base(base: u8) -> u8 {
+    match base {
+        b'A' => b'T',
+        b'T' => b'A',
+        b'C' => b'G',
+        b'G' => b'C',
+        other => other,
+    }
+}
+
+/// Count k-mers strand-independently: each k-mer is tallied under the lexicographically smaller
+/// of itself and its reverse complement. Palindromic k-mers are their own canonical form and are
+/// counted once per occurrence (odd-length DNA k-mers can never be palindromic).
+pub fn count_canonical_kmers(kmers: Vec<String>) -> HashMap<String, usize> {
+    let mut kmer_counts = HashMap::new();
+    for kmer in kmers {
+        *kmer_counts.entry(canonical_kmer(&kmer)).or_insert(0) += 1;
+    }
+    kmer_counts
+}
+
+/// Hash a k-mer together with a seed, giving a family of independent hash functions.
+pub fn seeded_hash(kmer: &str, seed: u64) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    seed.hash(&mut hasher);
+    kmer.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Find approximately the most frequent k-mers in a stream of sequences with the Misra-Gries
+/// algorithm, in one pass and using at most `capacity` counters. Every k-mer occurring more than
+/// total / (capacity + 1) times is guaranteed to be kept; kept counts are lower bounds.
+pub fn frequent_kmers_stream(seqs: impl Iterator<Item = String>, k: usize, capacity: usize) -> HashMap<String, usize> {
+    let mut counters: HashMap<String, usize> = HashMap::new();
+    if k == 0 || capacity == 0 {
+        return counters;
+    }
+    for seq in seqs {
+        if seq.len() < k {
+            continue;
+        }
+        for i in 0..=seq.len() - k {
+            let kmer = &seq[i..i + k];
+            if let Some(count) = counters.get_mut(kmer) {
+                *count += 1;
+            } else if counters.len() < capacity {
+                counters.insert(kmer.to_string(), 1);
+            } else {
+                // No free counter: decrement all, which forgets k-mers that drop to zero.
+                counters.retain(|_, count| {
+                    *count -= 1;
+                    *count > 0
+                });
+            }
+        }
+    }
+    counters
+}
+
+/// Compute randstrobe seeds (strobemers) of `n_strobes` k-mers for long-read seeding.
+/// The first strobe is the k-mer at each position; every following strobe is the k-mer in the
+/// window [w_min, w_max] bases after the previous strobe that minimizes (seed hash + k-mer hash),
+/// so the choice depends on the strobes already picked. Returns (start position, seed hash).
+/// Seeds stop once the next strobe's window would start past the last k-mer.
+pub fn strobemers(seq: &str, k: usize, w_min: usize, w_max: usize, n_strobes: usize) -> Vec<(usize, u64)> {
+    if k == 0 || k > seq.len() || n_strobes == 0 || w_min > w_max {
+        return Vec::new();
+    }
+    let hashes: Vec<u64> = (0..=seq.len() - k).map(|i| seeded_hash(&seq[i..i + k], 0)).collect();
+    let last = hashes.len() - 1;
+
+    let mut seeds = Vec::new();
+    'positions: for start in 0..hashes.len() {
+        let mut seed = hashes[start];
+        let mut previous = start;
+        for _ in 1..n_strobes {
+            let window_start = previous + w_min.max(1);
+            if window_start > last {
+                break 'positions;
+            }
+            let window_end = (previous + w_max.max(1)).min(last);
+            let chosen = (window_start..=window_end)
+                .min_by_key(|&p| seed.wrapping_add(hashes[p]))
+                .unwrap();
+            seed = seed.rotate_left(21) ^ hashes[chosen];
+            previous = chosen;
+        }
+        seeds.push((start, seed));
+    }
+    seeds
+}
+
+/// Index every k-mer of a sequence by the positions where it starts.
+pub fn kmer_positions(dna_sequence: &str, k: usize) -> HashMap<String, Vec<usize>> {
+    let mut positions: HashMap<String, Vec<usize>> = HashMap::new();
+    if k == 0 || k > dna_sequence.len() {
+        return positions;
+    }
+    for (i, kmer) in generate_kmers(dna_sequence, k).into_iter().enumerate() {
+        positions.entry(kmer).or_default().push(i);
+    }
+    positions
+}
+
+/// Choose a minimal set of k-mers (with start positions) whose spans cover every base of `seq`.
+/// Greedily tiling end-to-end is optimal for covering an interval: it uses ceil(len / k) k-mers,
+/// and only the last one is shifted left to end at the sequence end, so overlaps stay below k.
+pub fn minimal_kmer_cover(seq: &str, k: usize) -> Vec<(usize, String)> {
+    if k == 0 || k > seq.len() {
+        return Vec::new();
+    }
+    let mut cover = Vec::new();
+    let mut start = 0;
+    while start + k < seq.len() {
+        cover.push((start, seq[start..start + k].to_string()));
+        start += k;
+    }
+    let last = seq.len() - k;
+    cover.push((last, seq[last..].to_string()));
+    cover
+}
+
+/// Reverse complement a DNA string (A<->T, C<->G), preserving case and leaving other characters as-is.
+pub fn reverse_complement(kmer: &str) -> String {
+    kmer.chars()
+        .rev()
+        .map(|base| match base {
+            'A' => 'T',
+            'T' => 'A',
+            'C' => 'G',
+            'G' => 'C',
+            'a' => 't',
+            't' => 'a',
+            'c' => 'g',
+            'g' => 'c',
+            other => other,
+        })
+        .collect()
+}
+
+/// The canonical form of a k-mer: the lexicographically smaller of itself and its reverse complement.
+pub fn canonical_kmer(kmer: &str) -> String {
+    let rc = reverse_complement(kmer);
+    if rc.as_str() < kmer {
+        rc
+    } else {
+        kmer.to_string()
+    }
+}
+
+// Build a residue -> class mapping from groups of amino acids, each class written as the
+// group's first residue.
+fn alphabet_from_groups(groups: &[&str]) -> HashMap<char, char> {
+    let mut mapping = HashMap::new();
+    for group in groups {
+        if let Some(class) = group.chars().next() {
+            for residue in group.chars() {
+                mapping.insert(residue, class);
+            }
+        }
+    }
+    mapping
+}
+
+/// Murphy et al. (2000) 8-letter reduction by physicochemical similarity.
+pub fn murphy8_alphabet() -> HashMap<char, char> {
+    alphabet_from_groups(&["LVIMC", "AG", "ST", "P", "FYW", "EDNQ", "KR", "H"])
+}
+
+/// Dayhoff's 6 substitution classes.
+pub fn dayhoff6_alphabet() -> HashMap<char, char> {
+    alphabet_from_groups(&["AGPST", "C", "DENQ", "HKR", "ILMV", "FWY"])
+}
+
+/// Rewrite a protein sequence in a reduced alphabet; residues without a class become 'X'.
+pub fn reduce_alphabet(seq: &str, mapping: &HashMap<char, char>) -> String {
+    seq.chars()
+        .map(|residue| *mapping.get(&residue.to_ascii_uppercase()).unwrap_or(&'X'))
+        .collect()
+}
+
+/// Count protein k-mers after reducing the sequence to the given alphabet, which makes
+/// k-mer matches tolerant of conservative substitutions between remote homologs.
+/// Windows containing an unclassified residue ('X' after reduction) are skipped, as
+/// generate_kmers_filtered skips windows with non-ACGT bases.
+pub fn count_reduced_kmers(seq: &str, mapping: &HashMap<char, char>, k: usize) -> HashMap<String, usize> {
+    let reduced = reduce_alphabet(seq, mapping);
+    count_kmers(reduced.split('X').flat_map(|run| generate_kmers(run, k)).collect())
+}
+
+/// Map a nucleotide to its index in A, C, G, T order, or None for anything else.
+pub fn base_index(base: u8) -> Option<usize> {
+    match base {
+        b'A' | b'a' => Some(0),
+        b'C' | b'c' => Some(1),
+        b'G' | b'g' => Some(2),
+        b'T' | b't' => Some(3),
+        _ => None,
+    }
+}
+
+/// Pack a k-mer into a u64 using 2 bits per base (A=00, C=01, G=10, T=11), first base in the
+/// highest bits. Returns None for k-mers longer than 32 or containing a non-ACGT character.
+pub fn encode_kmer(kmer: &str) -> Option<u64> {
+    if kmer.len() > 32 {
+        return None;
+    }
+    kmer.bytes().try_fold(0u64, |code, base| base_index(base).map(|b| (code << 2) | b as u64))
+}
+
+/// Unpack a 2-bit encoded k-mer of length k back into a string.
+pub fn decode_kmer(code: u64, k: usize) -> String {
+    (0..k)
+        .map(|i| ['A', 'C', 'G', 'T'][((code >> (2 * (k - 1 - i))) & 3) as usize])
+        .collect()
+}
+
+/// Count k-mers over their 2-bit packed codes (see encode_kmer), rolling the code along the
+/// sequence so no per-k-mer String is allocated. K-mers containing a non-ACGT character are
+/// skipped; k must be between 1 and 32.
+pub fn count_kmers_packed(dna_sequence: &str, k: usize) -> HashMap<u64, usize> {
+    let mut counts = HashMap::new();
+    if k == 0 || k > 32 {
+        return counts;
+    }
+    let mask = if k == 32 { u64::MAX } else { (1u64 << (2 * k)) - 1 };
+    let mut code = 0u64;
+    // Number of consecutive valid bases ending at the current position.
+    let mut valid = 0;
+    for base in dna_sequence.bytes() {
+        match base_index(base) {
+            Some(b) => {
+                code = ((code << 2) | b as u64) & mask;
+                valid += 1;
+                if valid >= k {
+                    *counts.entry(code).or_insert(0) += 1;
+                }
+            }
+            None => valid = 0,
+        }
+    }
+    counts
+}
+
+/// A k-mer of up to 32 bases packed 2 bits per base into a u64 (see encode_kmer).
+/// The length isn't stored, so methods that need it take k explicitly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Kmer(pub u64);
+
+impl FromStr for Kmer {
+    type Err = KmerError;
+
+    fn from_str(kmer: &str) -> Result<Self, Self::Err> {
+        encode_kmer(kmer).map(Kmer).ok_or_else(|| KmerError::InvalidKmer(kmer.to_string()))
+    }
+}
+
+impl Kmer {
+    /// Decode the first k bases back to a string.
+    pub fn to_string(self, k: usize) -> String {
+        decode_kmer(self.0, k)
+    }
+
+    /// Complement every base (A<->T and C<->G are bitwise inverses) and reverse their order.
+    pub fn reverse_complement(self, k: usize) -> Kmer {
+        let mut code = !self.0;
+        let mut reversed = 0;
+        for _ in 0..k {
+            reversed = (reversed << 2) | (code & 3);
+            code >>= 2;
+        }
+        Kmer(reversed)
+    }
+
+    /// The smaller of the k-mer and its reverse complement; since codes preserve
+    /// lexicographic order this matches the string-based canonical_kmer.
+    pub fn canonical(self, k: usize) -> Kmer {
+        self.min(self.reverse_complement(k))
+    }
+
+    /// Whether the k-mer equals its own reverse complement.
+    pub fn is_palindrome(self, k: usize) -> bool {
+        self == self.reverse_complement(k)
+    }
+}
+
+/// Every k-mer of a sequence packed into a Kmer, skipping k-mers that contain a non-ACGT base,
+/// so the result matches generate_kmers_filtered without allocating a String per k-mer.
+/// Empty unless 1 <= k <= 32.
+pub fn generate_packed_kmers(dna_sequence: &str, k: usize) -> Vec<Kmer> {
+    let mut kmers = Vec::new();
+    if k == 0 || k > 32 {
+        return kmers;
+    }
+    let mask = if k == 32 { u64::MAX } else { (1u64 << (2 * k)) - 1 };
+    let mut code = 0u64;
+    // Number of consecutive valid bases ending at the current position.
+    let mut valid = 0;
+    for base in dna_sequence.bytes() {
+        match base_index(base) {
+            Some(b) => {
+                code = ((code << 2) | b as u64) & mask;
+                valid += 1;
+                if valid >= k {
+                    kmers.push(Kmer(code));
+                }
+            }
+            None => valid = 0,
+        }
+    }
+    kmers
+}
+
+/// A k-mer representation a De Bruijn graph can be built over: each k-mer splits into the
+/// (k-1)-mer nodes of its prefix and suffix. Implemented for String and the packed Kmer.
+pub trait KmerNode: Clone + Eq + Hash + Ord {
+    /// The first k-1 bases.
+    fn prefix(&self, k: usize) -> Self;
+    /// The last k-1 bases.
+    fn suffix(&self, k: usize) -> Self;
+}
+
+impl KmerNode for String {
+    fn prefix(&self, k: usize) -> Self {
+        self[..k - 1].to_string()
+    }
+
+    fn suffix(&self, k: usize) -> Self {
+        self[self.len() + 1 - k..].to_string()
+    }
+}
+
+impl KmerNode for Kmer {
+    fn prefix(&self, _k: usize) -> Self {
+        Kmer(self.0 >> 2)
+    }
+
+    fn suffix(&self, k: usize) -> Self {
+        Kmer(self.0 & ((1u64 << (2 * (k - 1))) - 1))
+    }
+}
+
+/// Count k-mers across FASTQ reads, weighting each occurrence by the probability that all of its
+/// bases were called correctly: the product of (1 - 10^(-Q/10)) over the k-mer's Phred scores.
+/// The quality encoding is detected from the records themselves.
+pub fn count_kmers_weighted(records: &[FastqRecord], k: usize) -> HashMap<String, f64> {
+    let encoding = detect_quality_encoding(records);
+    let mut weighted_counts = HashMap::new();
+    if k == 0 {
+        return weighted_counts;
+    }
+
+    for record in records {
+        let correctness: Vec<f64> = record
+            .phred_scores(encoding)
+            .into_iter()
+            .map(|q| 1.0 - 10f64.powf(-(q as f64) / 10.0))
+            .collect();
+        let len = record.sequence.len().min(correctness.len());
+        if len < k {
+            continue;
+        }
+        for i in 0..=len - k {
+            let weight: f64 = correctness[i..i + k].iter().product();
+            *weighted_counts.entry(record.sequence[i..i + k].to_string()).or_insert(0.0) += weight;
+        }
+    }
+    weighted_counts
+}
+
+/// Hard-mask (replace with N) the bases of a read whose Phred score is below `min_qual`, so
+/// generate_kmers_filtered and the counters skip every k-mer that covers one. Bases past the end
+/// of `scores` are kept.
+pub fn mask_low_quality(sequence: &str, scores: &[u8], min_qual: u8) -> String {
+    sequence
+        .chars()
+        .enumerate()
+        .map(|(i, base)| match scores.get(i) {
+            Some(&q) if q < min_qual => 'N',
+            _ => base,
+        })
+        .collect()
+}
+
+/// Hard-mask (replace with N) FASTQ reads with mask_low_quality, detecting the quality encoding
+/// from the records. Returns the masked sequences and how many k-mers the masking filtered out:
+/// windows of k A/C/G/T bases that now cover a masked base.
+pub fn mask_low_quality_records(records: &[FastqRecord], k: usize, min_qual: u8) -> (Vec<String>, usize) {
+    let encoding = detect_quality_encoding(records);
+    let mut filtered = 0;
+    let masked = records
+        .iter()
+        .map(|record| {
+            let masked = mask_low_quality(&record.sequence, &record.phred_scores(encoding), min_qual);
+            filtered += valid_windows(&record.sequence, k) - valid_windows(&masked, k);
+            masked
+        })
+        .collect();
+    (masked, filtered)
+}
+
+// Number of k-mers generate_kmers_filtered would emit for `sequence`
+fn valid_windows(sequence: &str, k: usize) -> usize {
+    if k == 0 {
+        return 0;
+    }
+    let mut run = 0;
+    let mut windows = 0;
+    for base in sequence.bytes() {
+        if matches!(base, b'A' | b'C' | b'G' | b'T') {
+            run += 1;
+            if run >= k {
+                windows += 1;
+            }
+        } else {
+            run = 0;
+        }
+    }
+    windows
+}
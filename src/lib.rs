@@ -0,0 +1,21 @@
+//! K-mer analysis library: counting and encoding k-mers, sketching and indexing them, building
+//! and traversing De Bruijn graphs, and reading, writing and plotting the results.
+
+pub mod alphabet;
+pub mod analysis;
+pub mod error;
+pub mod graph;
+pub mod index;
+pub mod io;
+pub mod kmer;
+pub mod mapping;
+pub mod minimizer;
+pub mod nthash;
+pub mod plot;
+pub mod report;
+pub mod simulate;
+pub mod stats;
+pub mod sketch;
+
+pub use alphabet::Alphabet;
+pub use error::KmerError;
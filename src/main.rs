@@ -1,272 +1,1849 @@
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::io;
-use rand::distributions::Uniform;
-use rand::distributions::Distribution;
-use std::fs::File;
-use std::io::{Write};
-use std::collections::HashMap;
-use plotters::prelude::*;
-use std::error::Error;
-use csv::Writer;
-use petgraph::graph::{UnGraph};
-use petgraph::dot::{Dot, Config};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{Duration, Instant};
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
+use indicatif::{ProgressBar, ProgressStyle};
+use log::{debug, error, info, warn, LevelFilter};
+use rayon::prelude::*;
+use kmer_analysis::alphabet::Alphabet;
+use kmer_analysis::error::KmerError;
+use kmer_analysis::graph::{create_petgraph, graph_metrics, DeBruijnGraph};
+use kmer_analysis::index::KmerIndex;
+use kmer_analysis::io::fasta::{count_kmers_in_file, read_fastq};
+use kmer_analysis::io::{
+    batch_samples, count_kmers_on_disk, count_kmers_packed_streaming, is_saved_index, load_index, read_sequences, save_graph, save_graph_dot,
+    save_kmer_counts, write_graph_metrics_to_csv, write_graph_metrics_to_json, write_graph_to_csv, write_graph_to_gfa,
+    write_graph_to_json, write_kmer_counts_jellyfish, write_kmer_counts_kmc, write_kmer_counts_to_csv,
+    write_kmer_counts_to_json, write_kmer_counts_to_tsv, write_kmer_summary_to_csv, write_matrix_to_csv, write_packed_kmer_counts_to_csv, write_fasta, write_fastq,
+    read_batch_manifest, write_batch_summary_to_csv, write_colored_graph_to_dot, write_kmer_diff_to_csv, write_sorted_kmer_counts_to_csv, write_to_file,
+    write_window_stats_to_csv, BatchSample, DiskCountOptions, FastqRecord, SampleSummary, SavedIndex,
+};
+use kmer_analysis::io::gzip::is_gzip_name;
+use kmer_analysis::kmer::{
+    self, base_composition, count_kmers, count_records_parallel, count_records_parallel_in, decode_kmer, filter_counts,
+    gc_content, mask_low_quality_records, sorted_counts, top_kmers, AmbiguousBasePolicy, CountOrder, KmerSummary,
+};
+use kmer_analysis::analysis::{compute_spectrum, diff_counts, error_threshold, estimate_genome, AssemblyStats, DiffSummary};
+use kmer_analysis::minimizer::{count_minimizers, record_minimizers};
+use kmer_analysis::plot::{kmer_histogram_svg, plot_count_scatter, plot_gc_windows, plot_kmer_histogram, plot_spectrum, spectrum_svg, SpectrumAxes};
+use kmer_analysis::report::{write_html_report, Report, ReportSection, ReportTable, REPORT_HISTOGRAM_MAX_KMERS};
+use kmer_analysis::stats::{sliding_window_stats, SequenceStats, WindowStats};
+use kmer_analysis::simulate::{generate_sequence, simulate_reads, MarkovModel, SequenceModel};
+use kmer_analysis::sketch::{
+    count_kmers_approximate, jaccard_similarity, pairwise_matrix, weighted_jaccard_similarity, MinHashSketch,
+};
 
 
+// Which file formats the counts and graph are written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Csv,
+    Json,
+    Both,
+}
 
-//Generating K-mers
-//writing a function to generate k-mers from a given DNA sequence
-fn generate_kmers(dna_sequence: &str, k: usize) -> Vec<String> {
-    let mut kmers = Vec::new();
-    for i in 0..=dna_sequence.len() - k {
-        kmers.push(dna_sequence[i..i + k].to_string());
-    }
-    kmers
+// File format of the `count` subcommand's output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CountFormat {
+    Csv,
+    Json,
+    // Tab-separated with a header row.
+    Tsv,
+    // `jellyfish dump` layout: >count, then the k-mer.
+    Jellyfish,
+    // KMC text dump: k-mer and count, tab-separated, no header.
+    Kmc,
+}
+
+// File format of the `graph` subcommand's output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum GraphFormat {
+    Dot,
+    Csv,
+    Json,
+    Gfa,
+}
+
+// File format of the `colored-graph` subcommand's output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ColoredGraphFormat {
+    // Nodes and edges labelled with their samples.
+    Dot,
+    // Unitig segments with an SM tag of their samples.
+    Gfa,
+}
+
+// What happens to N and other ambiguous bases in the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum AmbiguousBases {
+    // Keep them as N and skip the k-mers spanning them.
+    Skip,
+    // Exit with an error naming the first one.
+    Error,
+}
+
+// Order of the k-mer counts in text output and on stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SortBy {
+    // Lexicographically by k-mer.
+    Kmer,
+    // Most frequent first, ties broken by k-mer.
+    Count,
+}
+
+// What the `compare` subcommand's matrix holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CompareMetric {
+    // Mash distance, an estimate of the per-base mutation rate.
+    Distance,
+    // Estimated Jaccard similarity of the k-mer sets.
+    Jaccard,
+}
+
+// Image format of the full analysis's plots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum PlotFormat {
+    Png,
+    // Scalable vector graphics, for publication.
+    Svg,
+}
+
+// What the sequences are made of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum AlphabetKind {
+    // A, C, G and T.
+    Dna,
+    // A, C, G and U.
+    Rna,
+    // The 20 standard amino acids.
+    Protein,
+    // The symbols given with --symbols.
+    Custom,
+}
+
+// How log lines on stderr are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum LogFormat {
+    // `[LEVEL] message`.
+    Text,
+    // One JSON object per line with timestamp, level and message.
+    Json,
+}
+
+// Which plot the `plot` subcommand draws.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum PlotKind {
+    Histogram,
+    Spectrum,
+}
+
+// Command-line arguments. A subcommand runs one step of the analysis; without one, the full
+// analysis runs as before, with either --length or --input supplying the sequence unless
+// --interactive asks for the length and k on stdin.
+#[derive(Parser, Debug)]
+#[command(
+    version,
+    about = "K-mer counting, plotting and De Bruijn graph analysis",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[arg(long, global = true, help = "Number of threads used for counting (all cores by default)")]
+    threads: Option<usize>,
+
+    #[arg(long, global = true, help = "Gzip text outputs, adding .gz to their names (outputs named .gz always are)")]
+    gzip: bool,
+
+    #[arg(short, long, global = true, action = ArgAction::Count, help = "Log more detail: -v adds timings, -vv everything")]
+    verbose: u8,
+
+    #[arg(short, long, global = true, conflicts_with = "verbose", help = "Log errors only and hide progress spinners")]
+    quiet: bool,
+
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text, help = "Format of the log lines on stderr")]
+    log_format: LogFormat,
+
+    #[command(flatten)]
+    run: RunArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    #[command(about = "Generate a random DNA sequence")]
+    Generate(GenerateArgs),
+    #[command(about = "Count the k-mers of a sequence")]
+    Count(CountArgs),
+    #[command(about = "Build the De Bruijn graph of a sequence")]
+    Graph(GraphArgs),
+    #[command(about = "Build one De Bruijn graph of several samples, recording which samples each k-mer came from")]
+    ColoredGraph(ColoredGraphArgs),
+    #[command(about = "Assemble the contigs of the De Bruijn graph into a FASTA file")]
+    Contigs(ContigsArgs),
+    #[command(about = "Plot the k-mer histogram or spectrum of a sequence")]
+    Plot(PlotArgs),
+    #[command(about = "Compare samples by MinHash sketches of their canonical k-mers")]
+    Compare(CompareArgs),
+    #[command(about = "Join the k-mer counts of two samples, with fold changes and the k-mers unique to each")]
+    Diff(DiffArgs),
+    #[command(about = "Count the k-mers of every sample in a directory, glob or manifest, with a summary across them")]
+    Batch(BatchArgs),
+    #[command(about = "Print where each of the given k-mers occurs in a sequence")]
+    Query(QueryArgs),
+    #[command(about = "Report length, GC content, base composition and entropy, overall and in sliding windows")]
+    Stats(StatsArgs),
+    #[command(about = "Estimate genome size, coverage and error rate from the k-mer spectrum of reads")]
+    Estimate(EstimateArgs),
+    #[command(about = "Simulate error-containing short reads from a reference and write FASTQ")]
+    SimulateReads(SimulateReadsArgs),
+}
+
+// The alphabet of a subcommand's sequences.
+#[derive(Args, Debug)]
+struct AlphabetArgs {
+    #[arg(long, value_enum, default_value_t = AlphabetKind::Dna, help = "What the sequences are made of")]
+    alphabet: AlphabetKind,
+
+    #[arg(long, value_name = "SYMBOLS", required_if_eq("alphabet", "custom"), help = "Symbols of a custom alphabet, such as HP")]
+    symbols: Option<String>,
+}
+
+// Where a subcommand's sequence comes from: a file, or a random sequence of a given length.
+#[derive(Args, Debug)]
+struct SourceArgs {
+    #[arg(long, required_unless_present_any = ["length", "load_index"], conflicts_with = "length", help = "Read the sequence from a FASTA, FASTQ or raw text file")]
+    input: Option<PathBuf>,
+
+    #[arg(long, help = "Use a random DNA sequence of this length instead")]
+    length: Option<usize>,
+
+    #[arg(long, conflicts_with_all = ["input", "load_index"], help = "Seed for the random sequence, for reproducible runs")]
+    seed: Option<u64>,
+
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["input", "length"], help = "Reuse counts or a graph saved with --save-index instead of a sequence")]
+    load_index: Option<PathBuf>,
+
+    #[arg(long, value_enum, default_value_t = AmbiguousBases::Skip, help = "What to do with N and other ambiguous bases")]
+    ambiguous: AmbiguousBases,
+
+    #[arg(long, value_name = "Q", requires = "input", help = "Mask FASTQ bases with a Phred quality below Q, skipping the k-mers that cover them")]
+    min_qual: Option<u8>,
+
+    #[command(flatten)]
+    alphabet: AlphabetArgs,
+}
+
+#[derive(Args, Debug)]
+struct GenerateArgs {
+    #[arg(long, help = "Length of the sequence")]
+    length: usize,
+
+    #[arg(long, help = "Seed for the random generator, for reproducible runs")]
+    seed: Option<u64>,
+
+    #[arg(long, help = "File to write the sequence to (stdout if omitted)")]
+    output: Option<PathBuf>,
+
+    #[arg(long, value_name = "FRACTION", conflicts_with_all = ["base_freqs", "markov_from"], help = "Target GC content, between 0 and 1")]
+    gc: Option<f64>,
+
+    #[arg(long, value_name = "A,C,G,T", value_parser = parse_base_freqs, conflicts_with = "markov_from", help = "Relative base frequencies")]
+    base_freqs: Option<[f64; 4]>,
+
+    #[arg(long, value_name = "FILE", help = "Train a Markov chain on this sequence file and sample from it")]
+    markov_from: Option<PathBuf>,
+
+    #[arg(long, default_value_t = 3, requires = "markov_from", help = "Order of the Markov chain")]
+    order: usize,
+
+    #[command(flatten)]
+    alphabet: AlphabetArgs,
+}
+
+#[derive(Args, Debug)]
+struct CountArgs {
+    #[command(flatten)]
+    source: SourceArgs,
+
+    #[arg(short, long, required_unless_present = "load_index", help = "K-mer size")]
+    k: Option<usize>,
+
+    #[arg(long, help = "Print only the N most frequent k-mers")]
+    top: Option<usize>,
+
+    #[arg(long, conflicts_with_all = ["length", "load_index", "min_qual"], help = "Stream --input through a rolling 2-bit window instead of loading it (k <= 32)")]
+    streaming: bool,
+
+    #[arg(long, conflicts_with = "load_index", help = "Count each k-mer together with its reverse complement")]
+    canonical: bool,
+
+    #[arg(long, conflicts_with_all = ["streaming", "load_index"], help = "Count approximately in bounded memory, leaving out k-mers seen once")]
+    approx: bool,
+
+    #[arg(long, value_name = "W", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..), conflicts_with_all = ["streaming", "approx", "load_index"], help = "Count only the minimizer of each window of W k-mers")]
+    minimizers: Option<usize>,
+
+    #[arg(long, value_name = "SIZE", value_parser = parse_memory, default_value = "1G", requires = "approx", help = "Memory for --approx, in bytes or with a K, M or G suffix")]
+    memory: usize,
+
+    #[arg(long, conflicts_with_all = ["length", "load_index", "min_qual", "streaming", "approx", "minimizers", "top", "save_index"], help = "Count --input through temporary bucket files, for inputs whose k-mers don't fit in memory (k <= 32)")]
+    disk: bool,
+
+    #[arg(long, value_name = "PATH", requires = "disk", help = "Directory for --disk's temporary files (the system's by default)")]
+    tmp_dir: Option<PathBuf>,
+
+    #[arg(long, value_name = "SIZE", value_parser = parse_memory, default_value = "1G", requires = "disk", help = "Memory for --disk, in bytes or with a K, M or G suffix")]
+    memory_limit: usize,
+
+    #[arg(long, value_name = "FILE", help = "Also save the count table in binary, for --load-index")]
+    save_index: Option<PathBuf>,
+
+    #[arg(long, help = "File to write the counts to (tab-separated on stdout if omitted)")]
+    output: Option<PathBuf>,
+
+    #[arg(long, visible_alias = "output-format", value_enum, default_value_t = CountFormat::Csv, help = "Format of the --output file")]
+    format: CountFormat,
+
+    #[arg(long, value_enum, default_value_t = SortBy::Kmer, help = "Order of the counts in the output (JSON is always by k-mer)")]
+    sort: SortBy,
+}
+
+// How the `graph` and `contigs` subcommands build and simplify the De Bruijn graph.
+#[derive(Args, Debug)]
+struct GraphBuildArgs {
+    #[command(flatten)]
+    source: SourceArgs,
+
+    #[arg(short, long, required_unless_present = "load_index", help = "K-mer size")]
+    k: Option<usize>,
+
+    #[arg(long, value_name = "MAX_LEN", help = "Clip dead-end branches of at most MAX_LEN nodes")]
+    remove_tips: Option<usize>,
+
+    #[arg(long, help = "Collapse parallel paths of equal length, keeping the best covered")]
+    pop_bubbles: bool,
+
+    #[arg(long, value_name = "W", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..), conflicts_with = "load_index", help = "Build the graph from only the minimizer of each window of W k-mers")]
+    minimizers: Option<usize>,
+
+    #[arg(long, value_name = "N", help = "Leave out k-mers seen fewer than N times")]
+    min_count: Option<usize>,
+
+    #[arg(long, value_name = "N", help = "Leave out k-mers seen more than N times")]
+    max_count: Option<usize>,
+
+    #[arg(long, help = "Leave out k-mers below the error threshold detected from the k-mer spectrum")]
+    drop_errors: bool,
+
+    #[arg(long, value_name = "N", help = "Drop graph edges seen fewer than N times, also from a saved graph")]
+    min_coverage: Option<usize>,
+}
+
+#[derive(Args, Debug)]
+struct GraphArgs {
+    #[command(flatten)]
+    build: GraphBuildArgs,
+
+    #[arg(long, help = "File to write the graph to")]
+    output: PathBuf,
+
+    #[arg(long, value_enum, default_value_t = GraphFormat::Dot, help = "Format of the --output file")]
+    format: GraphFormat,
+
+    #[arg(long, value_name = "FILE", help = "Also save the (simplified) graph in binary, for --load-index")]
+    save_index: Option<PathBuf>,
+
+    #[arg(long, value_name = "FILE", help = "Print degree, component and path metrics and write them to FILE (JSON if it ends in .json, CSV otherwise)")]
+    metrics: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct ColoredGraphArgs {
+    #[arg(required_unless_present = "manifest", conflicts_with = "manifest", help = "Sequence files, one sample each, named after the file")]
+    inputs: Vec<PathBuf>,
+
+    #[arg(long, value_name = "TSV", help = "File listing one sample per line: a path, or a name and a path separated by a tab")]
+    manifest: Option<PathBuf>,
+
+    #[arg(short, long, help = "K-mer size")]
+    k: usize,
+
+    #[arg(long, value_enum, default_value_t = AmbiguousBases::Skip, help = "What to do with N and other ambiguous bases")]
+    ambiguous: AmbiguousBases,
+
+    #[arg(long, value_name = "MAX_LEN", help = "Clip dead-end branches of at most MAX_LEN nodes")]
+    remove_tips: Option<usize>,
+
+    #[arg(long, help = "Collapse parallel paths of equal length, keeping the best covered")]
+    pop_bubbles: bool,
+
+    #[arg(long, help = "File to write the graph to")]
+    output: PathBuf,
+
+    #[arg(long, value_enum, default_value_t = ColoredGraphFormat::Dot, help = "Format of the --output file")]
+    format: ColoredGraphFormat,
+}
+
+#[derive(Args, Debug)]
+struct QueryArgs {
+    #[command(flatten)]
+    source: SourceArgs,
+
+    #[arg(required_unless_present = "kmers_file", help = "K-mers to look up, all of the same length")]
+    kmers: Vec<String>,
+
+    #[arg(long, value_name = "FILE", help = "Also look up the k-mers listed one per line in FILE")]
+    kmers_file: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct StatsArgs {
+    #[command(flatten)]
+    source: SourceArgs,
+
+    #[arg(long, default_value_t = 1000, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..), help = "Bases per sliding window")]
+    window: usize,
+
+    #[arg(long, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..), help = "Bases between window starts (the window size if omitted)")]
+    step: Option<usize>,
+
+    #[arg(long, help = "CSV file to write the statistics of every window to")]
+    output: Option<PathBuf>,
+
+    #[arg(long, value_name = "PNG", help = "PNG file to plot GC content along each record in")]
+    plot: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct ContigsArgs {
+    #[command(flatten)]
+    build: GraphBuildArgs,
+
+    #[arg(long, help = "FASTA file to write the contigs to")]
+    output: PathBuf,
+
+    #[arg(long, value_name = "LEN", default_value_t = 0, help = "Leave out contigs shorter than LEN bases")]
+    min_length: usize,
+
+    #[arg(long, help = "Walk contigs through branches along the best-covered edges instead of stopping at them")]
+    greedy: bool,
+}
+
+#[derive(Args, Debug)]
+struct PlotArgs {
+    #[command(flatten)]
+    source: SourceArgs,
+
+    #[arg(short, long, required_unless_present = "load_index", help = "K-mer size")]
+    k: Option<usize>,
+
+    #[arg(long, help = "PNG file to draw the plot in")]
+    output: PathBuf,
+
+    #[arg(long, conflicts_with = "load_index", help = "Count each k-mer together with its reverse complement")]
+    canonical: bool,
+
+    #[arg(long, value_enum, default_value_t = PlotKind::Histogram, help = "Which plot to draw")]
+    kind: PlotKind,
+
+    #[arg(long, default_value_t = 640, help = "Width of the histogram in pixels")]
+    width: u32,
+
+    #[arg(long, default_value_t = 480, help = "Height of the histogram in pixels")]
+    height: u32,
+
+    #[arg(long, help = "Draw the spectrum's multiplicity axis on a log scale")]
+    log_x: bool,
+
+    #[arg(long, help = "Draw the spectrum's distinct k-mer axis on a log scale")]
+    log_y: bool,
+}
+
+#[derive(Args, Debug)]
+struct CompareArgs {
+    #[arg(required = true, num_args = 2.., help = "Sequence files (FASTA, FASTQ or raw text) or counts saved with --save-index")]
+    inputs: Vec<PathBuf>,
+
+    #[arg(short, long, default_value_t = 21, help = "K-mer size")]
+    k: usize,
+
+    #[arg(long, default_value_t = 1000, help = "Number of hashes kept in each sketch")]
+    sketch_size: usize,
+
+    #[arg(long, value_enum, default_value_t = CompareMetric::Distance, help = "Matrix of Mash distances or Jaccard similarities")]
+    metric: CompareMetric,
+
+    #[arg(long, help = "CSV file to write the matrix to (tab-separated on stdout if omitted)")]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct DiffArgs {
+    #[arg(help = "Sample A: a sequence file (FASTA, FASTQ or raw text) or counts saved with --save-index")]
+    sample_a: PathBuf,
+
+    #[arg(help = "Sample B, likewise")]
+    sample_b: PathBuf,
+
+    #[arg(short, long, help = "K-mer size [default: 21, or that of saved counts]")]
+    k: Option<usize>,
+
+    #[arg(long, help = "Count each k-mer of a sequence file together with its reverse complement")]
+    canonical: bool,
+
+    #[arg(long, help = "CSV file to write the joined counts to (tab-separated on stdout if omitted)")]
+    output: Option<PathBuf>,
+
+    #[arg(long, value_name = "FILE", help = "Scatter plot of the counts in A against B (SVG if the name ends in .svg)")]
+    plot: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct BatchArgs {
+    #[arg(required_unless_present = "manifest", conflicts_with = "manifest", help = "Directory of sequence files, or a quoted glob of them such as 'reads/*.fa'")]
+    inputs: Option<String>,
+
+    #[arg(long, value_name = "TSV", help = "File listing one sample per line: a path, or a name and a path separated by a tab")]
+    manifest: Option<PathBuf>,
+
+    #[arg(short, long, help = "K-mer size")]
+    k: usize,
+
+    #[arg(long, help = "Count each k-mer together with its reverse complement")]
+    canonical: bool,
+
+    #[arg(long, value_enum, default_value_t = AmbiguousBases::Skip, help = "What to do with N and other ambiguous bases")]
+    ambiguous: AmbiguousBases,
+
+    #[arg(long, value_enum, default_value_t = CountFormat::Csv, help = "Format of each sample's count file")]
+    format: CountFormat,
+
+    #[arg(long, value_enum, default_value_t = SortBy::Kmer, help = "Order of the counts in each sample's file (JSON is always by k-mer)")]
+    sort: SortBy,
+
+    #[arg(long, value_enum, help = "Also plot each sample's k-mer spectrum in this image format")]
+    spectrum: Option<PlotFormat>,
+
+    #[arg(long, default_value = "batch", help = "Directory for the summary and a subdirectory of outputs per sample")]
+    output_dir: PathBuf,
+
+    #[arg(long, help = "Process several samples at once, sharing the --threads counting threads")]
+    parallel: bool,
+
+    // Set from the global --gzip flag.
+    #[arg(skip)]
+    gzip: bool,
 }
 
+#[derive(Args, Debug)]
+struct EstimateArgs {
+    #[command(flatten)]
+    source: SourceArgs,
 
-//Counting K-mers Using Hashing
-//use a HashMap to count the frequency of each k-mer
+    #[arg(short, long, help = "K-mer size [default: 21, or that of --load-index]")]
+    k: Option<usize>,
 
-fn count_kmers(kmers: Vec<String>) -> HashMap<String, usize> {
-    let mut kmer_counts = HashMap::new();
-    for kmer in kmers {
-        *kmer_counts.entry(kmer).or_insert(0) += 1;
+    #[arg(long, conflicts_with = "load_index", help = "Count each k-mer together with its reverse complement")]
+    canonical: bool,
+
+    #[arg(long, help = "Read length, to also report the per-base coverage")]
+    read_length: Option<usize>,
+}
+
+#[derive(Args, Debug)]
+struct SimulateReadsArgs {
+    #[arg(long, help = "Reference sequence file (FASTA, FASTQ or raw text)")]
+    reference: PathBuf,
+
+    #[arg(long, default_value_t = 100, help = "Length of each read")]
+    read_length: usize,
+
+    #[arg(long, default_value_t = 10.0, help = "Mean depth of coverage across the reference")]
+    coverage: f64,
+
+    #[arg(long, default_value_t = 0.01, help = "Per-base substitution error rate")]
+    error_rate: f64,
+
+    #[arg(long, default_value_t = 0.0, help = "Per-base insertion/deletion error rate")]
+    indel_rate: f64,
+
+    #[arg(long, help = "Seed for the simulation, for reproducible runs")]
+    seed: Option<u64>,
+
+    #[arg(long, help = "FASTQ file to write the reads to")]
+    output: PathBuf,
+}
+
+// Arguments of the full analysis run when no subcommand is given.
+#[derive(Args, Debug)]
+struct RunArgs {
+    #[arg(long, required_unless_present_any = ["input", "interactive"], help = "Generate a random DNA sequence of this length")]
+    length: Option<usize>,
+
+    #[arg(long, conflicts_with = "length", help = "Read the sequence from a FASTA, FASTQ or raw text file instead")]
+    input: Option<PathBuf>,
+
+    #[arg(long, requires = "input", conflicts_with = "length", help = "Second sequence file to compare against --input by k-mer Jaccard similarity")]
+    input2: Option<PathBuf>,
+
+    #[arg(long, conflicts_with = "input", help = "Seed for the random sequence, for reproducible runs")]
+    seed: Option<u64>,
+
+    #[arg(long, value_enum, default_value_t = AmbiguousBases::Skip, help = "What to do with N and other ambiguous bases")]
+    ambiguous: AmbiguousBases,
+
+    #[arg(short, long, value_name = "K[,K...|START-END[:STEP]]", value_parser = parse_k_sizes, required_unless_present = "interactive", help = "K-mer size, or several to analyse each in its own k<K> subdirectory")]
+    k: Option<KSizes>,
+
+    #[arg(long, help = "Print only the N most frequent k-mers instead of every count")]
+    top: Option<usize>,
+
+    #[arg(long, help = "Count each k-mer together with its reverse complement")]
+    canonical: bool,
+
+    #[arg(long, value_enum, default_value_t = SortBy::Kmer, help = "Order of the printed counts and the CSV (JSON is always by k-mer)")]
+    sort: SortBy,
+
+    #[arg(long, value_enum, default_value_t = OutputFormat::Csv, help = "Format of the k-mer count and graph files")]
+    format: OutputFormat,
+
+    #[arg(long, default_value = ".", help = "Directory where the CSV, PNG and DOT outputs are written")]
+    output_dir: PathBuf,
+
+    #[arg(long, value_enum, default_value_t = PlotFormat::Png, help = "Image format of the histogram and spectrum plots")]
+    plot_format: PlotFormat,
+
+    #[arg(long, value_name = "FILE", help = "Also write a self-contained HTML report of the plots, counts and graph")]
+    report: Option<PathBuf>,
+
+    #[arg(long, help = "Prompt for the sequence length and k on stdin")]
+    interactive: bool,
+
+    // Set from the global --gzip flag.
+    #[arg(skip)]
+    gzip: bool,
+}
+
+// Parse "A,C,G,T" relative frequencies for --base-freqs
+fn parse_base_freqs(value: &str) -> Result<[f64; 4], String> {
+    let frequencies: Vec<f64> = value
+        .split(',')
+        .map(|frequency| frequency.trim().parse::<f64>().map_err(|e| format!("'{}': {}", frequency, e)))
+        .collect::<Result<_, _>>()?;
+    match frequencies[..] {
+        [a, c, g, t] if frequencies.iter().all(|&f| f >= 0.0) && a + c + g + t > 0.0 => Ok([a, c, g, t]),
+        [_, _, _, _] => Err("frequencies must be non-negative and not all zero".to_string()),
+        _ => Err(format!("expected 4 comma-separated frequencies, got {}", frequencies.len())),
     }
-    kmer_counts
 }
 
-// Define the structure for a De Bruijn graph.
-struct DeBruijnGraph {
-    // Use a HashMap to represent the graph.
-    // Keys are String representing a k-1-mer (a node),
-    // Values are Vec<String> representing a list of adjacent k-1-mers (edges).
-    edges: HashMap<String, Vec<String>>,
+// Parse a memory size such as 4G, 512M, 64K or a plain number of bytes
+fn parse_memory(value: &str) -> Result<usize, String> {
+    let value = value.trim();
+    let (digits, scale) = match value.char_indices().last() {
+        Some((i, 'K' | 'k')) => (&value[..i], 1 << 10),
+        Some((i, 'M' | 'm')) => (&value[..i], 1 << 20),
+        Some((i, 'G' | 'g')) => (&value[..i], 1 << 30),
+        _ => (value, 1),
+    };
+    let amount: usize = digits.parse().map_err(|e| format!("'{}': {}", value, e))?;
+    amount.checked_mul(scale).filter(|&bytes| bytes > 0).ok_or_else(|| format!("'{}': expected a positive size", value))
 }
 
-// Implement methods for the DeBruijnGraph structure.
-impl DeBruijnGraph {
-    // Constructor method to create a new De Bruijn graph from a list of k-mers.
-    fn new(kmers: &[String]) -> Self {
-        let mut edges = HashMap::new();
+// One or more k-mer sizes for the full analysis, in increasing order without repeats.
+#[derive(Debug, Clone)]
+struct KSizes(Vec<usize>);
 
-        // Iterate over each k-mer in the given list.
-        for kmer in kmers {
-            // Split the k-mer into two parts:
-            // - The first part (node) is all but the last character.
-            // - The second part (next) is all but the first character.
-            // This split creates an overlap between the k-1-mers.
-            let (node, next) = kmer.split_at(kmer.len() - 1);
+// Parse `-k 21`, a list like `-k 15,21,31`, or ranges like `-k 15-31:2` (the step defaults to 1)
+fn parse_k_sizes(value: &str) -> Result<KSizes, String> {
+    let parse = |n: &str| n.trim().parse::<usize>().map_err(|e| format!("'{}': {}", n.trim(), e));
+    let mut sizes = Vec::new();
+    for item in value.split(',') {
+        match item.split_once('-') {
+            Some((start, rest)) => {
+                let (end, step) = match rest.split_once(':') {
+                    Some((end, step)) => (end, parse(step)?),
+                    None => (rest, 1),
+                };
+                let (start, end) = (parse(start)?, parse(end)?);
+                if start > end || step == 0 {
+                    return Err(format!("'{}': expected START-END[:STEP] with START <= END and STEP > 0", item.trim()));
+                }
+                sizes.extend((start..=end).step_by(step));
+            }
+            None => sizes.push(parse(item)?),
+        }
+    }
+    sizes.sort_unstable();
+    sizes.dedup();
+    Ok(KSizes(sizes))
+}
 
-            // Insert the node into the HashMap if it doesn't exist,
-            // and append the adjacent node to the list of edges.
-            edges.entry(node.to_string()).or_insert_with(Vec::new).push(next.to_string());
+// Prompt on stdout and read a number from stdin, asking again until one is typed; exits if
+// stdin can't be read or runs out
+fn prompt_number(prompt: &str) -> usize {
+    loop {
+        println!("{}", prompt);
+        let mut input = String::new();
+        match io::stdin().read_line(&mut input) {
+            Ok(0) => {
+                error!("No input on stdin");
+                process::exit(1);
+            }
+            Ok(_) => {}
+            Err(e) => {
+                error!("Failed to read stdin: {}", e);
+                process::exit(1);
+            }
+        }
+        match input.trim().parse() {
+            Ok(number) => return number,
+            Err(_) => warn!("{:?} is not a number", input.trim()),
         }
+    }
+}
 
-        // Return a new De Bruijn graph with these edges.
-        DeBruijnGraph { edges }
+// Create the output directory, exiting if that fails
+fn create_output_dir(output_dir: &Path) {
+    if let Err(e) = fs::create_dir_all(output_dir) {
+        error!("Failed to create output directory {}: {}", output_dir.display(), e);
+        process::exit(1);
     }
+}
 
-    // Method to display the graph, useful for debugging and visualization.
-    fn display(&self) {
-        // Iterate over each node and its adjacent nodes in the graph.
-        for (node, next_nodes) in &self.edges {
-            // Print the current node and its connected nodes.
-            println!("{} -> {:?}", node, next_nodes);
+// Path of an output file inside the output directory, as a string for the writers
+fn output_path(output_dir: &Path, file_name: &str) -> String {
+    output_dir.join(file_name).to_string_lossy().into_owned()
+}
+
+// Path of a text output of the full analysis or a batch run, gzipped under --gzip
+fn text_output_path(gzip: bool, output_dir: &Path, file_name: &str) -> String {
+    let path = output_path(output_dir, file_name);
+    if gzip { path + ".gz" } else { path }
+}
+
+// The alphabet --alphabet and --symbols pick, exiting if the custom symbols can't be used
+fn alphabet(args: &AlphabetArgs) -> Alphabet {
+    let alphabet = match (args.alphabet, &args.symbols) {
+        (AlphabetKind::Custom, Some(symbols)) => Alphabet::custom(symbols),
+        (AlphabetKind::Custom, None) => unreachable!("clap requires --symbols with --alphabet custom"),
+        (_, Some(_)) => {
+            error!("--symbols needs --alphabet custom");
+            process::exit(1);
+        }
+        (AlphabetKind::Dna, None) => Ok(Alphabet::dna()),
+        (AlphabetKind::Rna, None) => Ok(Alphabet::rna()),
+        (AlphabetKind::Protein, None) => Ok(Alphabet::protein()),
+    };
+    match alphabet {
+        Ok(alphabet) => alphabet,
+        Err(e) => {
+            error!("{}", e);
+            process::exit(1);
         }
     }
 }
 
+// Exit unless the sequences are DNA, for the options that only handle A/C/G/T
+fn require_dna(alphabet: &Alphabet, what: &str) {
+    if alphabet.name() != "dna" {
+        error!("{} only works on DNA, not the {} alphabet", what, alphabet.name());
+        process::exit(1);
+    }
+}
 
-// Function to generate a random DNA sequence of a given length
-fn generate_random_dna_sequence(length: usize) -> String {
-    // Define the DNA bases
-    let bases = ['A', 'C', 'G', 'T'];
+// Exit unless the alphabet has a complement, which canonical k-mers need
+fn require_complement(alphabet: &Alphabet) {
+    if !alphabet.has_complement() {
+        error!("--canonical needs DNA or RNA: the {} alphabet has no complement", alphabet.name());
+        process::exit(1);
+    }
+}
 
-    // Initialize a random number generator
-    let mut rng = rand::thread_rng();
+// Read every record of the input file, exiting with an error message if it can't be read
+fn read_input(input: &Path) -> Vec<String> {
+    match step(&format!("Reading {}", input.display()), || read_sequences(&input.to_string_lossy())) {
+        Ok(sequences) => sequences,
+        Err(e) => {
+            error!("Failed to read DNA sequence from {}: {}", input.display(), e);
+            process::exit(1);
+        }
+    }
+}
 
-    // Create a uniform distribution to randomly select from the DNA bases
-    let between = Uniform::from(0..bases.len());
+// The sanitized records a subcommand works on, with low-quality bases masked under --min-qual
+fn load_sequences(source: &SourceArgs, k: usize) -> Vec<String> {
+    let alphabet = alphabet(&source.alphabet);
+    let sequences = match (&source.input, source.length) {
+        (Some(input), _) => read_input(input),
+        (None, Some(length)) => vec![alphabet.random_sequence(length, source.seed)],
+        (None, None) => unreachable!("clap requires --input or --length"),
+    };
+    let sequences = clean_sequences(&sequences, source.ambiguous, &alphabet);
+    let Some(min_qual) = source.min_qual else {
+        return sequences;
+    };
+    require_dna(&alphabet, "--min-qual");
+    let input = source.input.as_deref().expect("clap requires --input with --min-qual");
+    let records = match step(&format!("Reading qualities from {}", input.display()), || read_fastq(&input.to_string_lossy())) {
+        Ok(records) => records,
+        Err(e) => {
+            error!("Failed to read FASTQ qualities from {}: {}", input.display(), e);
+            process::exit(1);
+        }
+    };
+    // Mask the cleaned sequences, which line up base for base with the quality strings once the
+    // empty records read_sequences drops are left out here too
+    let records: Vec<FastqRecord> = records
+        .into_iter()
+        .filter(|record| !record.sequence.is_empty())
+        .zip(sequences)
+        .map(|(record, sequence)| FastqRecord { sequence, ..record })
+        .collect();
+    let (masked, filtered) = mask_low_quality_records(&records, k, min_qual);
+    info!("Filtered {} k-mers with a base below Q{}", filtered, min_qual);
+    masked
+}
 
-    // Generate the DNA sequence by randomly selecting bases
-    (0..length).map(|_| bases[between.sample(&mut rng)]).collect()
+// The k-mer size given with -k; clap only lets it be missing alongside --load-index
+fn given_k(k: Option<usize>) -> usize {
+    k.expect("clap requires -k without --load-index")
 }
 
-// Function to write the generated DNA sequence to a file
-fn write_to_file(contents: &str, file_name: &str) -> io::Result<()> {
-    // Create or open the file
-    let mut file = File::create(file_name)?;
+// Load a file saved with --save-index, exiting if it can't be read
+fn load_saved_index(index: &Path) -> SavedIndex {
+    match load_index(&index.to_string_lossy()) {
+        Ok(saved) => saved,
+        Err(e) => {
+            error!("Failed to load index {}: {}", index.display(), e);
+            process::exit(1);
+        }
+    }
+}
 
-    // Write the DNA sequence to the file
-    file.write_all(contents.as_bytes())?;
+// Exit if -k was given and disagrees with the k an index was saved with
+fn check_index_k(k: Option<usize>, saved_k: usize, index: &Path) {
+    if let Some(k) = k.filter(|&k| k != saved_k) {
+        error!("{} was saved with k = {}, not {}", index.display(), saved_k, k);
+        process::exit(1);
+    }
+}
 
-    // Return the result of the file operation
-    Ok(())
+// The k-mer size and count table of a subcommand's source: loaded from --load-index, or counted
+fn source_counts(source: &SourceArgs, k: Option<usize>, canonical: bool) -> (usize, HashMap<String, usize>) {
+    let Some(index) = &source.load_index else {
+        let k = given_k(k);
+        let alphabet = alphabet(&source.alphabet);
+        if canonical {
+            require_complement(&alphabet);
+        }
+        let sequences = load_sequences(source, k);
+        check_k(k, &sequences);
+        return (k, step("Counting k-mers", || count_records_parallel_in(&sequences, k, &alphabet, canonical)));
+    };
+    match load_saved_index(index) {
+        SavedIndex::Counts { k: saved_k, counts } => {
+            check_index_k(k, saved_k, index);
+            (saved_k, counts)
+        }
+        SavedIndex::Graph(_) => {
+            error!("{} holds a De Bruijn graph, not k-mer counts", index.display());
+            process::exit(1);
+        }
+    }
 }
-// Function to read the DNA sequence from a file
-fn read_dna_sequence_from_file(file_name: &str) -> io::Result<String> {
-    fs::read_to_string(file_name)
+
+fn count_order(sort: SortBy) -> CountOrder {
+    match sort {
+        SortBy::Kmer => CountOrder::Kmer,
+        SortBy::Count => CountOrder::Count,
+    }
 }
 
-// Function to write k-mer counts to a CSV file
-fn write_kmer_counts_to_csv(kmer_counts: &HashMap<String, usize>, file_name: &str) -> Result<(), Box<dyn Error>> {
-    let mut wtr = Writer::from_path(file_name)?;
+// Uppercase each record and strip whitespace, exiting with the record and position of the
+// first character outside the alphabet (or ambiguous one, if those are errors)
+fn clean_sequences(sequences: &[String], ambiguous: AmbiguousBases, alphabet: &Alphabet) -> Vec<String> {
+    let policy = ambiguous_policy(ambiguous);
+    sequences
+        .iter()
+        .enumerate()
+        .map(|(i, sequence)| match alphabet.validate(sequence, policy) {
+            Ok(clean) => clean,
+            Err(e) => {
+                error!("Invalid sequence in record {}: {}", i + 1, e);
+                process::exit(1);
+            }
+        })
+        .collect()
+}
 
-    // Write header
-    wtr.write_record(&["K-mer", "Count"])?;
+// The library's policy for --ambiguous
+fn ambiguous_policy(ambiguous: AmbiguousBases) -> AmbiguousBasePolicy {
+    match ambiguous {
+        AmbiguousBases::Skip => AmbiguousBasePolicy::Skip,
+        AmbiguousBases::Error => AmbiguousBasePolicy::Error,
+    }
+}
 
-    // Write k-mer data
-    for (kmer, count) in kmer_counts {
-        wtr.write_record(&[kmer, &count.to_string()])?;
+// Exit with an error unless 1 <= k <= the longest record
+fn check_k(k: usize, sequences: &[String]) {
+    let longest_record = sequences.iter().map(String::len).max().unwrap_or(0);
+    if let Err(e) = kmer::check_k(k, longest_record) {
+        error!("{} (the longest sequence length)", e);
+        process::exit(1);
     }
+}
 
-    wtr.flush()?;
-    Ok(())
+// Generate k-mers record by record, skipping any that span an N or another symbol outside the
+// alphabet, so none cross a record boundary
+fn record_kmers(sequences: &[String], k: usize, alphabet: &Alphabet) -> Vec<String> {
+    sequences.iter().flat_map(|sequence| alphabet.kmers(sequence, k)).collect()
 }
 
-fn write_graph_to_csv(graph: &DeBruijnGraph, file_name: &str) -> Result<(), Box<dyn Error>> {
-    let mut wtr = Writer::from_path(file_name)?;
+// Print an error and exit if a writer failed
+fn exit_on_error<E: std::fmt::Display>(result: Result<(), E>, what: &str) {
+    if let Err(e) = result {
+        error!("Failed to {}: {}", what, e);
+        process::exit(1);
+    }
+}
 
-    // Write header
-    wtr.write_record(&["Node", "Connected Nodes"])?;
+fn generate(args: &GenerateArgs) {
+    let alphabet = alphabet(&args.alphabet);
+    if args.gc.is_some() || args.base_freqs.is_some() || args.markov_from.is_some() {
+        require_dna(&alphabet, "--gc, --base-freqs and --markov-from");
+    }
+    let model = match (args.gc, &args.base_freqs, &args.markov_from) {
+        (Some(gc), _, _) => Some(SequenceModel::with_gc_content(gc)),
+        (_, Some(frequencies), _) => Some(SequenceModel::BaseFrequencies(*frequencies)),
+        (_, _, Some(training)) => Some(SequenceModel::Markov(MarkovModel::train(&read_input(training).concat(), args.order))),
+        _ => None,
+    };
+    let sequence = match model {
+        Some(model) => generate_sequence(&model, args.length, args.seed.unwrap_or_else(rand::random)),
+        None => alphabet.random_sequence(args.length, args.seed),
+    };
+    match &args.output {
+        Some(output) => exit_on_error(write_to_file(&sequence, &output.to_string_lossy()), "write DNA sequence"),
+        None => println!("{}", sequence),
+    }
+}
 
-    // Write graph data
-    for (node, edges) in &graph.edges {
-        let connected_nodes = edges.join(", ");
-        wtr.write_record(&[node, &connected_nodes])?;
+fn count(args: &CountArgs) {
+    if args.disk {
+        count_on_disk(args);
+        return;
     }
+    let (k, kmer_counts) = if args.streaming {
+        require_dna(&alphabet(&args.source.alphabet), "--streaming");
+        let k = given_k(args.k);
+        let input = args.source.input.as_deref().expect("clap requires --input with --streaming");
+        let packed = match step("Counting k-mers", || count_kmers_packed_streaming(&input.to_string_lossy(), k, args.canonical)) {
+            Ok(packed) => packed,
+            Err(e) => {
+                error!("Failed to count k-mers in {}: {}", input.display(), e);
+                process::exit(1);
+            }
+        };
+        // Without --top or --save-index the CSV is written straight from the packed codes
+        if let (Some(output), None, None, CountFormat::Csv) = (&args.output, args.top, &args.save_index, args.format) {
+            exit_on_error(write_packed_kmer_counts_to_csv(&packed, k, count_order(args.sort), &output.to_string_lossy()), "write k-mer counts");
+            return;
+        }
+        (k, packed.into_iter().map(|(code, count)| (decode_kmer(code, k), count)).collect())
+    } else if args.approx || args.minimizers.is_some() {
+        require_dna(&alphabet(&args.source.alphabet), "--approx and --minimizers");
+        let k = given_k(args.k);
+        let sequences = load_sequences(&args.source, k);
+        check_k(k, &sequences);
+        let counts = step("Counting k-mers", || match args.minimizers {
+            Some(w) => count_minimizers(&sequences, k, w, args.canonical),
+            None => count_kmers_approximate(&sequences, k, args.canonical, args.memory),
+        });
+        (k, counts)
+    } else {
+        source_counts(&args.source, args.k, args.canonical)
+    };
+    if let Some(index) = &args.save_index {
+        exit_on_error(save_kmer_counts(&kmer_counts, k, &index.to_string_lossy()), "save k-mer counts");
+    }
+    let kmer_counts = match args.top {
+        Some(n) => top_kmers(&kmer_counts, n).into_iter().collect(),
+        None => kmer_counts,
+    };
+    let order = count_order(args.sort);
+    match &args.output {
+        Some(output) => exit_on_error(write_counts(&kmer_counts, args.format, order, &output.to_string_lossy()), "write k-mer counts"),
+        None => {
+            for (kmer, count) in sorted_counts(&kmer_counts, order) {
+                println!("{}\t{}", kmer, count);
+            }
+        }
+    }
+}
 
-    wtr.flush()?;
-    Ok(())
+// Write a count table to a file in one of the `count` formats
+fn write_counts(kmer_counts: &HashMap<String, usize>, format: CountFormat, order: CountOrder, file_name: &str) -> Result<(), KmerError> {
+    match format {
+        CountFormat::Csv => write_kmer_counts_to_csv(kmer_counts, order, file_name),
+        CountFormat::Json => write_kmer_counts_to_json(kmer_counts, file_name),
+        CountFormat::Tsv => Ok(write_kmer_counts_to_tsv(kmer_counts, order, file_name)?),
+        CountFormat::Jellyfish => Ok(write_kmer_counts_jellyfish(kmer_counts, order, file_name)?),
+        CountFormat::Kmc => Ok(write_kmer_counts_kmc(kmer_counts, order, file_name)?),
+    }
 }
 
+// Count through bucket files under --tmp-dir and stream the counts out in k-mer order, which
+// is the only order and CSV the only file format that need no table in memory
+fn count_on_disk(args: &CountArgs) {
+    require_dna(&alphabet(&args.source.alphabet), "--disk");
+    if args.sort != SortBy::Kmer || (args.output.is_some() && args.format != CountFormat::Csv) {
+        error!("--disk writes CSV sorted by k-mer");
+        process::exit(1);
+    }
+    let k = given_k(args.k);
+    let input = args.source.input.as_deref().expect("clap requires --input with --disk");
+    let options = DiskCountOptions {
+        tmp_dir: args.tmp_dir.clone().unwrap_or_else(std::env::temp_dir),
+        memory_limit: args.memory_limit,
+    };
+    let counts = match step("Counting k-mers on disk", || count_kmers_on_disk(&input.to_string_lossy(), k, args.canonical, &options)) {
+        Ok(counts) => counts,
+        Err(e) => {
+            error!("Failed to count k-mers in {}: {}", input.display(), e);
+            process::exit(1);
+        }
+    };
+    match &args.output {
+        Some(output) => exit_on_error(write_sorted_kmer_counts_to_csv(counts, k, &output.to_string_lossy()), "write k-mer counts"),
+        None => {
+            for entry in counts {
+                match entry {
+                    Ok((code, count)) => println!("{}\t{}", decode_kmer(code, k), count),
+                    Err(e) => {
+                        error!("Failed to read back k-mer counts: {}", e);
+                        process::exit(1);
+                    }
+                }
+            }
+        }
+    }
+}
 
-// Function to plot a histogram of k-mer counts
-fn plot_kmer_histogram(kmer_counts: &HashMap<String, usize>, output_file: &str) -> Result<(), Box<dyn std::error::Error>> {
-    // Create a drawing area for the plot, specifying the output file and dimensions
-    let root_area = BitMapBackend::new(output_file, (640, 480)).into_drawing_area();
-    // Fill the drawing area with a white background
-    root_area.fill(&WHITE)?;
+fn graph(args: &GraphArgs) {
+    let dbg = build_graph(&args.build);
+    if let Some(index) = &args.save_index {
+        exit_on_error(save_graph(&dbg, &index.to_string_lossy()), "save De Bruijn graph");
+    }
+    if let Some(metrics_file) = &args.metrics {
+        let metrics = graph_metrics(&create_petgraph(&dbg));
+        println!("{}", metrics);
+        let metrics_file = metrics_file.to_string_lossy();
+        if metrics_file.trim_end_matches(".gz").ends_with(".json") {
+            exit_on_error(write_graph_metrics_to_json(&metrics, &metrics_file), "write graph metrics");
+        } else {
+            exit_on_error(write_graph_metrics_to_csv(&metrics, &metrics_file), "write graph metrics");
+        }
+    }
+    let output = args.output.to_string_lossy();
+    match args.format {
+        GraphFormat::Dot => exit_on_error(save_graph_dot(&create_petgraph(&dbg), &output), "write De Bruijn graph"),
+        GraphFormat::Csv => exit_on_error(write_graph_to_csv(&dbg, &output), "write De Bruijn graph"),
+        GraphFormat::Json => exit_on_error(write_graph_to_json(&dbg, &output), "write De Bruijn graph"),
+        GraphFormat::Gfa => exit_on_error(write_graph_to_gfa(&dbg, &output), "write De Bruijn graph"),
+    }
+}
 
-    // Determine the maximum count for the y-axis
-    let max_count = *kmer_counts.values().max().unwrap();
+fn colored_graph(args: &ColoredGraphArgs) {
+    let samples = match &args.manifest {
+        Some(manifest) => read_batch_manifest(&manifest.to_string_lossy()),
+        None => Ok(args.inputs.iter().cloned().map(BatchSample::from_path).collect()),
+    };
+    let samples = match samples {
+        Ok(samples) => samples,
+        Err(e) => {
+            error!("{}", e);
+            process::exit(1);
+        }
+    };
+    let alphabet = Alphabet::dna();
+    let samples: Vec<(String, Vec<String>)> = samples
+        .into_iter()
+        .map(|sample| {
+            let sequences = clean_sequences(&read_input(&sample.path), args.ambiguous, &alphabet);
+            check_k(args.k, &sequences);
+            (sample.name, sequences)
+        })
+        .collect();
+    let mut dbg = match step("Building the colored De Bruijn graph", || DeBruijnGraph::from_samples(&samples, args.k, &alphabet)) {
+        Ok(dbg) => dbg,
+        Err(e) => {
+            error!("{}", e);
+            process::exit(1);
+        }
+    };
+    if let Some(max_len) = args.remove_tips {
+        info!("Removed tips: {}", dbg.remove_tips(max_len));
+    }
+    if args.pop_bubbles {
+        info!("Popped bubbles: {}", dbg.pop_bubbles());
+    }
 
-    // Build the chart with specified caption, margins, and axis sizes
-    let mut chart = ChartBuilder::on(&root_area)
-        .caption("K-mer Frequency Histogram", ("sans-serif", 40))
-        .margin(5)
-        .x_label_area_size(30)
-        .y_label_area_size(40)
-        .build_cartesian_2d(0usize..kmer_counts.len(), 0usize..max_count)?;
+    println!("Sample\tK-mers\tUnique");
+    for (name, (edges, unique)) in dbg.samples.iter().zip(dbg.sample_edge_counts()) {
+        println!("{}\t{}\t{}", name, edges, unique);
+    }
+    for (shared_by, edges) in dbg.color_histogram() {
+        println!("K-mers in {} of {} samples: {}", shared_by, dbg.samples.len(), edges);
+    }
+    let output = args.output.to_string_lossy();
+    match args.format {
+        ColoredGraphFormat::Dot => exit_on_error(write_colored_graph_to_dot(&dbg, &output), "write colored De Bruijn graph"),
+        ColoredGraphFormat::Gfa => exit_on_error(write_graph_to_gfa(&dbg, &output), "write colored De Bruijn graph"),
+    }
+}
 
+fn contigs(args: &ContigsArgs) {
+    let dbg = build_graph(&args.build);
+    let contigs = if args.greedy {
+        dbg.greedy_contigs()
+    } else {
+        let (unitigs, _) = dbg.collapse_unitigs();
+        unitigs.segments.into_iter().zip(unitigs.coverage).collect()
+    };
+    let mut contigs: Vec<(String, f64)> = contigs.into_iter().filter(|(contig, _)| contig.len() >= args.min_length).collect();
+    // Longest first, as assemblers list them
+    contigs.sort_by_key(|(contig, _)| std::cmp::Reverse(contig.len()));
+    let records: Vec<(String, String)> = contigs
+        .iter()
+        .enumerate()
+        .map(|(i, (contig, coverage))| (format!("contig_{} length={} coverage={:.1}", i + 1, contig.len(), coverage), contig.clone()))
+        .collect();
+    exit_on_error(write_fasta(&records, &args.output.to_string_lossy()), "write contigs");
+    let lengths: Vec<usize> = contigs.iter().map(|(contig, _)| contig.len()).collect();
+    println!("{}", AssemblyStats::from_lengths(&lengths));
+}
 
+// Build the graph from the source, filter its k-mers and simplify it as asked
+fn build_graph(args: &GraphBuildArgs) -> DeBruijnGraph {
+    let mut dbg = match &args.source.load_index {
+        Some(index) => match load_saved_index(index) {
+            SavedIndex::Counts { k, mut counts } => {
+                check_index_k(args.k, k, index);
+                filter_graph_counts(args, &mut counts);
+                step("Building the De Bruijn graph", || DeBruijnGraph::from_counts(&counts))
+            }
+            SavedIndex::Graph(graph) => {
+                check_index_k(args.k, graph.k, index);
+                if filters_counts(args) {
+                    error!("{} holds a De Bruijn graph, but count filters need k-mer counts", index.display());
+                    process::exit(1);
+                }
+                graph
+            }
+        },
+        None => {
+            let k = given_k(args.k);
+            let alphabet = alphabet(&args.source.alphabet);
+            if args.minimizers.is_some() {
+                require_dna(&alphabet, "--minimizers");
+            }
+            let sequences = load_sequences(&args.source, k);
+            check_k(k, &sequences);
+            let kmers = step("Collecting k-mers", || match args.minimizers {
+                Some(w) => record_minimizers(&sequences, k, w, false),
+                None => record_kmers(&sequences, k, &alphabet),
+            });
+            if filters_counts(args) {
+                let mut counts = step("Counting k-mers", || count_kmers(kmers));
+                filter_graph_counts(args, &mut counts);
+                step("Building the De Bruijn graph", || DeBruijnGraph::from_counts(&counts))
+            } else {
+                step("Building the De Bruijn graph", || DeBruijnGraph::new(&kmers))
+            }
+        }
+    };
+    if let Some(min_coverage) = args.min_coverage {
+        info!("Dropped low-coverage edges: {}", dbg.remove_low_coverage_edges(min_coverage));
+    }
+    if let Some(max_len) = args.remove_tips {
+        info!("Removed tips: {}", dbg.remove_tips(max_len));
+    }
+    if args.pop_bubbles {
+        info!("Popped bubbles: {}", dbg.pop_bubbles());
+    }
+    info!("Unitigs: {}", dbg.collapse_unitigs().1);
+    dbg
+}
 
-    // Configure and draw the mesh (grid lines) for the chart
-    chart.configure_mesh().draw()?;
+// Whether any of --min-count, --max-count or --drop-errors was given
+fn filters_counts(args: &GraphBuildArgs) -> bool {
+    args.min_count.is_some() || args.max_count.is_some() || args.drop_errors
+}
 
-    // Convert k-mer count data into a format suitable for the histogram
-    let data: Vec<(usize, usize)> = kmer_counts.iter().enumerate().map(|(index, (_kmer, &count))| (index, count)).collect();
+// Apply the count filters to the table the graph is built from
+fn filter_graph_counts(args: &GraphBuildArgs, counts: &mut HashMap<String, usize>) {
+    let mut min_count = args.min_count.unwrap_or(0);
+    if args.drop_errors {
+        match error_threshold(&compute_spectrum(counts)) {
+            Some(threshold) => {
+                info!("Error threshold: {}x", threshold);
+                min_count = min_count.max(threshold);
+            }
+            None => warn!("No error threshold found: the k-mer spectrum has no coverage peak"),
+        }
+    }
+    info!("Filtered k-mers: {}", filter_counts(counts, min_count, args.max_count));
+}
 
-    // Draw the histogram series on the chart
-    chart.draw_series(
-        Histogram::vertical(&chart)
-            .style(RED.filled())
-            .data(data.iter().map(|&(index, count)| (index, count))),
-    )?;
+fn query(args: &QueryArgs) {
+    if let Some(index) = &args.source.load_index {
+        error!("Queries need sequences, not the saved index {}", index.display());
+        process::exit(1);
+    }
+    let mut kmers: Vec<String> = args.kmers.iter().map(|kmer| kmer.to_ascii_uppercase()).collect();
+    if let Some(file) = &args.kmers_file {
+        match fs::read_to_string(file) {
+            Ok(contents) => kmers.extend(contents.split_whitespace().map(str::to_ascii_uppercase)),
+            Err(e) => {
+                error!("Failed to read k-mers from {}: {}", file.display(), e);
+                process::exit(1);
+            }
+        }
+    }
+    // The query k-mers set k
+    let Some(k) = kmers.first().map(String::len) else {
+        error!("No k-mers to look up");
+        process::exit(1);
+    };
+    if let Some(kmer) = kmers.iter().find(|kmer| kmer.len() != k) {
+        error!("All query k-mers must have the same length: {} has {} bases, not {}", kmer, kmer.len(), k);
+        process::exit(1);
+    }
+    let sequences = load_sequences(&args.source, k);
+    check_k(k, &sequences);
+    let index = KmerIndex::build(&sequences, k);
+    // One line per occurrence, with the record counted from 1 like the other outputs
+    for kmer in &kmers {
+        let positions = index.locate(kmer);
+        if positions.is_empty() {
+            warn!("{}: not found", kmer);
+        }
+        for position in positions {
+            println!("{}\t{}\t{}", kmer, position.sequence + 1, position.offset);
+        }
+    }
+}
 
+fn stats(args: &StatsArgs) {
+    if let Some(index) = &args.source.load_index {
+        error!("Statistics need sequences, not the saved index {}", index.display());
+        process::exit(1);
+    }
+    require_dna(&alphabet(&args.source.alphabet), "stats");
+    // Composition statistics have no k; --min-qual masks single bases
+    let sequences = load_sequences(&args.source, 1);
+    let records: Vec<SequenceStats> = sequences.iter().map(|sequence| SequenceStats::of(sequence)).collect();
+    println!("Records: {}", records.len());
+    println!("{}", SequenceStats::of(&sequences.concat()));
+    if records.len() > 1 {
+        println!("Record\tLength\tGC%\tEntropy");
+        for (i, record) in records.iter().enumerate() {
+            println!("{}\t{}\t{:.2}\t{:.3}", i + 1, record.length, record.gc_content() * 100.0, record.entropy());
+        }
+    }
+    if args.output.is_none() && args.plot.is_none() {
+        return;
+    }
+    let step = args.step.unwrap_or(args.window);
+    let windows: Vec<Vec<WindowStats>> = sequences.iter().map(|sequence| sliding_window_stats(sequence, args.window, step)).collect();
+    if let Some(output) = &args.output {
+        exit_on_error(write_window_stats_to_csv(&windows, &output.to_string_lossy()), "write window statistics");
+    }
+    if let Some(plot) = &args.plot {
+        exit_on_error(plot_gc_windows(&windows, &plot.to_string_lossy()), "plot GC content");
+    }
+}
 
-    // Present the final plot
-    root_area.present()?;
-    Ok(())
+fn plot(args: &PlotArgs) {
+    let (_, kmer_counts) = source_counts(&args.source, args.k, args.canonical);
+    let output = args.output.to_string_lossy();
+    match args.kind {
+        PlotKind::Histogram => exit_on_error(plot_kmer_histogram(&kmer_counts, &output, args.width, args.height), "plot k-mer histogram"),
+        PlotKind::Spectrum => {
+            let axes = SpectrumAxes { log_x: args.log_x, log_y: args.log_y };
+            exit_on_error(plot_spectrum(&compute_spectrum(&kmer_counts), &output, axes), "plot k-mer spectrum")
+        }
+    }
 }
 
-fn create_petgraph(de_bruijn_graph: &DeBruijnGraph) -> UnGraph<String, ()> {
-    let mut graph = UnGraph::<String, ()>::new_undirected();
+fn compare(args: &CompareArgs) {
+    let labels: Vec<String> = args.inputs.iter().map(|input| input.display().to_string()).collect();
+    let sketches: Vec<MinHashSketch> = args
+        .inputs
+        .iter()
+        .map(|input| {
+            if !is_saved_index(&input.to_string_lossy()) {
+                let sequences = clean_sequences(&read_input(input), AmbiguousBases::Skip, &Alphabet::dna());
+                return MinHashSketch::from_records(&sequences, args.k, args.sketch_size);
+            }
+            match load_saved_index(input) {
+                SavedIndex::Counts { k, counts } => {
+                    check_index_k(Some(args.k), k, input);
+                    MinHashSketch::from_kmers(counts.keys(), k, args.sketch_size)
+                }
+                SavedIndex::Graph(_) => {
+                    error!("{} holds a De Bruijn graph, not k-mer counts", input.display());
+                    process::exit(1);
+                }
+            }
+        })
+        .collect();
+    let measure = match args.metric {
+        CompareMetric::Distance => MinHashSketch::mash_distance,
+        CompareMetric::Jaccard => MinHashSketch::jaccard,
+    };
+    let matrix = pairwise_matrix(&sketches, measure);
+    match &args.output {
+        Some(output) => {
+            exit_on_error(write_matrix_to_csv(&labels, &matrix, &output.to_string_lossy()), "write matrix");
+            info!("Compared {} samples in {}", labels.len(), output.display());
+        }
+        None => {
+            println!("\t{}", labels.join("\t"));
+            for (label, row) in labels.iter().zip(&matrix) {
+                let values: Vec<String> = row.iter().map(|value| format!("{:.6}", value)).collect();
+                println!("{}\t{}", label, values.join("\t"));
+            }
+        }
+    }
+}
 
-    let mut index_map = std::collections::HashMap::new();
+// The k-mer size and counts of one diff sample: saved counts as they are, or a sequence file
+// counted at k (21 unless given)
+fn sample_counts(input: &Path, k: Option<usize>, canonical: bool) -> (usize, HashMap<String, usize>) {
+    if !is_saved_index(&input.to_string_lossy()) {
+        let k = k.unwrap_or(21);
+        let sequences = clean_sequences(&read_input(input), AmbiguousBases::Skip, &Alphabet::dna());
+        check_k(k, &sequences);
+        return (k, step(&format!("Counting k-mers in {}", input.display()), || count_records_parallel(&sequences, k, canonical)));
+    }
+    match load_saved_index(input) {
+        SavedIndex::Counts { k: saved_k, counts } => {
+            check_index_k(k, saved_k, input);
+            (saved_k, counts)
+        }
+        SavedIndex::Graph(_) => {
+            error!("{} holds a De Bruijn graph, not k-mer counts", input.display());
+            process::exit(1);
+        }
+    }
+}
 
-    for (node, edges) in &de_bruijn_graph.edges {
-        let node_index = *index_map.entry(node.clone()).or_insert_with(|| graph.add_node(node.clone()));
-        for edge in edges {
-            let edge_index = *index_map.entry(edge.clone()).or_insert_with(|| graph.add_node(edge.clone()));
-            graph.add_edge(node_index, edge_index, ());
+fn diff(args: &DiffArgs) {
+    // Sample B is counted at A's k, so saved counts on either side set it for both
+    let (k, counts_a) = sample_counts(&args.sample_a, args.k, args.canonical);
+    let (_, counts_b) = sample_counts(&args.sample_b, Some(k), args.canonical);
+    let diffs = diff_counts(&counts_a, &counts_b);
+    let summary = DiffSummary::of(&diffs);
+    match &args.output {
+        Some(output) => {
+            exit_on_error(write_kmer_diff_to_csv(&diffs, &output.to_string_lossy()), "write k-mer diff");
+            println!("{}", summary);
+        }
+        None => {
+            println!("kmer\tcount_a\tcount_b\tlog2_fold_change");
+            for diff in &diffs {
+                println!("{}\t{}\t{}\t{:.4}", diff.kmer, diff.count_a, diff.count_b, diff.log2_fold_change);
+            }
+            info!("{}", summary);
         }
     }
+    if let Some(plot) = &args.plot {
+        exit_on_error(plot_count_scatter(&diffs, &plot.to_string_lossy()), "plot the count scatter");
+        info!("Count scatter plotted in {}", plot.display());
+    }
+}
 
-    graph
+fn batch(args: &BatchArgs) {
+    let samples = match (&args.inputs, &args.manifest) {
+        (_, Some(manifest)) => read_batch_manifest(&manifest.to_string_lossy()),
+        (Some(inputs), None) => batch_samples(inputs),
+        (None, None) => unreachable!("clap requires inputs or --manifest"),
+    };
+    let samples = match samples {
+        Ok(samples) => samples,
+        Err(e) => {
+            error!("{}", e);
+            process::exit(1);
+        }
+    };
+    create_output_dir(&args.output_dir);
+    info!("Processing {} samples", samples.len());
+    // A sample that fails is logged and left out of the summary, so one bad file doesn't stop
+    // the rest
+    let process = |sample: &BatchSample| match batch_sample(args, sample) {
+        Ok(summary) => {
+            info!("{}: {} distinct k-mers", sample.name, summary.distinct);
+            Some(summary)
+        }
+        Err(e) => {
+            error!("Failed to process {} ({}): {}", sample.name, sample.path.display(), e);
+            None
+        }
+    };
+    let results: Vec<Option<SampleSummary>> = if args.parallel {
+        samples.par_iter().map(process).collect()
+    } else {
+        samples.iter().map(process).collect()
+    };
+    let summaries: Vec<SampleSummary> = results.into_iter().flatten().collect();
+    let summary_file = text_output_path(args.gzip, &args.output_dir, "batch_summary.csv");
+    exit_on_error(write_batch_summary_to_csv(&summaries, &summary_file), "write batch summary");
+    info!("Summary of {} samples saved to {}", summaries.len(), summary_file);
+    if summaries.len() < samples.len() {
+        error!("{} of {} samples failed", samples.len() - summaries.len(), samples.len());
+        process::exit(1);
+    }
+}
+
+// Count one sample of a batch into its own subdirectory and summarize it
+fn batch_sample(args: &BatchArgs, sample: &BatchSample) -> Result<SampleSummary, KmerError> {
+    let alphabet = Alphabet::dna();
+    let policy = ambiguous_policy(args.ambiguous);
+    let sequences = read_sequences(&sample.path.to_string_lossy())?
+        .iter()
+        .map(|sequence| alphabet.validate(sequence, policy))
+        .collect::<Result<Vec<String>, KmerError>>()?;
+    kmer::check_k(args.k, sequences.iter().map(String::len).max().unwrap_or(0))?;
+    let kmer_counts = count_records_parallel(&sequences, args.k, args.canonical);
+
+    let sample_dir = args.output_dir.join(&sample.name);
+    fs::create_dir_all(&sample_dir)?;
+    let counts_name = match args.format {
+        CountFormat::Csv => "kmer_counts.csv",
+        CountFormat::Json => "kmer_counts.json",
+        CountFormat::Tsv => "kmer_counts.tsv",
+        CountFormat::Jellyfish => "kmer_counts.fa",
+        CountFormat::Kmc => "kmer_counts.txt",
+    };
+    write_counts(&kmer_counts, args.format, count_order(args.sort), &text_output_path(args.gzip, &sample_dir, counts_name))?;
+    if let Some(format) = args.spectrum {
+        let extension = match format {
+            PlotFormat::Png => "png",
+            PlotFormat::Svg => "svg",
+        };
+        let spectrum_file = output_path(&sample_dir, &format!("kmer_spectrum.{}", extension));
+        plot_spectrum(&compute_spectrum(&kmer_counts), &spectrum_file, SpectrumAxes::default())?;
+    }
+
+    let stats = SequenceStats::of(&sequences.concat());
+    let summary = KmerSummary::from_counts(args.k, &kmer_counts);
+    Ok(SampleSummary {
+        sample: sample.name.clone(),
+        input: sample.path.clone(),
+        records: sequences.len(),
+        length: stats.length,
+        gc_content: stats.gc_content(),
+        distinct: summary.distinct,
+        unique: summary.unique,
+        total: summary.total,
+    })
 }
 
-fn save_graph_dot(graph: &UnGraph<String, ()>, file_name: &str) -> Result<(), std::io::Error> {
-    let dot = Dot::with_config(&graph, &[Config::EdgeNoLabel]);
-    let mut file = File::create(file_name)?;
-    writeln!(file, "{:?}", dot)
+fn estimate(args: &EstimateArgs) {
+    // Count 21-mers unless told otherwise; a loaded index brings its own k
+    let k = args.k.or(args.source.load_index.is_none().then_some(21));
+    let (k, kmer_counts) = source_counts(&args.source, k, args.canonical);
+    let spectrum = compute_spectrum(&kmer_counts);
+    let Some(estimate) = estimate_genome(&spectrum, k) else {
+        error!("No coverage peak in the k-mer spectrum: coverage is too low to estimate the genome");
+        process::exit(1);
+    };
+    println!("{}", estimate);
+    if let Some(read_length) = args.read_length {
+        println!("Base coverage: {:.1}x", estimate.base_coverage(read_length));
+    }
 }
 
+fn simulate(args: &SimulateReadsArgs) {
+    let reference = clean_sequences(&read_input(&args.reference), AmbiguousBases::Skip, &Alphabet::dna()).concat();
+    let reads = simulate_reads(
+        &reference,
+        args.read_length,
+        args.coverage,
+        args.error_rate,
+        args.indel_rate,
+        args.seed.unwrap_or_else(rand::random),
+    );
+    exit_on_error(write_fastq(&reads, &args.output.to_string_lossy()), "write reads");
+    info!("Simulated {} reads of {} bp in {}", reads.len(), args.read_length, args.output.display());
+}
+
+// Route log lines to stderr at the level -v/-q ask for, as text or JSON lines
+fn init_logging(cli: &Cli) {
+    let level = match (cli.quiet, cli.verbose) {
+        (true, _) => LevelFilter::Error,
+        (false, 0) => LevelFilter::Info,
+        (false, 1) => LevelFilter::Debug,
+        (false, _) => LevelFilter::Trace,
+    };
+    let mut builder = env_logger::Builder::new();
+    builder.filter_level(level);
+    match cli.log_format {
+        LogFormat::Text => builder.format(|buf, record| {
+            use std::io::Write;
+            writeln!(buf, "[{}] {}", record.level(), record.args())
+        }),
+        LogFormat::Json => builder.format(|buf, record| {
+            use std::io::Write;
+            let line = serde_json::json!({
+                "timestamp": buf.timestamp().to_string(),
+                "level": record.level().as_str(),
+                "message": record.args().to_string(),
+            });
+            writeln!(buf, "{}", line)
+        }),
+    };
+    builder.init();
+}
+
+// Run a long step behind a spinner on stderr, hidden unless info lines are shown, and log how
+// long it took
+fn step<T>(message: &str, work: impl FnOnce() -> T) -> T {
+    let spinner = if log::log_enabled!(log::Level::Info) {
+        ProgressBar::new_spinner()
+    } else {
+        ProgressBar::hidden()
+    };
+    spinner.set_style(ProgressStyle::with_template("{spinner} {msg} [{elapsed}]").expect("valid spinner template"));
+    spinner.set_message(message.to_string());
+    spinner.enable_steady_tick(Duration::from_millis(100));
+    let start = Instant::now();
+    let result = work();
+    spinner.finish_and_clear();
+    debug!("{} took {:.2?}", message, start.elapsed());
+    result
+}
 
 fn main() {
-    // Prompt for and read the DNA sequence length from the user
-    println!("Enter the length of the DNA sequence:");
-    let mut dna_length_str = String::new();
-    io::stdin().read_line(&mut dna_length_str).expect("Failed to read line");
-    let dna_length: usize = dna_length_str.trim().parse().expect("Please type a number!");
-
-    // Generate a random DNA sequence and save it to a file
-    let dna_sequence = generate_random_dna_sequence(dna_length);
-    match write_to_file(&dna_sequence, "random_dna_sequence.txt") {
-        Ok(_) => println!("DNA sequence saved to random_dna_sequence.txt"),
-        Err(e) => eprintln!("Failed to write DNA sequence to file: {}", e),
-    }
-
-    // Read the DNA sequence from the file
-    let dna_sequence = match read_dna_sequence_from_file("random_dna_sequence.txt") {
-        Ok(sequence) => sequence,
-        Err(e) => {
-            eprintln!("Failed to read DNA sequence from file: {}", e);
-            return;
+    let mut cli = Cli::parse();
+    init_logging(&cli);
+    if cli.gzip {
+        gzip_outputs(&mut cli);
+    }
+    if let Some(threads) = cli.threads {
+        if let Err(e) = rayon::ThreadPoolBuilder::new().num_threads(threads).build_global() {
+            error!("Failed to start {} counting threads: {}", threads, e);
+            process::exit(1);
+        }
+    }
+    match &cli.command {
+        Some(Command::Generate(args)) => generate(args),
+        Some(Command::Count(args)) => count(args),
+        Some(Command::Graph(args)) => graph(args),
+        Some(Command::ColoredGraph(args)) => colored_graph(args),
+        Some(Command::Contigs(args)) => contigs(args),
+        Some(Command::Plot(args)) => plot(args),
+        Some(Command::Compare(args)) => compare(args),
+        Some(Command::Diff(args)) => diff(args),
+        Some(Command::Batch(args)) => batch(args),
+        Some(Command::Query(args)) => query(args),
+        Some(Command::Stats(args)) => stats(args),
+        Some(Command::Estimate(args)) => estimate(args),
+        Some(Command::SimulateReads(args)) => simulate(args),
+        None => run(&cli.run),
+    }
+}
+
+// Add .gz to every text output of the chosen command, so the writers compress it
+fn gzip_outputs(cli: &mut Cli) {
+    let output = match &mut cli.command {
+        Some(Command::Generate(args)) => args.output.as_mut(),
+        Some(Command::Count(args)) => args.output.as_mut(),
+        Some(Command::Graph(args)) => Some(&mut args.output),
+        Some(Command::ColoredGraph(args)) => Some(&mut args.output),
+        Some(Command::Contigs(args)) => Some(&mut args.output),
+        Some(Command::Compare(args)) => args.output.as_mut(),
+        Some(Command::Diff(args)) => args.output.as_mut(),
+        Some(Command::SimulateReads(args)) => Some(&mut args.output),
+        Some(Command::Stats(args)) => args.output.as_mut(),
+        Some(Command::Batch(args)) => {
+            args.gzip = true;
+            None
+        }
+        Some(Command::Plot(_) | Command::Query(_) | Command::Estimate(_)) => None,
+        None => {
+            cli.run.gzip = true;
+            None
+        }
+    };
+    if let Some(output) = output.filter(|output| !is_gzip_name(&output.to_string_lossy())) {
+        output.as_mut_os_string().push(".gz");
+    }
+}
+
+// The full analysis: counts, plots, graph and its outputs in one go
+fn run(cli: &RunArgs) {
+    create_output_dir(&cli.output_dir);
+    let output_dir = cli.output_dir.as_path();
+
+    let sequences = match &cli.input {
+        // Read every record of the given file
+        Some(input) => read_input(input),
+        None => {
+            // Take the length from the arguments or prompt for it
+            let dna_length = match cli.length {
+                Some(length) => length,
+                None => prompt_number("Enter the length of the DNA sequence:"),
+            };
+
+            // Generate a random DNA sequence and save it to a file
+            let sequence_file = text_output_path(cli.gzip, output_dir, "random_dna_sequence.txt");
+            let dna_sequence = Alphabet::dna().random_sequence(dna_length, cli.seed);
+            match write_to_file(&dna_sequence, &sequence_file) {
+                Ok(_) => info!("DNA sequence saved to {}", sequence_file),
+                Err(e) => error!("Failed to write DNA sequence to file: {}", e),
+            }
+            vec![dna_sequence]
         }
     };
 
-    // Prompt for and read the k-mer size from the user
-    println!("Enter the size of k-mer:");
-    let mut k_str = String::new();
-    io::stdin().read_line(&mut k_str).expect("Failed to read line");
-    let k: usize = k_str.trim().parse().expect("Please type a number!");
+    // Report GC content and base composition over all records
+    let dna_sequence = sequences.concat();
+    let mut report = Report { title: "K-mer analysis report".to_string(), sections: Vec::new() };
+    if cli.report.is_some() {
+        let stats = SequenceStats::of(&dna_sequence);
+        let [a, c, g, t] = stats.base_counts;
+        report.sections.push(ReportSection {
+            title: "Input".to_string(),
+            tables: vec![ReportTable::key_values(
+                "Sequence",
+                &[
+                    ("Records", sequences.len().to_string()),
+                    ("Length", format!("{} bp", stats.length)),
+                    ("GC content", format!("{:.2}%", stats.gc_content() * 100.0)),
+                    ("Base composition", format!("A={} C={} G={} T={} other={}", a, c, g, t, stats.other)),
+                ],
+            )],
+            plots: Vec::new(),
+        });
+    }
+    let composition = base_composition(&dna_sequence);
+    println!("GC content: {:.2}%", gc_content(&dna_sequence) * 100.0);
+    println!(
+        "Base composition: A={} C={} G={} T={} N={} other={}",
+        composition.get(&'A').unwrap_or(&0),
+        composition.get(&'C').unwrap_or(&0),
+        composition.get(&'G').unwrap_or(&0),
+        composition.get(&'T').unwrap_or(&0),
+        composition.get(&'N').unwrap_or(&0),
+        composition.get(&'?').unwrap_or(&0),
+    );
+
+    // Uppercase each record, mark ambiguous bases as N and reject anything else
+    let sequences = clean_sequences(&sequences, cli.ambiguous, &Alphabet::dna());
+
+    // Take the k-mer sizes from the arguments or prompt for one
+    let k_sizes = match &cli.k {
+        Some(KSizes(sizes)) => sizes.clone(),
+        None => vec![prompt_number("Enter the size of k-mer:")],
+    };
+    for &k in &k_sizes {
+        check_k(k, &sequences);
+    }
+    if let [k] = k_sizes[..] {
+        analyse_k(cli, &sequences, k, output_dir, &mut report);
+        write_report(cli, &report);
+        return;
+    }
+
+    // Run each k in its own subdirectory, then compare them side by side
+    let mut summaries = Vec::new();
+    for &k in &k_sizes {
+        println!("== k = {} ==", k);
+        let k_dir = output_dir.join(format!("k{}", k));
+        create_output_dir(&k_dir);
+        summaries.push(analyse_k(cli, &sequences, k, &k_dir, &mut report));
+    }
+    println!("K\tDistinct\tUnique\tTotal");
+    for summary in &summaries {
+        println!("{}\t{}\t{}\t{}", summary.k, summary.distinct, summary.unique, summary.total);
+    }
+    let summary_file = text_output_path(cli.gzip, output_dir, "kmer_summary.csv");
+    match write_kmer_summary_to_csv(&summaries, &summary_file) {
+        Ok(_) => info!("K-mer summary saved to {}", summary_file),
+        Err(e) => error!("Failed to write k-mer summary to CSV: {}", e),
+    }
+    write_report(cli, &report);
+}
+
+// Write the HTML report if --report asked for one
+fn write_report(cli: &RunArgs, report: &Report) {
+    if let Some(file) = &cli.report {
+        match write_html_report(report, &file.to_string_lossy()) {
+            Ok(_) => info!("Report saved to {}", file.display()),
+            Err(e) => error!("Failed to write HTML report: {}", e),
+        }
+    }
+}
 
-    // Generate k-mers from the DNA sequence
-    let kmers = generate_kmers(&dna_sequence, k);
+// Count, plot and build the graph at one k, writing the outputs to output_dir and adding a
+// section to the report if one was asked for
+fn analyse_k(cli: &RunArgs, sequences: &[String], k: usize, output_dir: &Path, report: &mut Report) -> KmerSummary {
+    // Generate k-mers from the records
+    let kmers = record_kmers(sequences, k, &Alphabet::dna());
 
     // Count the frequency of each k-mer
-    let kmer_counts = count_kmers(kmers.clone()); // Clone kmers for further use
+    let kmer_counts = step("Counting k-mers", || count_records_parallel(sequences, k, cli.canonical));
+
+    // Compare against the second sequence, if one was given
+    if let Some(input2) = &cli.input2 {
+        match step(&format!("Counting k-mers in {}", input2.display()), || count_kmers_in_file(&input2.to_string_lossy(), k, cli.canonical)) {
+            Ok(other_counts) => {
+                println!("Jaccard similarity: {:.4}", jaccard_similarity(&kmer_counts, &other_counts));
+                println!("Weighted Jaccard similarity: {:.4}", weighted_jaccard_similarity(&kmer_counts, &other_counts));
+            }
+            Err(e) => error!("Failed to read DNA sequence from {}: {}", input2.display(), e),
+        }
+    }
 
     // Plot the k-mer histogram
-    match plot_kmer_histogram(&kmer_counts, "kmer_histogram.png") {
-        Ok(_) => println!("K-mer histogram plotted in kmer_histogram.png"),
-        Err(e) => eprintln!("Failed to plot k-mer histogram: {}", e),
+    let extension = match cli.plot_format {
+        PlotFormat::Png => "png",
+        PlotFormat::Svg => "svg",
+    };
+    let histogram_file = output_path(output_dir, &format!("kmer_histogram.{}", extension));
+    match plot_kmer_histogram(&kmer_counts, &histogram_file, 640, 480) {
+        Ok(_) => info!("K-mer histogram plotted in {}", histogram_file),
+        Err(e) => error!("Failed to plot k-mer histogram: {}", e),
+    }
+    // Plot the k-mer spectrum
+    let spectrum_file = output_path(output_dir, &format!("kmer_spectrum.{}", extension));
+    let spectrum = compute_spectrum(&kmer_counts);
+    match plot_spectrum(&spectrum, &spectrum_file, SpectrumAxes::default()) {
+        Ok(_) => info!("K-mer spectrum plotted in {}", spectrum_file),
+        Err(e) => error!("Failed to plot k-mer spectrum: {}", e),
     }
-    // Optionally, display k-mer counts
-    for (kmer, count) in &kmer_counts {
-        println!("{}: {}", kmer, count);
+    // Display the most frequent k-mers, or every count
+    match cli.top {
+        Some(n) => {
+            for (kmer, count) in top_kmers(&kmer_counts, n) {
+                println!("{}: {}", kmer, count);
+            }
+        }
+        None => {
+            for (kmer, count) in sorted_counts(&kmer_counts, count_order(cli.sort)) {
+                println!("{}: {}", kmer, count);
+            }
+        }
     }
 
     // Create a De Bruijn graph from the k-mers
-    let dbg = DeBruijnGraph::new(&kmers);
+    let dbg = step("Building the De Bruijn graph", || DeBruijnGraph::new(&kmers));
 
     // Display the De Bruijn graph
     dbg.display();
+    // Report the unambiguous stretches of the graph
+    let contigs = dbg.contigs();
+    let longest = contigs.iter().map(String::len).max().unwrap_or(0);
+    println!("Contigs: {} (longest {} bp)", contigs.len(), longest);
+    // Reassemble the sequence from the graph
+    let assembly = dbg.assemble();
+    let longest = assembly.iter().map(String::len).max().unwrap_or(0);
+    println!("Assembly: {} sequence(s) (longest {} bp)", assembly.len(), longest);
     // Convert to petgraph graph
     let graph = create_petgraph(&dbg);
 
     // Save graph to DOT file
-    if let Err(e) = save_graph_dot(&graph, "de_bruijn_graph.dot") {
-        eprintln!("Failed to save graph to DOT file: {}", e);
+    if let Err(e) = save_graph_dot(&graph, &text_output_path(cli.gzip, output_dir, "de_bruijn_graph.dot")) {
+        error!("Failed to save graph to DOT file: {}", e);
     }
-    // Write k-mer counts to an Excel file
-    match write_kmer_counts_to_csv(&kmer_counts, "kmer_counts.csv") {
-        Ok(_) => println!("K-mer counts saved to kmer_counts.csv"),
-        Err(e) => eprintln!("Failed to write k-mer counts to CSV: {}", e),
+    // Write k-mer counts and the graph in the requested formats
+    if cli.format != OutputFormat::Json {
+        let counts_file = text_output_path(cli.gzip, output_dir, "kmer_counts.csv");
+        match write_kmer_counts_to_csv(&kmer_counts, count_order(cli.sort), &counts_file) {
+            Ok(_) => info!("K-mer counts saved to {}", counts_file),
+            Err(e) => error!("Failed to write k-mer counts to CSV: {}", e),
+        }
+        let graph_file = text_output_path(cli.gzip, output_dir, "de_bruijn_graph.csv");
+        match write_graph_to_csv(&dbg, &graph_file) {
+            Ok(_) => info!("De Bruijn graph saved to {}", graph_file),
+            Err(e) => error!("Failed to write De Bruijn graph to CSV: {}", e),
+        }
+    }
+    if cli.format != OutputFormat::Csv {
+        let counts_file = text_output_path(cli.gzip, output_dir, "kmer_counts.json");
+        match write_kmer_counts_to_json(&kmer_counts, &counts_file) {
+            Ok(_) => info!("K-mer counts saved to {}", counts_file),
+            Err(e) => error!("Failed to write k-mer counts to JSON: {}", e),
+        }
+        let graph_file = text_output_path(cli.gzip, output_dir, "de_bruijn_graph.json");
+        match write_graph_to_json(&dbg, &graph_file) {
+            Ok(_) => info!("De Bruijn graph saved to {}", graph_file),
+            Err(e) => error!("Failed to write De Bruijn graph to JSON: {}", e),
+        }
+    }
+
+    let summary = KmerSummary::from_counts(k, &kmer_counts);
+    if cli.report.is_some() {
+        report.sections.push(report_section(&summary, &kmer_counts, &spectrum, &dbg, &contigs, assembly.len()));
     }
-    match write_graph_to_csv(&dbg, "de_bruijn_graph.csv") {
-        Ok(_) => println!("De Bruijn graph saved to de_bruijn_graph.csv"),
-        Err(e) => eprintln!("Failed to write De Bruijn graph to CSV: {}", e),
+    summary
+}
+
+// The report section of one k: count and graph tables, then the spectrum and (for modest
+// inputs) the histogram
+fn report_section(
+    summary: &KmerSummary,
+    kmer_counts: &HashMap<String, usize>,
+    spectrum: &BTreeMap<usize, usize>,
+    dbg: &DeBruijnGraph,
+    contigs: &[String],
+    assembled: usize,
+) -> ReportSection {
+    let counts = ReportTable::key_values(
+        "K-mer counts",
+        &[
+            ("Distinct", summary.distinct.to_string()),
+            ("Unique", summary.unique.to_string()),
+            ("Total", summary.total.to_string()),
+        ],
+    );
+    let top = ReportTable {
+        title: "Most frequent k-mers".to_string(),
+        header: vec!["K-mer".to_string(), "Count".to_string()],
+        rows: top_kmers(kmer_counts, 10).into_iter().map(|(kmer, count)| vec![kmer, count.to_string()]).collect(),
+    };
+    let lengths: Vec<usize> = contigs.iter().map(String::len).collect();
+    let assembly = AssemblyStats::from_lengths(&lengths);
+    let graph = ReportTable::key_values(
+        "De Bruijn graph",
+        &[
+            ("Nodes", dbg.node_count().to_string()),
+            ("Edges", dbg.edge_count().to_string()),
+            ("Contigs", assembly.contigs.to_string()),
+            ("Contig length", format!("{} bp", assembly.total_length)),
+            ("N50", format!("{} bp", assembly.n50)),
+            ("Longest contig", format!("{} bp", assembly.longest)),
+            ("Assembled sequences", assembled.to_string()),
+        ],
+    );
+    let mut plots = Vec::new();
+    match spectrum_svg(spectrum, SpectrumAxes::default()) {
+        Ok(svg) => plots.push(svg),
+        Err(e) => error!("Failed to plot k-mer spectrum for the report: {}", e),
+    }
+    if kmer_counts.len() <= REPORT_HISTOGRAM_MAX_KMERS {
+        match kmer_histogram_svg(kmer_counts, 640, 480) {
+            Ok(svg) => plots.push(svg),
+            Err(e) => error!("Failed to plot k-mer histogram for the report: {}", e),
+        }
     }
+    ReportSection { title: format!("k = {}", summary.k), tables: vec![counts, top, graph], plots }
 }
@@ -0,0 +1,238 @@
+//! Placing reads and sequences against each other by shared k-mers.
+
+use std::collections::{HashMap, HashSet};
+use crate::kmer::{canonical_kmer, generate_kmers, kmer_positions, reverse_complement};
+
+/// Which strand of a reference a match lies on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strand {
+    /// Matches the reference as given.
+    Forward,
+    /// Matches the reverse complement of the reference.
+    Reverse,
+    /// No shared k-mers, or as many on each strand.
+    Unknown,
+}
+
+/// Find k-mers shared between two sequences along with their orientation, for synteny dotplots.
+/// Each (position in a, position in b, strand) is Forward when b holds the same k-mer and Reverse
+/// when b holds its reverse complement; palindromic k-mers are reported on both strands.
+pub fn shared_kmers_oriented(a: &str, b: &str, k: usize) -> Vec<(usize, usize, Strand)> {
+    let index_b = kmer_positions(b, k);
+    let mut matches = Vec::new();
+    for (kmer, positions_a) in kmer_positions(a, k) {
+        for (strand, target) in [(Strand::Forward, kmer.clone()), (Strand::Reverse, reverse_complement(&kmer))] {
+            if let Some(positions_b) = index_b.get(&target) {
+                for &i in &positions_a {
+                    for &j in positions_b {
+                        matches.push((i, j, strand));
+                    }
+                }
+            }
+        }
+    }
+    matches.sort_by_key(|&(i, j, strand)| (i, j, strand == Strand::Reverse));
+    matches
+}
+
+/// Decide which strand a read came from by counting how many of its forward k-mers, versus
+/// the k-mers of its reverse complement, occur in the reference set. A strand must have at
+/// least twice the hits of the other to dominate; otherwise the result is Unknown.
+pub fn detect_strand(read: &str, reference_kmers: &HashSet<String>, k: usize) -> Strand {
+    if k == 0 || k > read.len() {
+        return Strand::Unknown;
+    }
+    let hits = |seq: &str| {
+        generate_kmers(seq, k)
+            .iter()
+            .filter(|kmer| reference_kmers.contains(*kmer))
+            .count()
+    };
+    let forward = hits(read);
+    let reverse = hits(&reverse_complement(read));
+
+    if forward > 2 * reverse {
+        Strand::Forward
+    } else if reverse > 2 * forward {
+        Strand::Reverse
+    } else {
+        Strand::Unknown
+    }
+}
+
+/// Per canonical k-mer, log2 of forward- over reverse-strand coverage (each +1 so k-mers seen on
+/// only one strand stay finite). Balanced k-mers sit near 0; large magnitudes flag strand bias.
+pub fn strand_coverage_asymmetry(
+    forward_counts: &HashMap<String, usize>,
+    reverse_counts: &HashMap<String, usize>,
+) -> HashMap<String, f64> {
+    let mut coverage: HashMap<String, (usize, usize)> = HashMap::new();
+    for (kmer, &count) in forward_counts {
+        coverage.entry(canonical_kmer(kmer)).or_default().0 += count;
+    }
+    for (kmer, &count) in reverse_counts {
+        coverage.entry(canonical_kmer(kmer)).or_default().1 += count;
+    }
+    coverage
+        .into_iter()
+        .map(|(kmer, (forward, reverse))| (kmer, ((forward + 1) as f64 / (reverse + 1) as f64).log2()))
+        .collect()
+}
+
+/// Place a read on the reference by letting each of its k-mers vote for the read's start position.
+/// Both orientations are tried; returns the winning (start, end) span on the reference, if any k-mer hit.
+pub fn map_read_by_kmers(read: &str, reference_index: &HashMap<String, Vec<usize>>, k: usize) -> Option<(usize, usize)> {
+    let mut best: Option<(usize, usize)> = None;
+    let mut best_votes = 0;
+    for oriented in [read.to_string(), reverse_complement(read)] {
+        let mut votes: HashMap<usize, usize> = HashMap::new();
+        for (kmer, read_positions) in kmer_positions(&oriented, k) {
+            if let Some(hits) = reference_index.get(&kmer) {
+                for &hit in hits {
+                    for &read_pos in &read_positions {
+                        if hit >= read_pos {
+                            *votes.entry(hit - read_pos).or_insert(0) += 1;
+                        }
+                    }
+                }
+            }
+        }
+        if let Some((&start, &count)) = votes.iter().max_by_key(|&(&start, &count)| (count, std::cmp::Reverse(start))) {
+            if count > best_votes {
+                best_votes = count;
+                best = Some((start, start + oriented.len()));
+            }
+        }
+    }
+    best
+}
+
+/// Estimate the insert (fragment) size of each read pair by anchoring both mates on the
+/// reference with shared k-mers and measuring the outer distance spanned by the pair.
+/// Pairs where either mate doesn't map are skipped.
+pub fn estimate_insert_sizes(pairs: &[(String, String)], reference: &str, k: usize) -> Vec<usize> {
+    let reference_index = kmer_positions(reference, k);
+    pairs
+        .iter()
+        .filter_map(|(mate1, mate2)| {
+            let (start1, end1) = map_read_by_kmers(mate1, &reference_index, k)?;
+            let (start2, end2) = map_read_by_kmers(mate2, &reference_index, k)?;
+            Some(end1.max(end2) - start1.min(start2))
+        })
+        .collect()
+}
+
+/// Find likely chimeric contigs using paired reads: each pair whose mates both map to the same
+/// contig supports the span between them, and an internal stretch that no pair spans, with pair
+/// support on both sides, marks a misjoin. Returns (contig index, split position) at the middle
+/// of each unsupported stretch.
+pub fn detect_chimeric_contigs(contigs: &[String], pairs: &[(String, String)], k: usize) -> Vec<(usize, usize)> {
+    let mut splits = Vec::new();
+    for (contig_index, contig) in contigs.iter().enumerate() {
+        let index = kmer_positions(contig, k);
+        // support[p] counts pair spans crossing the boundary between bases p - 1 and p.
+        let mut support = vec![0i64; contig.len() + 1];
+        for (mate1, mate2) in pairs {
+            let (Some((start1, end1)), Some((start2, end2))) =
+                (map_read_by_kmers(mate1, &index, k), map_read_by_kmers(mate2, &index, k))
+            else {
+                continue;
+            };
+            let (start, end) = (start1.min(start2), end1.max(end2).min(contig.len()));
+            if start + 1 < end {
+                support[start + 1] += 1;
+                support[end] -= 1;
+            }
+        }
+        let mut running = 0;
+        let covered: Vec<bool> = support
+            .iter()
+            .map(|delta| {
+                running += delta;
+                running > 0
+            })
+            .collect();
+
+        let (Some(first), Some(last)) = (covered.iter().position(|&c| c), covered.iter().rposition(|&c| c)) else {
+            continue;
+        };
+        let mut position = first;
+        while position < last {
+            if covered[position] {
+                position += 1;
+                continue;
+            }
+            let gap_start = position;
+            while !covered[position] {
+                position += 1;
+            }
+            splits.push((contig_index, (gap_start + position - 1) / 2));
+        }
+    }
+    splits
+}
+
+/// Break contigs at the given (contig index, position) split points.
+pub fn split_contigs(contigs: &[String], splits: &[(usize, usize)]) -> Vec<String> {
+    let mut pieces = Vec::new();
+    for (contig_index, contig) in contigs.iter().enumerate() {
+        let mut cuts: Vec<usize> = splits
+            .iter()
+            .filter(|&&(index, position)| index == contig_index && position > 0 && position < contig.len())
+            .map(|&(_, position)| position)
+            .collect();
+        cuts.sort_unstable();
+        cuts.dedup();
+        let mut start = 0;
+        for cut in cuts {
+            pieces.push(contig[start..cut].to_string());
+            start = cut;
+        }
+        pieces.push(contig[start..].to_string());
+    }
+    pieces
+}
+
+/// Find shared k-mer anchors (position in a, position in b) and chain them into the
+/// longest collinear set, i.e. anchors strictly increasing in both sequences.
+/// The chain is the longest increasing subsequence of b-positions once anchors are sorted by a-position.
+pub fn anchor_chain(a: &str, b: &str, k: usize) -> Vec<(usize, usize)> {
+    let index_b = kmer_positions(b, k);
+    let mut anchors = Vec::new();
+    for (kmer, positions_a) in kmer_positions(a, k) {
+        if let Some(positions_b) = index_b.get(&kmer) {
+            for &i in &positions_a {
+                for &j in positions_b {
+                    anchors.push((i, j));
+                }
+            }
+        }
+    }
+    // Sorting b-positions descending within the same a-position stops two anchors
+    // at one a-position from both joining the chain.
+    anchors.sort_by(|x, y| x.0.cmp(&y.0).then(y.1.cmp(&x.1)));
+
+    // tails[len] holds the index of the anchor ending the best chain of length len + 1.
+    let mut tails: Vec<usize> = Vec::new();
+    let mut previous: Vec<Option<usize>> = vec![None; anchors.len()];
+    for (idx, &(_, j)) in anchors.iter().enumerate() {
+        let len = tails.partition_point(|&t| anchors[t].1 < j);
+        if len > 0 {
+            previous[idx] = Some(tails[len - 1]);
+        }
+        if len == tails.len() {
+            tails.push(idx);
+        } else {
+            tails[len] = idx;
+        }
+    }
+
+    let mut chain = Vec::new();
+    let mut current = tails.last().copied();
+    while let Some(idx) = current {
+        chain.push(anchors[idx]);
+        current = previous[idx];
+    }
+    chain.reverse();
+    chain
+}
@@ -0,0 +1,80 @@
+//! Window minimizers: a sampled subset of k-mers that every window of w consecutive k-mers is
+//! guaranteed to contribute to, for counting and building graphs in a fraction of the memory.
+
+use std::collections::{HashMap, VecDeque};
+use crate::kmer::{canonical_kmer, count_kmers};
+
+/// Select window minimizers: for every run of w consecutive k-mers, the lexicographically
+/// smallest one (leftmost on ties) with its position. A selection shared by overlapping windows
+/// is emitted only once. If the sequence has fewer than w k-mers, they form a single window.
+/// A monotone deque of candidate positions keeps this linear in the sequence length.
+pub fn minimizers(dna_sequence: &str, k: usize, w: usize) -> Vec<(usize, String)> {
+    if k == 0 || w == 0 || k > dna_sequence.len() {
+        return Vec::new();
+    }
+    let kmers: Vec<&str> = (0..=dna_sequence.len() - k).map(|i| &dna_sequence[i..i + k]).collect();
+    window_minima(&kmers, w)
+        .into_iter()
+        .map(|position| (position, kmers[position].to_string()))
+        .collect()
+}
+
+/// Like minimizers, but k-mers are compared and returned in canonical form, so a sequence and
+/// its reverse complement select the same minimizers.
+pub fn canonical_minimizers(dna_sequence: &str, k: usize, w: usize) -> Vec<(usize, String)> {
+    if k == 0 || w == 0 || k > dna_sequence.len() {
+        return Vec::new();
+    }
+    let kmers: Vec<String> = (0..=dna_sequence.len() - k).map(|i| canonical_kmer(&dna_sequence[i..i + k])).collect();
+    window_minima(&kmers, w)
+        .into_iter()
+        .map(|position| (position, kmers[position].clone()))
+        .collect()
+}
+
+/// The minimizer k-mers of every record, in order, for the counting and graph pipelines.
+/// Windows never span a non-ACGT base or a record boundary: each run of ACGT bases is
+/// minimized on its own, as counting skips the k-mers spanning an N.
+pub fn record_minimizers(sequences: &[String], k: usize, w: usize, canonical: bool) -> Vec<String> {
+    let select = if canonical { canonical_minimizers } else { minimizers };
+    sequences
+        .iter()
+        .flat_map(|sequence| sequence.split(|base| !matches!(base, 'A' | 'C' | 'G' | 'T')))
+        .flat_map(|run| select(run, k, w))
+        .map(|(_, kmer)| kmer)
+        .collect()
+}
+
+/// Count only the window minimizers of every record (see record_minimizers). A k-mer is
+/// counted once per run of windows that selects it, so counts scale with coverage as exact
+/// counts do while only about 2 / (w + 1) of the distinct k-mers are kept.
+pub fn count_minimizers(sequences: &[String], k: usize, w: usize, canonical: bool) -> HashMap<String, usize> {
+    count_kmers(record_minimizers(sequences, k, w, canonical))
+}
+
+// Positions of the window minima of `keys` over windows of w (or all, if fewer) keys
+fn window_minima<T: Ord>(keys: &[T], w: usize) -> Vec<usize> {
+    let window = w.min(keys.len());
+    let mut selected: Vec<usize> = Vec::new();
+    // Positions in the current window whose keys are non-decreasing from front to back (equal
+    // keys kept, so ties go to the leftmost); the front is the window's minimizer.
+    let mut candidates: VecDeque<usize> = VecDeque::new();
+    for (position, key) in keys.iter().enumerate() {
+        while candidates.back().is_some_and(|&back| keys[back] > *key) {
+            candidates.pop_back();
+        }
+        candidates.push_back(position);
+        if position + 1 < window {
+            continue;
+        }
+        let start = position + 1 - window;
+        while candidates.front().is_some_and(|&front| front < start) {
+            candidates.pop_front();
+        }
+        let minimizer = candidates[0];
+        if selected.last() != Some(&minimizer) {
+            selected.push(minimizer);
+        }
+    }
+    selected
+}
@@ -0,0 +1,104 @@
+//! ntHash rolling hashes of DNA k-mers (Mohamadi et al., 2016). Sliding the window one base
+//! updates the previous hash in O(1) instead of hashing all k bases again, and both strands are
+//! hashed at once, so canonical hashes come without building reverse complements.
+
+// Random seeds of A, C, G and T; a base's complement has the seed at 3 - its index
+const SEEDS: [u64; 4] = [0x3c8b_fbb3_95c6_0474, 0x3193_c185_62a0_2b4c, 0x2032_3ed0_8257_2324, 0x2955_49f5_4be2_4456];
+
+/// Rolling hashes of the k-mers of a DNA sequence, as (start position, hash) in sequence order.
+/// Windows holding anything but uppercase A, C, G and T are skipped, as generate_kmers_filtered
+/// skips them, and hashing restarts after them. With `canonical` set a k-mer and its reverse
+/// complement hash alike.
+pub struct NtHash<'a> {
+    seq: &'a [u8],
+    k: usize,
+    canonical: bool,
+    // Start of the next window
+    next: usize,
+    // Forward and reverse strand hashes of the window before `next`, if it was hashed
+    hashes: Option<(u64, u64)>,
+}
+
+impl<'a> NtHash<'a> {
+    /// Hash the k-mers of `seq`; k = 0 gives none.
+    pub fn new(seq: &'a str, k: usize, canonical: bool) -> Self {
+        NtHash { seq: seq.as_bytes(), k, canonical, next: 0, hashes: None }
+    }
+}
+
+impl Iterator for NtHash<'_> {
+    type Item = (usize, u64);
+
+    fn next(&mut self) -> Option<(usize, u64)> {
+        let k = self.k;
+        while k > 0 && self.next + k <= self.seq.len() {
+            let start = self.next;
+            let hashes = match self.hashes {
+                Some((forward, reverse)) => {
+                    let Some(incoming) = code(self.seq[start + k - 1]) else {
+                        self.hashes = None;
+                        self.next = start + k;
+                        continue;
+                    };
+                    // The outgoing base was part of the previous, valid window
+                    let outgoing = code(self.seq[start - 1]).unwrap_or(0);
+                    (
+                        forward.rotate_left(1) ^ SEEDS[outgoing].rotate_left(k as u32) ^ SEEDS[incoming],
+                        reverse.rotate_right(1)
+                            ^ SEEDS[3 - outgoing].rotate_right(1)
+                            ^ SEEDS[3 - incoming].rotate_left((k - 1) as u32),
+                    )
+                }
+                None => match self.seq[start..start + k].iter().rposition(|&base| code(base).is_none()) {
+                    Some(offset) => {
+                        self.next = start + offset + 1;
+                        continue;
+                    }
+                    None => hash_window(&self.seq[start..start + k]),
+                },
+            };
+            self.hashes = Some(hashes);
+            self.next = start + 1;
+            let (forward, reverse) = hashes;
+            return Some((start, if self.canonical { forward.wrapping_add(reverse) } else { forward }));
+        }
+        None
+    }
+}
+
+/// The NtHash hash of a single k-mer, or None if it is empty or holds anything but A, C, G
+/// and T.
+pub fn nthash(kmer: &str, canonical: bool) -> Option<u64> {
+    NtHash::new(kmer, kmer.len(), canonical).next().map(|(_, hash)| hash)
+}
+
+/// A further hash of a k-mer from its hash and a seed, for structures that probe several
+/// cells per k-mer, such as Bloom filters and Count-Min sketch rows.
+pub fn rehash(hash: u64, seed: u64) -> u64 {
+    // SplitMix64 finalizer of the hash offset by the seed
+    let mut x = hash ^ seed.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+// Index of an uppercase base into SEEDS
+fn code(base: u8) -> Option<usize> {
+    match base {
+        b'A' => Some(0),
+        b'C' => Some(1),
+        b'G' => Some(2),
+        b'T' => Some(3),
+        _ => None,
+    }
+}
+
+// Forward and reverse strand hashes of a window of bases, computed from scratch: base i of k is
+// rotated by k - 1 - i on the forward strand and its complement by i on the reverse strand
+fn hash_window(window: &[u8]) -> (u64, u64) {
+    let k = window.len();
+    window.iter().enumerate().fold((0, 0), |(forward, reverse), (i, &base)| {
+        let code = code(base).unwrap_or(0);
+        (forward ^ SEEDS[code].rotate_left((k - 1 - i) as u32), reverse ^ SEEDS[3 - code].rotate_left(i as u32))
+    })
+}
@@ -0,0 +1,353 @@
+//! Plotting with plotters.
+
+use std::collections::{BTreeMap, HashMap};
+use plotters::coord::ranged1d::{AsRangedCoord, ValueFormatter};
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use crate::analysis::KmerDiff;
+use crate::error::KmerError;
+use crate::stats::WindowStats;
+
+/// Whether a plot file is drawn as SVG, which its `.svg` extension selects; any other name is
+/// drawn as a PNG bitmap.
+pub fn is_svg_name(file_name: &str) -> bool {
+    file_name.to_ascii_lowercase().ends_with(".svg")
+}
+
+/// Function to plot a histogram of k-mer counts at the given image size in pixels, as SVG if
+/// the file name ends in `.svg` and PNG otherwise.
+/// Returns an error without creating the file when there are no counts.
+pub fn plot_kmer_histogram(
+    kmer_counts: &HashMap<String, usize>,
+    output_file: &str,
+    width: u32,
+    height: u32,
+) -> Result<(), KmerError> {
+    // Check before creating the backend, which would otherwise leave a blank image behind
+    if kmer_counts.is_empty() {
+        return Err(KmerError::NothingToPlot("k-mer counts"));
+    }
+    // Create a drawing area for the plot, specifying the output file and dimensions
+    if is_svg_name(output_file) {
+        draw_histogram(SVGBackend::new(output_file, (width, height)).into_drawing_area(), kmer_counts)
+    } else {
+        draw_histogram(BitMapBackend::new(output_file, (width, height)).into_drawing_area(), kmer_counts)
+    }
+}
+
+/// Draw the k-mer histogram of plot_kmer_histogram as an SVG document in memory, for embedding
+/// in an HTML report.
+pub fn kmer_histogram_svg(kmer_counts: &HashMap<String, usize>, width: u32, height: u32) -> Result<String, KmerError> {
+    let mut svg = String::new();
+    draw_histogram(SVGBackend::with_string(&mut svg, (width, height)).into_drawing_area(), kmer_counts)?;
+    Ok(svg)
+}
+
+// Draw the histogram on any backend
+fn draw_histogram<DB>(root_area: DrawingArea<DB, Shift>, kmer_counts: &HashMap<String, usize>) -> Result<(), KmerError>
+where
+    DB: DrawingBackend,
+{
+    // Determine the maximum count for the y-axis
+    let Some(&max_count) = kmer_counts.values().max() else {
+        return Err(KmerError::NothingToPlot("k-mer counts"));
+    };
+    // Leave some headroom so the tallest bar isn't clipped at the top border
+    let y_max = max_count + (max_count / 10).max(1);
+
+    // Fill the drawing area with a white background
+    root_area.fill(&WHITE)?;
+
+    // Build the chart with specified caption, margins, and axis sizes
+    let mut chart = ChartBuilder::on(&root_area)
+        .caption("K-mer Frequency Histogram", ("sans-serif", 40))
+        .margin(5)
+        .x_label_area_size(30)
+        .y_label_area_size(40)
+        .build_cartesian_2d(0usize..kmer_counts.len(), 0usize..y_max)?;
+
+    // Configure and draw the mesh (grid lines) for the chart
+    chart.configure_mesh().draw()?;
+
+    // Convert k-mer count data into a format suitable for the histogram
+    let data: Vec<(usize, usize)> = kmer_counts.iter().enumerate().map(|(index, (_kmer, &count))| (index, count)).collect();
+
+    // Draw the histogram series on the chart
+    chart.draw_series(
+        Histogram::vertical(&chart)
+            .style(RED.filled())
+            .data(data.iter().map(|&(index, count)| (index, count))),
+    )?;
+
+
+    // Present the final plot
+    root_area.present()?;
+    Ok(())
+}
+
+/// Axis scales for [`plot_spectrum`]. Log axes keep the low-count error spike and the long
+/// tail of repeats on the same plot as the genomic peak.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SpectrumAxes {
+    /// Draw the multiplicity axis on a log scale.
+    pub log_x: bool,
+    /// Draw the distinct k-mer axis on a log scale.
+    pub log_y: bool,
+}
+
+/// Function to plot the k-mer spectrum: distinct k-mers (y) at each multiplicity (x), as SVG if
+/// the file name ends in `.svg` and PNG otherwise.
+/// Unlike the per-k-mer histogram this stays readable for large inputs, showing the error
+/// spike at multiplicity 1 apart from the genomic peak.
+pub fn plot_spectrum(
+    spectrum: &BTreeMap<usize, usize>,
+    output_file: &str,
+    axes: SpectrumAxes,
+) -> Result<(), KmerError> {
+    // Check before creating the backend, which would otherwise leave a blank image behind
+    if spectrum.is_empty() {
+        return Err(KmerError::NothingToPlot("k-mer counts"));
+    }
+    if is_svg_name(output_file) {
+        render_spectrum(SVGBackend::new(output_file, (640, 480)).into_drawing_area(), spectrum, axes)
+    } else {
+        render_spectrum(BitMapBackend::new(output_file, (640, 480)).into_drawing_area(), spectrum, axes)
+    }
+}
+
+/// Draw the k-mer spectrum of plot_spectrum as an SVG document in memory, for embedding in an
+/// HTML report.
+pub fn spectrum_svg(spectrum: &BTreeMap<usize, usize>, axes: SpectrumAxes) -> Result<String, KmerError> {
+    let mut svg = String::new();
+    render_spectrum(SVGBackend::with_string(&mut svg, (640, 480)).into_drawing_area(), spectrum, axes)?;
+    Ok(svg)
+}
+
+// Pick the axis ranges and draw the spectrum on any backend
+fn render_spectrum<DB>(root_area: DrawingArea<DB, Shift>, spectrum: &BTreeMap<usize, usize>, axes: SpectrumAxes) -> Result<(), KmerError>
+where
+    DB: DrawingBackend,
+{
+    let (Some(&max_multiplicity), Some(&max_kmers)) = (spectrum.keys().max(), spectrum.values().max()) else {
+        return Err(KmerError::NothingToPlot("k-mer counts"));
+    };
+    let x_max = max_multiplicity as f64 + 1.0;
+    let y_max = max_kmers as f64 * if axes.log_y { 2.0 } else { 1.1 };
+    // A log axis can't start at zero; every plotted value is at least 1
+    let x_min = if axes.log_x { 0.5 } else { 0.0 };
+    let y_min = if axes.log_y { 0.5 } else { 0.0 };
+
+    root_area.fill(&WHITE)?;
+
+    match (axes.log_x, axes.log_y) {
+        (false, false) => draw_spectrum(&root_area, spectrum, x_min..x_max, y_min..y_max, y_min)?,
+        (true, false) => draw_spectrum(&root_area, spectrum, (x_min..x_max).log_scale(), y_min..y_max, y_min)?,
+        (false, true) => draw_spectrum(&root_area, spectrum, x_min..x_max, (y_min..y_max).log_scale(), y_min)?,
+        (true, true) => draw_spectrum(&root_area, spectrum, (x_min..x_max).log_scale(), (y_min..y_max).log_scale(), y_min)?,
+    }
+
+    root_area.present()?;
+    Ok(())
+}
+
+// Draw the spectrum bars on whichever pair of axis scales the caller picked
+fn draw_spectrum<DB, X, Y>(
+    root_area: &DrawingArea<DB, Shift>,
+    spectrum: &BTreeMap<usize, usize>,
+    x_range: X,
+    y_range: Y,
+    baseline: f64,
+) -> Result<(), KmerError>
+where
+    DB: DrawingBackend,
+    X: AsRangedCoord<Value = f64>,
+    Y: AsRangedCoord<Value = f64>,
+    X::CoordDescType: ValueFormatter<f64>,
+    Y::CoordDescType: ValueFormatter<f64>,
+{
+    let mut chart = ChartBuilder::on(root_area)
+        .caption("K-mer Spectrum", ("sans-serif", 40))
+        .margin(5)
+        .x_label_area_size(30)
+        .y_label_area_size(40)
+        .build_cartesian_2d(x_range, y_range)?;
+
+    chart
+        .configure_mesh()
+        .x_desc("Multiplicity")
+        .y_desc("Distinct k-mers")
+        .draw()?;
+
+    // One bar per multiplicity, centred on it
+    chart.draw_series(spectrum.iter().map(|(&multiplicity, &kmers)| {
+        let x = multiplicity as f64;
+        Rectangle::new([(x - 0.4, baseline), (x + 0.4, kmers as f64)], RED.filled())
+    }))?;
+    Ok(())
+}
+
+/// Function to plot each k-mer's count in sample B (y) against its count in sample A (x), from
+/// diff_counts, on log axes of count + 1 so k-mers missing from one sample sit on the axis; a
+/// grey diagonal marks equal counts. SVG if the file name ends in `.svg`, PNG otherwise.
+pub fn plot_count_scatter(diffs: &[KmerDiff], output_file: &str) -> Result<(), KmerError> {
+    // Check before creating the backend, which would otherwise leave a blank image behind
+    if diffs.is_empty() {
+        return Err(KmerError::NothingToPlot("k-mer counts"));
+    }
+    if is_svg_name(output_file) {
+        draw_count_scatter(SVGBackend::new(output_file, (640, 640)).into_drawing_area(), diffs)
+    } else {
+        draw_count_scatter(BitMapBackend::new(output_file, (640, 640)).into_drawing_area(), diffs)
+    }
+}
+
+// Draw the count scatter on any backend
+fn draw_count_scatter<DB>(root_area: DrawingArea<DB, Shift>, diffs: &[KmerDiff]) -> Result<(), KmerError>
+where
+    DB: DrawingBackend,
+{
+    let Some(max_count) = diffs.iter().map(|diff| diff.count_a.max(diff.count_b)).max() else {
+        return Err(KmerError::NothingToPlot("k-mer counts"));
+    };
+    let axis_max = (max_count + 1) as f64 * 2.0;
+
+    root_area.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root_area)
+        .caption("K-mer Counts: A vs B", ("sans-serif", 40))
+        .margin(5)
+        .x_label_area_size(30)
+        .y_label_area_size(40)
+        .build_cartesian_2d((0.5..axis_max).log_scale(), (0.5..axis_max).log_scale())?;
+
+    chart
+        .configure_mesh()
+        .x_desc("Count in A + 1")
+        .y_desc("Count in B + 1")
+        .draw()?;
+
+    chart.draw_series(LineSeries::new([(0.5, 0.5), (axis_max, axis_max)], &RGBColor(160, 160, 160)))?;
+    chart.draw_series(
+        diffs
+            .iter()
+            .map(|diff| Circle::new(((diff.count_a + 1) as f64, (diff.count_b + 1) as f64), 2, RED.mix(0.5).filled())),
+    )?;
+
+    root_area.present()?;
+    Ok(())
+}
+
+/// Function to plot the distribution of contig mean coverages, binned to whole numbers
+pub fn plot_contig_coverage_spectrum(spectrum: &[(String, f64)], output_file: &str) -> Result<(), KmerError> {
+    if spectrum.is_empty() {
+        return Err(KmerError::NothingToPlot("contigs"));
+    }
+
+    // Count how many contigs fall into each whole-number coverage bin
+    let mut bins: HashMap<usize, usize> = HashMap::new();
+    for (_, coverage) in spectrum {
+        *bins.entry(coverage.round() as usize).or_insert(0) += 1;
+    }
+    let max_coverage = *bins.keys().max().unwrap();
+    let max_contigs = *bins.values().max().unwrap();
+
+    let root_area = BitMapBackend::new(output_file, (640, 480)).into_drawing_area();
+    root_area.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root_area)
+        .caption("Contig Coverage Spectrum", ("sans-serif", 40))
+        .margin(5)
+        .x_label_area_size(30)
+        .y_label_area_size(40)
+        .build_cartesian_2d(0usize..max_coverage + 1, 0usize..max_contigs + 1)?;
+
+    chart
+        .configure_mesh()
+        .x_desc("Mean k-mer coverage")
+        .y_desc("Contigs")
+        .draw()?;
+
+    chart.draw_series(
+        Histogram::vertical(&chart)
+            .style(BLUE.filled())
+            .data(bins),
+    )?;
+
+    root_area.present()?;
+    Ok(())
+}
+
+/// Function to plot abundance-rank data as a Whittaker curve on log-log axes
+pub fn plot_abundance_rank(rank_data: &[(usize, usize)], output_file: &str) -> Result<(), KmerError> {
+    let points: Vec<(f64, f64)> = rank_data
+        .iter()
+        .filter(|&&(rank, count)| rank > 0 && count > 0)
+        .map(|&(rank, count)| (rank as f64, count as f64))
+        .collect();
+    if points.is_empty() {
+        return Err(KmerError::NothingToPlot("k-mer counts"));
+    }
+    let max_rank = points.iter().map(|&(rank, _)| rank).fold(1.0, f64::max);
+    let max_count = points.iter().map(|&(_, count)| count).fold(1.0, f64::max);
+
+    let root_area = BitMapBackend::new(output_file, (640, 480)).into_drawing_area();
+    root_area.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root_area)
+        .caption("K-mer Abundance Rank", ("sans-serif", 40))
+        .margin(5)
+        .x_label_area_size(30)
+        .y_label_area_size(40)
+        .build_cartesian_2d((1.0..max_rank * 2.0).log_scale(), (1.0..max_count * 2.0).log_scale())?;
+
+    chart
+        .configure_mesh()
+        .x_desc("Rank")
+        .y_desc("Count")
+        .draw()?;
+
+    chart.draw_series(LineSeries::new(points, &RED))?;
+
+    root_area.present()?;
+    Ok(())
+}
+
+/// Function to plot GC% along each record as a line chart, one line per record, at the centre
+/// of each window from sliding_window_stats
+pub fn plot_gc_windows(records: &[Vec<WindowStats>], output_file: &str) -> Result<(), KmerError> {
+    let series: Vec<Vec<(f64, f64)>> = records
+        .iter()
+        .map(|windows| {
+            windows
+                .iter()
+                .map(|window| (window.start as f64 + window.stats.length as f64 / 2.0, window.stats.gc_content() * 100.0))
+                .collect()
+        })
+        .collect();
+    let Some(max_position) = series.iter().flatten().map(|&(position, _)| position).reduce(f64::max) else {
+        return Err(KmerError::NothingToPlot("windows"));
+    };
+
+    let root_area = BitMapBackend::new(output_file, (640, 480)).into_drawing_area();
+    root_area.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root_area)
+        .caption("GC Content", ("sans-serif", 40))
+        .margin(5)
+        .x_label_area_size(30)
+        .y_label_area_size(40)
+        .build_cartesian_2d(0.0..max_position.max(1.0), 0.0..100.0)?;
+
+    chart
+        .configure_mesh()
+        .x_desc("Position")
+        .y_desc("GC %")
+        .draw()?;
+
+    for (i, points) in series.into_iter().enumerate() {
+        chart.draw_series(LineSeries::new(points, &Palette99::pick(i)))?;
+    }
+
+    root_area.present()?;
+    Ok(())
+}
@@ -0,0 +1,118 @@
+//! Self-contained HTML reports: summary tables and inline SVG plots in one file with no
+//! external scripts, styles or images, so it can be mailed or archived as is.
+
+use std::io;
+use crate::io::write_output;
+
+/// Most distinct k-mers for which a report embeds the per-k-mer histogram; it draws one bar per
+/// k-mer, so past this the SVG would dwarf the rest of the report.
+pub const REPORT_HISTOGRAM_MAX_KMERS: usize = 10_000;
+
+/// A table of a report section: a header row and rows of already formatted cells.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReportTable {
+    /// Caption shown above the table.
+    pub title: String,
+    /// Column headings.
+    pub header: Vec<String>,
+    /// Cells of each row, one per heading.
+    pub rows: Vec<Vec<String>>,
+}
+
+impl ReportTable {
+    /// A two-column table of name/value pairs.
+    pub fn key_values(title: &str, pairs: &[(&str, String)]) -> Self {
+        ReportTable {
+            title: title.to_string(),
+            header: vec!["".to_string(), "Value".to_string()],
+            rows: pairs.iter().map(|(name, value)| vec![name.to_string(), value.clone()]).collect(),
+        }
+    }
+}
+
+/// One collapsible section of a report, such as the results at one k.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReportSection {
+    /// Heading of the section.
+    pub title: String,
+    /// Tables, in order.
+    pub tables: Vec<ReportTable>,
+    /// SVG documents of the plots, drawn after the tables.
+    pub plots: Vec<String>,
+}
+
+/// An HTML report made of sections.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+    /// Page title and top heading.
+    pub title: String,
+    /// Sections, in order.
+    pub sections: Vec<ReportSection>,
+}
+
+impl Report {
+    /// Render the report as a single HTML page. Text is escaped; the plots are inlined as they
+    /// are. Each section is a `<details>` element, so readers can fold away what they don't need.
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str(&format!("<title>{}</title>\n", escape(&self.title)));
+        html.push_str(concat!(
+            "<style>\n",
+            "body { font-family: sans-serif; margin: 2em; color: #222; }\n",
+            "summary { font-size: 1.4em; font-weight: bold; cursor: pointer; margin: 1em 0 0.5em; }\n",
+            "table { border-collapse: collapse; margin: 0.5em 0 1.5em; }\n",
+            "caption { text-align: left; font-weight: bold; padding-bottom: 0.3em; }\n",
+            "th, td { border: 1px solid #ccc; padding: 0.25em 0.75em; text-align: left; }\n",
+            "th { background: #f3f3f3; }\n",
+            "svg { display: block; max-width: 100%; height: auto; margin-bottom: 1em; }\n",
+            "</style>\n",
+        ));
+        html.push_str(&format!("</head>\n<body>\n<h1>{}</h1>\n", escape(&self.title)));
+        for section in &self.sections {
+            html.push_str(&format!("<details open>\n<summary>{}</summary>\n", escape(&section.title)));
+            for table in &section.tables {
+                html.push_str(&format!("<table>\n<caption>{}</caption>\n<tr>", escape(&table.title)));
+                for heading in &table.header {
+                    html.push_str(&format!("<th>{}</th>", escape(heading)));
+                }
+                html.push_str("</tr>\n");
+                for row in &table.rows {
+                    html.push_str("<tr>");
+                    for cell in row {
+                        html.push_str(&format!("<td>{}</td>", escape(cell)));
+                    }
+                    html.push_str("</tr>\n");
+                }
+                html.push_str("</table>\n");
+            }
+            for plot in &section.plots {
+                html.push_str(plot);
+                html.push('\n');
+            }
+            html.push_str("</details>\n");
+        }
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+/// Write a report to an HTML file (gzipped if its name ends in `.gz`).
+pub fn write_html_report(report: &Report, file_name: &str) -> io::Result<()> {
+    write_output(file_name, report.to_html().as_bytes())
+}
+
+// Escape the characters HTML gives a meaning to
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
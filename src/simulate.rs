@@ -0,0 +1,236 @@
+//! Random sequence and read simulation.
+
+use std::collections::HashMap;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::alphabet::Alphabet;
+use crate::io::FastqRecord;
+use crate::kmer::{base_index, reverse_complement};
+
+/// Function to generate a random DNA sequence of a given length
+pub fn generate_random_dna_sequence(length: usize) -> String {
+    Alphabet::dna().random_sequence(length, None)
+}
+
+/// Generate a random DNA sequence reproducibly: the same length and seed give the same sequence.
+pub fn generate_random_dna_sequence_seeded(length: usize, seed: u64) -> String {
+    Alphabet::dna().random_sequence(length, Some(seed))
+}
+
+/// How generate_sequence draws bases.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SequenceModel {
+    /// Independent bases with these relative A, C, G, T frequencies.
+    BaseFrequencies([f64; 4]),
+    /// An order-k Markov chain trained on a real sequence.
+    Markov(MarkovModel),
+}
+
+impl SequenceModel {
+    /// Every base equally likely, as generate_random_dna_sequence draws them.
+    pub fn uniform() -> Self {
+        SequenceModel::BaseFrequencies([1.0; 4])
+    }
+
+    /// Independent bases with the given GC fraction, split evenly between C and G (and the rest
+    /// between A and T). The fraction is clamped to [0, 1].
+    pub fn with_gc_content(gc: f64) -> Self {
+        let gc = gc.clamp(0.0, 1.0);
+        let at = 1.0 - gc;
+        SequenceModel::BaseFrequencies([at / 2.0, gc / 2.0, gc / 2.0, at / 2.0])
+    }
+}
+
+/// Base counts following every context of `order` bases in a training sequence, so generated
+/// sequences reproduce its k-mer composition up to k = order + 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkovModel {
+    /// Length of the context each base is conditioned on.
+    pub order: usize,
+    /// Counts of A, C, G, T after each context of `order` uppercase ACGT bases.
+    pub transitions: HashMap<String, [usize; 4]>,
+    /// Counts of A, C, G, T over the whole training sequence, used for unseen contexts.
+    pub background: [usize; 4],
+}
+
+impl MarkovModel {
+    /// Count transitions in `seq`. Windows containing a non-ACGT character are skipped.
+    pub fn train(seq: &str, order: usize) -> Self {
+        let seq = seq.to_ascii_uppercase();
+        let bytes = seq.as_bytes();
+        let mut background = [0; 4];
+        for &base in bytes {
+            if let Some(b) = base_index(base) {
+                background[b] += 1;
+            }
+        }
+        let mut transitions: HashMap<String, [usize; 4]> = HashMap::new();
+        for window in bytes.windows(order + 1) {
+            if window.iter().all(|&base| base_index(base).is_some()) {
+                let context = String::from_utf8_lossy(&window[..order]).into_owned();
+                transitions.entry(context).or_insert([0; 4])[base_index(window[order]).unwrap()] += 1;
+            }
+        }
+        MarkovModel { order, transitions, background }
+    }
+}
+
+/// Draw an index in 0..4 with probability proportional to `weights`; uniform if all are zero.
+fn draw_base<R: Rng>(rng: &mut R, weights: &[f64; 4]) -> usize {
+    let total: f64 = weights.iter().sum();
+    if total <= 0.0 {
+        return rng.gen_range(0..4);
+    }
+    let mut target = rng.gen_range(0.0..total);
+    for (i, &weight) in weights.iter().enumerate() {
+        if target < weight {
+            return i;
+        }
+        target -= weight;
+    }
+    3
+}
+
+/// Generate a sequence of `length` bases from a model, reproducibly for a given seed. A Markov
+/// chain starts from a context drawn by how often it occurs in the training sequence and falls
+/// back to the background base frequencies whenever its context was never seen.
+pub fn generate_sequence(model: &SequenceModel, length: usize, seed: u64) -> String {
+    const BASES: [u8; 4] = *b"ACGT";
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut sequence = Vec::with_capacity(length);
+    match model {
+        SequenceModel::BaseFrequencies(frequencies) => {
+            sequence.extend((0..length).map(|_| BASES[draw_base(&mut rng, frequencies)]));
+        }
+        SequenceModel::Markov(markov) => {
+            // Sorted so the same seed picks the same start whatever the HashMap order.
+            let mut contexts: Vec<(&String, usize)> =
+                markov.transitions.iter().map(|(context, counts)| (context, counts.iter().sum())).collect();
+            contexts.sort();
+            let total: usize = contexts.iter().map(|&(_, count)| count).sum();
+            if total > 0 && markov.order > 0 {
+                let mut target = rng.gen_range(0..total);
+                for &(context, count) in &contexts {
+                    if target < count {
+                        sequence.extend(context.bytes().take(length));
+                        break;
+                    }
+                    target -= count;
+                }
+            }
+            while sequence.len() < length {
+                let context = &sequence[sequence.len().saturating_sub(markov.order)..];
+                let counts = if context.len() == markov.order {
+                    std::str::from_utf8(context).ok().and_then(|context| markov.transitions.get(context))
+                } else {
+                    None
+                };
+                let weights = counts.unwrap_or(&markov.background).map(|count| count as f64);
+                sequence.push(BASES[draw_base(&mut rng, &weights)]);
+            }
+        }
+    }
+    String::from_utf8(sequence).expect("generated bases are ASCII")
+}
+
+/// Function to simulate paired-end reads from a reference sequence.
+/// Fragment lengths are drawn from a normal distribution (mean_insert, insert_sd), clamped to
+/// [read_len, reference length]; mate 1 is the fragment's first read_len bases and mate 2 the
+/// reverse complement of its last read_len bases. Enough pairs are drawn to reach `coverage`.
+pub fn simulate_paired_reads(
+    reference: &str,
+    read_len: usize,
+    mean_insert: usize,
+    insert_sd: usize,
+    coverage: f64,
+    seed: u64,
+) -> Vec<(String, String)> {
+    if read_len == 0 || reference.len() < read_len {
+        return Vec::new();
+    }
+    let mut rng = StdRng::seed_from_u64(seed);
+    let num_pairs = (coverage * reference.len() as f64 / (2 * read_len) as f64).round() as usize;
+
+    (0..num_pairs)
+        .map(|_| {
+            // Box-Muller transform for a standard normal sample.
+            let u1: f64 = rng.gen_range(f64::EPSILON..1.0);
+            let u2: f64 = rng.gen();
+            let z = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+            let insert = (mean_insert as f64 + z * insert_sd as f64).round().max(0.0) as usize;
+            let insert = insert.clamp(read_len, reference.len());
+
+            let start = rng.gen_range(0..=reference.len() - insert);
+            let fragment = &reference[start..start + insert];
+            (fragment[..read_len].to_string(), reverse_complement(&fragment[insert - read_len..]))
+        })
+        .collect()
+}
+
+/// Simulate single-end sequencing reads of `read_len` bases from a reference, drawn uniformly from
+/// either strand until the reads reach `coverage`. While copying each read, every base is
+/// substituted with probability `substitution_rate`, and an indel (an inserted random base or a
+/// skipped reference base, equally often) occurs with probability `indel_rate`, so reads carry
+/// realistic errors yet keep the requested length unless deletions run them off the end.
+/// Qualities are the Phred+33 score of the total error rate, capped at 40. Each read id records
+/// its start and strand, e.g. "read7_1520_-".
+pub fn simulate_reads(
+    reference: &str,
+    read_len: usize,
+    coverage: f64,
+    substitution_rate: f64,
+    indel_rate: f64,
+    seed: u64,
+) -> Vec<FastqRecord> {
+    const BASES: [u8; 4] = *b"ACGT";
+    if read_len == 0 || reference.len() < read_len {
+        return Vec::new();
+    }
+    let mut rng = StdRng::seed_from_u64(seed);
+    let num_reads = (coverage * reference.len() as f64 / read_len as f64).round() as usize;
+    let error_rate = (substitution_rate + indel_rate).clamp(1e-4, 1.0);
+    let quality = (b'!' + (-10.0 * error_rate.log10()).round().clamp(0.0, 40.0) as u8) as char;
+    let forward = reference.as_bytes();
+    let reverse = reverse_complement(reference).into_bytes();
+
+    (0..num_reads)
+        .map(|i| {
+            let start = rng.gen_range(0..=reference.len() - read_len);
+            let is_reverse = rng.gen_bool(0.5);
+            // A reverse read starting at `start` reads the reverse strand from the mirrored position.
+            let (strand, offset) = if is_reverse {
+                (reverse.as_slice(), reference.len() - read_len - start)
+            } else {
+                (forward, start)
+            };
+
+            let mut read = Vec::with_capacity(read_len);
+            let mut position = offset;
+            while read.len() < read_len && position < strand.len() {
+                if rng.gen_bool(indel_rate.clamp(0.0, 1.0)) {
+                    if rng.gen_bool(0.5) {
+                        read.push(BASES[rng.gen_range(0..4)]);
+                    } else {
+                        position += 1;
+                    }
+                    continue;
+                }
+                let base = strand[position];
+                read.push(if rng.gen_bool(substitution_rate.clamp(0.0, 1.0)) {
+                    // Any of the three other bases.
+                    let others: Vec<u8> = BASES.iter().copied().filter(|&other| other != base).collect();
+                    others[rng.gen_range(0..others.len())]
+                } else {
+                    base
+                });
+                position += 1;
+            }
+            let sequence = String::from_utf8(read).expect("read bases are ASCII");
+            FastqRecord {
+                id: format!("read{}_{}_{}", i + 1, start, if is_reverse { '-' } else { '+' }),
+                quality: quality.to_string().repeat(sequence.len()),
+                sequence,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,414 @@
+//! Sketches and similarity measures for comparing k-mer profiles.
+
+use std::collections::HashMap;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::kmer::{canonical_kmer, seeded_hash};
+use crate::nthash::{nthash, rehash, NtHash};
+
+/// Count-Min sketch for approximate k-mer counts in bounded memory.
+/// Each of `depth` rows hashes a k-mer into one of `width` counters; the estimate is the
+/// minimum over rows, so it never underestimates and only overestimates on collisions.
+/// The `_hash` methods take a k-mer's hash instead, such as NtHash yields; a sketch should be
+/// fed one kind or the other.
+pub struct CountMinSketch {
+    width: usize,
+    depth: usize,
+    counters: Vec<u32>,
+}
+
+impl CountMinSketch {
+    /// An empty sketch of `depth` rows of `width` counters (each at least 1).
+    pub fn new(width: usize, depth: usize) -> Self {
+        let width = width.max(1);
+        let depth = depth.max(1);
+        CountMinSketch { width, depth, counters: vec![0; width * depth] }
+    }
+
+    /// Index into the counters of the k-mer's cell in `row`.
+    pub fn cell(&self, kmer: &str, row: usize) -> usize {
+        self.hash_cell(seeded_hash(kmer, 0), row)
+    }
+
+    fn hash_cell(&self, hash: u64, row: usize) -> usize {
+        row * self.width + (rehash(hash, row as u64) % self.width as u64) as usize
+    }
+
+    /// Count one occurrence of the k-mer.
+    pub fn add(&mut self, kmer: &str) {
+        self.add_hash(seeded_hash(kmer, 0));
+    }
+
+    /// Count one occurrence of the k-mer with this hash.
+    pub fn add_hash(&mut self, hash: u64) {
+        for row in 0..self.depth {
+            let cell = self.hash_cell(hash, row);
+            self.counters[cell] = self.counters[cell].saturating_add(1);
+        }
+    }
+
+    /// Estimated count of the k-mer, never below the true count.
+    pub fn estimate(&self, kmer: &str) -> usize {
+        self.estimate_hash(seeded_hash(kmer, 0))
+    }
+
+    /// Estimated count of the k-mer with this hash.
+    pub fn estimate_hash(&self, hash: u64) -> usize {
+        (0..self.depth).map(|row| self.counters[self.hash_cell(hash, row)] as usize).min().unwrap_or(0)
+    }
+}
+
+/// Bloom filter over k-mers: a set that answers "possibly seen" or "definitely not seen" in
+/// `num_bits` bits. After n insertions with h hashes a k-mer never inserted is reported as seen
+/// with probability about (1 - e^(-hn/num_bits))^h. As with CountMinSketch, the `_hash`
+/// methods take k-mer hashes such as NtHash yields.
+pub struct BloomFilter {
+    num_hashes: usize,
+    bits: Vec<u64>,
+}
+
+impl BloomFilter {
+    /// An empty filter of `num_bits` bits (at least 64) probed by `num_hashes` hashes (at least 1).
+    pub fn new(num_bits: usize, num_hashes: usize) -> Self {
+        BloomFilter { num_hashes: num_hashes.max(1), bits: vec![0; num_bits.div_ceil(64).max(1)] }
+    }
+
+    // Bit positions of the k-mer with this hash, seeded apart from the Count-Min rows
+    fn positions(&self, hash: u64) -> impl Iterator<Item = usize> {
+        let num_bits = self.bits.len() as u64 * 64;
+        (0..self.num_hashes as u64).map(move |i| (rehash(hash, BLOOM_SEED + i) % num_bits) as usize)
+    }
+
+    /// Whether the k-mer may have been inserted; false means it definitely was not.
+    pub fn contains(&self, kmer: &str) -> bool {
+        self.contains_hash(seeded_hash(kmer, 0))
+    }
+
+    /// Whether the k-mer with this hash may have been inserted.
+    pub fn contains_hash(&self, hash: u64) -> bool {
+        self.positions(hash).all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+
+    /// Insert the k-mer, returning whether it may already have been present.
+    pub fn insert(&mut self, kmer: &str) -> bool {
+        self.insert_hash(seeded_hash(kmer, 0))
+    }
+
+    /// Insert the k-mer with this hash, returning whether it may already have been present.
+    pub fn insert_hash(&mut self, hash: u64) -> bool {
+        let positions: Vec<usize> = self.positions(hash).collect();
+        let mut present = true;
+        for bit in positions {
+            let mask = 1 << (bit % 64);
+            present &= self.bits[bit / 64] & mask != 0;
+            self.bits[bit / 64] |= mask;
+        }
+        present
+    }
+}
+
+// First hash seed used by BloomFilter, far from the seeds of CountMinSketch rows
+const BLOOM_SEED: u64 = 1 << 32;
+
+/// Number of hash functions used by both structures in count_kmers_approximate.
+pub const APPROX_HASHES: usize = 4;
+
+/// Count k-mers approximately in about `memory` bytes, for inputs whose exact counts would not
+/// fit in RAM. Half the budget goes to a Bloom filter that catches each k-mer's first
+/// occurrence, so singletons (mostly sequencing errors) are never stored, and half to a
+/// Count-Min sketch of width w = memory / 2 / (4 * APPROX_HASHES) u32 counters that counts
+/// every occurrence. Only k-mers seen at least twice are returned, plus the Bloom filter's
+/// false positives. Each count is at least the true count, and with N k-mer occurrences in
+/// total exceeds it by more than e * N / w with probability at most e^-APPROX_HASHES (about 2%).
+/// The returned counts map uses the same layout as count_records_parallel. K-mers are hashed
+/// with NtHash as the window slides, and only repeated ones are ever copied into Strings.
+pub fn count_kmers_approximate(sequences: &[String], k: usize, canonical: bool, memory: usize) -> HashMap<String, usize> {
+    let mut seen = BloomFilter::new(memory / 2 * 8, APPROX_HASHES);
+    let mut sketch = CountMinSketch::new(memory / 2 / (4 * APPROX_HASHES), APPROX_HASHES);
+    // Repeated k-mers by hash; two k-mers sharing all 64 bits would be merged, which is far
+    // rarer than the sketch's own overestimates
+    let mut repeated = HashMap::new();
+    for sequence in sequences {
+        for (start, hash) in NtHash::new(sequence, k, canonical) {
+            sketch.add_hash(hash);
+            if seen.insert_hash(hash) {
+                repeated.entry(hash).or_insert_with(|| {
+                    let kmer = &sequence[start..start + k];
+                    if canonical { canonical_kmer(kmer) } else { kmer.to_string() }
+                });
+            }
+        }
+    }
+    repeated.into_iter().map(|(hash, kmer)| (kmer, sketch.estimate_hash(hash))).collect()
+}
+
+/// Bottom-s MinHash sketch: the `size` smallest hashes of a sequence's canonical k-mers.
+/// Comparing two sketches estimates the Jaccard similarity of the full k-mer sets.
+pub struct MinHashSketch {
+    /// K-mer size the sketch was built with.
+    pub k: usize,
+    /// Maximum number of hashes kept.
+    pub size: usize,
+    /// The smallest hashes, sorted ascending.
+    pub hashes: Vec<u64>,
+}
+
+impl MinHashSketch {
+    /// Sketch the canonical k-mers of `seq`, keeping the `size` smallest hashes. The k-mers are
+    /// hashed by NtHash, strand-independently; those spanning a non-ACGT base are left out.
+    pub fn from_sequence(seq: &str, k: usize, size: usize) -> Self {
+        Self::from_hashes(NtHash::new(seq, k, true).map(|(_, hash)| hash).collect(), k, size)
+    }
+
+    /// Sketch the canonical k-mers of every record, as for a FASTA file. K-mers spanning a
+    /// non-ACGT base or a record boundary are left out, as in counting.
+    pub fn from_records(sequences: &[String], k: usize, size: usize) -> Self {
+        let hashes = sequences
+            .iter()
+            .flat_map(|sequence| NtHash::new(sequence, k, true))
+            .map(|(_, hash)| hash)
+            .collect();
+        Self::from_hashes(hashes, k, size)
+    }
+
+    /// Sketch a set of k-mers, such as the keys of a saved count table, under their canonical
+    /// form. They hash as in from_records; k-mers holding a non-ACGT base are left out.
+    pub fn from_kmers<'a>(kmers: impl IntoIterator<Item = &'a String>, k: usize, size: usize) -> Self {
+        let hashes = kmers.into_iter().filter_map(|kmer| nthash(kmer, true)).collect();
+        Self::from_hashes(hashes, k, size)
+    }
+
+    // Keep the `size` smallest distinct hashes
+    fn from_hashes(mut hashes: Vec<u64>, k: usize, size: usize) -> Self {
+        hashes.sort_unstable();
+        hashes.dedup();
+        hashes.truncate(size);
+        MinHashSketch { k, size, hashes }
+    }
+
+    /// Estimate Jaccard similarity from the bottom-s hashes of the union of both sketches.
+    pub fn jaccard(&self, other: &MinHashSketch) -> f64 {
+        let size = self.size.min(other.size);
+        let (mut i, mut j) = (0, 0);
+        let (mut seen, mut shared) = (0, 0);
+        while seen < size && (i < self.hashes.len() || j < other.hashes.len()) {
+            let a = self.hashes.get(i).copied().unwrap_or(u64::MAX);
+            let b = other.hashes.get(j).copied().unwrap_or(u64::MAX);
+            if a == b {
+                shared += 1;
+                i += 1;
+                j += 1;
+            } else if a < b {
+                i += 1;
+            } else {
+                j += 1;
+            }
+            seen += 1;
+        }
+        if seen == 0 {
+            return 0.0;
+        }
+        shared as f64 / seen as f64
+    }
+
+    /// Mash distance, -ln(2J / (1 + J)) / k, an estimate of the per-base mutation rate.
+    /// Sketches with nothing in common are at the maximum distance of 1.0.
+    pub fn mash_distance(&self, other: &MinHashSketch) -> f64 {
+        let jaccard = self.jaccard(other);
+        if jaccard == 0.0 {
+            return 1.0;
+        }
+        (((1.0 + jaccard) / (2.0 * jaccard)).ln() / self.k as f64).min(1.0)
+    }
+}
+
+/// Abundance-aware MinHash using improved consistent weighted sampling (Ioffe, 2010).
+/// For each of `num_hashes` hash functions every k-mer draws deterministic random variables from
+/// a seed derived from (k-mer, hash index), and the k-mer with the smallest score is sampled
+/// together with a quantized weight t. The fraction of matching samples between two sketches
+/// estimates the weighted Jaccard similarity sum(min(a, b)) / sum(max(a, b)).
+pub struct WeightedMinHashSketch {
+    samples: Vec<Option<(u64, i64)>>,
+}
+
+impl WeightedMinHashSketch {
+    /// Sketch a k-mer count table with `num_hashes` weighted samples.
+    pub fn from_counts(kmer_counts: &HashMap<String, usize>, num_hashes: usize) -> Self {
+        let samples = (0..num_hashes as u64)
+            .map(|hash_index| {
+                let mut best: Option<(f64, u64, i64)> = None;
+                for (kmer, &count) in kmer_counts {
+                    if count == 0 {
+                        continue;
+                    }
+                    let kmer_hash = seeded_hash(kmer, hash_index);
+                    let mut rng = StdRng::seed_from_u64(kmer_hash);
+                    // Gamma(2, 1) draws as the negative log of two uniforms.
+                    let mut gamma = || -(rng.gen_range(f64::EPSILON..1.0f64).ln() + rng.gen_range(f64::EPSILON..1.0f64).ln());
+                    let (r, c) = (gamma(), gamma());
+                    let beta: f64 = rng.gen();
+
+                    let t = ((count as f64).ln() / r + beta).floor();
+                    let y = (r * (t - beta)).exp();
+                    let score = c / (y * r.exp());
+                    if best.is_none_or(|(best_score, _, _)| score < best_score) {
+                        best = Some((score, seeded_hash(kmer, 0), t as i64));
+                    }
+                }
+                best.map(|(_, kmer_hash, t)| (kmer_hash, t))
+            })
+            .collect();
+        WeightedMinHashSketch { samples }
+    }
+
+    /// Estimated weighted Jaccard similarity: the fraction of hash functions whose samples agree.
+    pub fn similarity(&self, other: &WeightedMinHashSketch) -> f64 {
+        let compared = self.samples.len().min(other.samples.len());
+        if compared == 0 {
+            return 0.0;
+        }
+        let matches = self
+            .samples
+            .iter()
+            .zip(&other.samples)
+            .filter(|(a, b)| a.is_some() && a == b)
+            .count();
+        matches as f64 / compared as f64
+    }
+}
+
+/// Sketch each (label, sequence) genome with MinHash and compute the full pairwise Mash distance
+/// matrix, ready for tree building such as neighbor-joining. Returns the labels in input order.
+pub fn phylogenomic_distances(genomes: &[(String, String)], k: usize, sketch_size: usize) -> (Vec<String>, Vec<Vec<f64>>) {
+    let labels: Vec<String> = genomes.iter().map(|(label, _)| label.clone()).collect();
+    let sketches: Vec<MinHashSketch> = genomes
+        .iter()
+        .map(|(_, seq)| MinHashSketch::from_sequence(seq, k, sketch_size))
+        .collect();
+    (labels, pairwise_matrix(&sketches, MinHashSketch::mash_distance))
+}
+
+/// Symmetric matrix of `measure` between every pair of sketches, such as
+/// MinHashSketch::mash_distance or MinHashSketch::jaccard. The diagonal compares each sketch
+/// with itself.
+pub fn pairwise_matrix(sketches: &[MinHashSketch], measure: fn(&MinHashSketch, &MinHashSketch) -> f64) -> Vec<Vec<f64>> {
+    let n = sketches.len();
+    let mut matrix = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        for j in i..n {
+            let value = measure(&sketches[i], &sketches[j]);
+            matrix[i][j] = value;
+            matrix[j][i] = value;
+        }
+    }
+    matrix
+}
+
+/// Jaccard similarity of two samples' k-mer sets: |intersection| / |union| over the map keys.
+/// Two empty samples are treated as dissimilar (0.0); identical key sets give 1.0.
+pub fn jaccard_similarity(a: &HashMap<String, usize>, b: &HashMap<String, usize>) -> f64 {
+    let shared = a.keys().filter(|kmer| b.contains_key(*kmer)).count();
+    let union = a.len() + b.len() - shared;
+    if union == 0 {
+        return 0.0;
+    }
+    shared as f64 / union as f64
+}
+
+/// Weighted Jaccard similarity of two count profiles: sum of min(count) over sum of max(count)
+/// across all k-mers, so shared abundance matters and not just shared presence.
+/// As with jaccard_similarity, two empty samples give 0.0.
+pub fn weighted_jaccard_similarity(a: &HashMap<String, usize>, b: &HashMap<String, usize>) -> f64 {
+    let mut min_sum = 0;
+    let mut max_sum = 0;
+    for (kmer, &count_a) in a {
+        let count_b = *b.get(kmer).unwrap_or(&0);
+        min_sum += count_a.min(count_b);
+        max_sum += count_a.max(count_b);
+    }
+    max_sum += b.iter().filter(|(kmer, _)| !a.contains_key(*kmer)).map(|(_, &count)| count).sum::<usize>();
+    if max_sum == 0 {
+        return 0.0;
+    }
+    min_sum as f64 / max_sum as f64
+}
+
+/// For each minimum-count threshold, the Jaccard similarity of the two samples restricted to
+/// k-mers with at least that count, showing whether overlap comes from rare or common k-mers.
+pub fn overlap_vs_threshold(
+    a: &HashMap<String, usize>,
+    b: &HashMap<String, usize>,
+    thresholds: &[usize],
+) -> Vec<(usize, f64)> {
+    let at_least = |counts: &HashMap<String, usize>, threshold: usize| -> HashMap<String, usize> {
+        counts
+            .iter()
+            .filter(|&(_, &count)| count >= threshold)
+            .map(|(kmer, &count)| (kmer.clone(), count))
+            .collect()
+    };
+    thresholds
+        .iter()
+        .map(|&threshold| (threshold, jaccard_similarity(&at_least(a, threshold), &at_least(b, threshold))))
+        .collect()
+}
+
+/// Compute the Pearson correlation between two k-mer count profiles.
+/// The count vectors are taken over the union of k-mers, with absent k-mers counted as zero.
+/// Returns 0.0 when the correlation is undefined (empty input or a profile with no variance).
+pub fn kmer_correlation(a: &HashMap<String, usize>, b: &HashMap<String, usize>) -> f64 {
+    let mut union: Vec<&String> = a.keys().chain(b.keys()).collect();
+    union.sort();
+    union.dedup();
+
+    let n = union.len() as f64;
+    if union.is_empty() {
+        return 0.0;
+    }
+
+    let xs: Vec<f64> = union.iter().map(|kmer| *a.get(*kmer).unwrap_or(&0) as f64).collect();
+    let ys: Vec<f64> = union.iter().map(|kmer| *b.get(*kmer).unwrap_or(&0) as f64).collect();
+    let mean_x = xs.iter().sum::<f64>() / n;
+    let mean_y = ys.iter().sum::<f64>() / n;
+
+    let mut covariance = 0.0;
+    let mut var_x = 0.0;
+    let mut var_y = 0.0;
+    for (x, y) in xs.iter().zip(&ys) {
+        covariance += (x - mean_x) * (y - mean_y);
+        var_x += (x - mean_x).powi(2);
+        var_y += (y - mean_y).powi(2);
+    }
+
+    if var_x == 0.0 || var_y == 0.0 {
+        return 0.0;
+    }
+    covariance / (var_x.sqrt() * var_y.sqrt())
+}
+
+/// Birthday-paradox probability that at least two of `num_distinct_kmers` random positions draw
+/// the same k-mer from the 4^k space: 1 - exp(-n(n-1) / (2 * 4^k)). The space is computed in f64
+/// so large k cannot overflow.
+pub fn kmer_collision_probability(num_distinct_kmers: usize, k: usize) -> f64 {
+    let space = 4f64.powi(k as i32);
+    let n = num_distinct_kmers as f64;
+    let pairs = n * (n - 1.0).max(0.0) / 2.0;
+    -(-pairs / space).exp_m1()
+}
+
+/// Total variation distance between two k-mer count profiles after normalizing each to frequencies:
+/// 0.0 for identical composition, 1.0 for profiles sharing no k-mers.
+pub fn profile_distance(a: &HashMap<String, usize>, b: &HashMap<String, usize>) -> f64 {
+    let total_a = a.values().sum::<usize>().max(1) as f64;
+    let total_b = b.values().sum::<usize>().max(1) as f64;
+    let mut distance: f64 = a
+        .iter()
+        .map(|(kmer, &count)| (count as f64 / total_a - *b.get(kmer).unwrap_or(&0) as f64 / total_b).abs())
+        .sum();
+    distance += b
+        .iter()
+        .filter(|(kmer, _)| !a.contains_key(*kmer))
+        .map(|(_, &count)| count as f64 / total_b)
+        .sum::<f64>();
+    distance / 2.0
+}
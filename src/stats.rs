@@ -0,0 +1,119 @@
+//! Composition statistics of a sequence: length, base counts, GC content and Shannon entropy,
+//! over the whole sequence or in sliding windows along it.
+
+use std::fmt;
+use crate::kmer::base_index;
+
+/// Length and base composition of a sequence or window.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SequenceStats {
+    /// Number of bases, whitespace excluded.
+    pub length: usize,
+    /// Counts of A, C, G and T, case-insensitively.
+    pub base_counts: [usize; 4],
+    /// Count of N and every other character outside A/C/G/T.
+    pub other: usize,
+}
+
+impl SequenceStats {
+    /// Count the bases of a sequence, skipping whitespace.
+    pub fn of(seq: &str) -> Self {
+        let mut stats = SequenceStats::default();
+        for base in seq.bytes().filter(|base| !base.is_ascii_whitespace()) {
+            stats.add(base);
+        }
+        stats
+    }
+
+    /// Fraction of G and C among the A/C/G/T bases, as gc_content computes it; 0.0 without any.
+    pub fn gc_content(&self) -> f64 {
+        let acgt: usize = self.base_counts.iter().sum();
+        if acgt == 0 {
+            return 0.0;
+        }
+        (self.base_counts[1] + self.base_counts[2]) as f64 / acgt as f64
+    }
+
+    /// Shannon entropy in bits of the A/C/G/T frequencies: 2.0 for equal frequencies, 0.0 for a
+    /// single repeated base (or no A/C/G/T at all). Low-complexity stretches score low.
+    pub fn entropy(&self) -> f64 {
+        let acgt: usize = self.base_counts.iter().sum();
+        self.base_counts
+            .iter()
+            .filter(|&&count| count > 0)
+            .map(|&count| {
+                let p = count as f64 / acgt as f64;
+                -p * p.log2()
+            })
+            .sum()
+    }
+
+    fn add(&mut self, base: u8) {
+        self.length += 1;
+        match base_index(base) {
+            Some(i) => self.base_counts[i] += 1,
+            None => self.other += 1,
+        }
+    }
+
+    fn remove(&mut self, base: u8) {
+        self.length -= 1;
+        match base_index(base) {
+            Some(i) => self.base_counts[i] -= 1,
+            None => self.other -= 1,
+        }
+    }
+}
+
+impl fmt::Display for SequenceStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let [a, c, g, t] = self.base_counts;
+        writeln!(f, "Length: {} bp", self.length)?;
+        writeln!(f, "GC content: {:.2}%", self.gc_content() * 100.0)?;
+        writeln!(f, "Base composition: A={} C={} G={} T={} other={}", a, c, g, t, self.other)?;
+        write!(f, "Entropy: {:.3} bits", self.entropy())
+    }
+}
+
+/// The statistics of one window of a sequence.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowStats {
+    /// 0-based position of the window's first base.
+    pub start: usize,
+    /// Composition of the window.
+    pub stats: SequenceStats,
+}
+
+/// Statistics of every window of `window` bases along a cleaned sequence (no whitespace),
+/// advancing `step` bases at a time. Counts are updated as the window slides, so step 1 stays
+/// linear in the sequence length. A sequence shorter than the window gives a single window over
+/// all of it; window or step 0 gives none.
+pub fn sliding_window_stats(seq: &str, window: usize, step: usize) -> Vec<WindowStats> {
+    let bytes = seq.as_bytes();
+    if window == 0 || step == 0 || bytes.is_empty() {
+        return Vec::new();
+    }
+    if bytes.len() <= window {
+        return vec![WindowStats { start: 0, stats: SequenceStats::of(seq) }];
+    }
+    let mut windows = Vec::new();
+    let mut stats = SequenceStats::default();
+    bytes[..window].iter().for_each(|&base| stats.add(base));
+    let mut start = 0;
+    loop {
+        windows.push(WindowStats { start, stats });
+        let next = start + step;
+        if next + window > bytes.len() {
+            break;
+        }
+        if step >= window {
+            stats = SequenceStats::default();
+            bytes[next..next + window].iter().for_each(|&base| stats.add(base));
+        } else {
+            bytes[start..next].iter().for_each(|&base| stats.remove(base));
+            bytes[start + window..next + window].iter().for_each(|&base| stats.add(base));
+        }
+        start = next;
+    }
+    windows
+}
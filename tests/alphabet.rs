@@ -0,0 +1,43 @@
+use kmer_analysis::graph::DeBruijnGraph;
+use kmer_analysis::kmer::{count_records_parallel_in, encode_kmer, AmbiguousBasePolicy};
+use kmer_analysis::{Alphabet, KmerError};
+
+#[test]
+fn packs_kmers_at_the_alphabet_width() {
+    let dna = Alphabet::dna();
+    assert_eq!((dna.bits_per_symbol(), dna.max_packed_k()), (2, 32));
+    assert_eq!(dna.encode("GATTACA"), encode_kmer("GATTACA"));
+    let protein = Alphabet::protein();
+    assert_eq!((protein.bits_per_symbol(), protein.max_packed_k()), (5, 12));
+    let code = protein.encode("MKWVTF").unwrap();
+    assert_eq!(protein.decode(code, 6), "MKWVTF");
+    assert_eq!(protein.encode("MKB"), None);
+    assert_eq!(Alphabet::custom("HP").unwrap().bits_per_symbol(), 1);
+}
+
+#[test]
+fn validates_against_the_alphabet() {
+    let protein = Alphabet::protein();
+    assert_eq!(protein.validate("mkw\nbv", AmbiguousBasePolicy::Skip).unwrap(), "MKWXV");
+    assert!(matches!(
+        protein.validate("MKB", AmbiguousBasePolicy::Error),
+        Err(KmerError::AmbiguousBase { base: 'B', position: 3 })
+    ));
+    assert!(matches!(
+        Alphabet::rna().validate("ACGT", AmbiguousBasePolicy::Skip),
+        Err(KmerError::InvalidCharacter { character: 'T', position: 4 })
+    ));
+    assert!(matches!(Alphabet::custom("HPH"), Err(KmerError::InvalidAlphabet(_))));
+}
+
+#[test]
+fn counts_and_links_kmers_of_any_alphabet() {
+    let protein = Alphabet::protein();
+    let sequences = vec!["MKWXMKW".to_string()];
+    assert_eq!(protein.kmers(&sequences[0], 3), vec!["MKW", "MKW"]);
+    assert_eq!(count_records_parallel_in(&sequences, 3, &protein, true)["MKW"], 2);
+    let graph = DeBruijnGraph::from_sequences(&["MKWVT".to_string()], 3, &protein);
+    assert_eq!(graph.assemble(), vec!["MKWVT".to_string()]);
+    assert_eq!(Alphabet::rna().canonical("UUG"), "CAA");
+    assert_eq!(protein.reverse_complement("MKW"), None);
+}
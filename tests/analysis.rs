@@ -0,0 +1,306 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use kmer_analysis::analysis::{
+    abundance_rank_data, abundance_table, apply_mask, bin_by_coverage_gc, chao1_estimate, completeness_estimate,
+    composition_changepoints, compute_mask, compute_spectrum, contig_coverage_spectrum, diff_counts, error_threshold,
+    estimate_genome, gc_coverage_bias, kmer_context_entropy, kmer_information_gain, linguistic_complexity,
+    mlst_profile, novel_content_windows, screen_contaminants, singleton_doubleton_counts, tetranucleotide_zscores,
+    uniqueness_vs_k, AssemblyStats, CANONICAL_TETRANUCLEOTIDES, DiffSummary, MaskStyle,
+};
+use kmer_analysis::kmer::{count_kmers, count_records_parallel, decode_kmer, generate_kmers, reverse_complement};
+use kmer_analysis::simulate::{generate_random_dna_sequence_seeded, simulate_reads};
+
+#[test]
+fn genome_estimate_recovers_simulated_size_and_error_rate() {
+    let genome = generate_random_dna_sequence_seeded(20_000, 11);
+    let reads: Vec<String> = simulate_reads(&genome, 100, 40.0, 0.01, 0.0, 5)
+        .into_iter()
+        .map(|read| read.sequence)
+        .collect();
+    let spectrum = compute_spectrum(&count_records_parallel(&reads, 21, true));
+
+    let estimate = estimate_genome(&spectrum, 21).expect("spectrum has a genomic peak");
+    // 40x base coverage of 100 bp reads gives 40 * 80 / 100 = 32x k-mer coverage, of which
+    // 0.99^21 (about 81%) is free of errors
+    assert!((24..=28).contains(&estimate.kmer_coverage), "{estimate:?}");
+    assert!((estimate.base_coverage(100) - 40.0).abs() < 3.0, "{estimate:?}");
+    assert!(estimate.genome_size.abs_diff(20_000) < 1_000, "{estimate:?}");
+    assert!((estimate.error_rate - 0.01).abs() < 0.003, "{estimate:?}");
+}
+
+#[test]
+fn spectrum_without_a_peak_has_no_estimate() {
+    let spectrum: BTreeMap<usize, usize> = [(1, 500), (2, 40), (3, 5)].into_iter().collect();
+    assert_eq!(estimate_genome(&spectrum, 21), None);
+    assert_eq!(error_threshold(&spectrum), None);
+}
+
+#[test]
+fn error_threshold_is_the_trough_before_the_genomic_peak() {
+    let spectrum: BTreeMap<usize, usize> = [(1, 500), (2, 40), (3, 10), (4, 30), (5, 80), (6, 30)].into_iter().collect();
+    assert_eq!(error_threshold(&spectrum), Some(3));
+    assert_eq!(estimate_genome(&spectrum, 21).map(|estimate| estimate.error_threshold), Some(3));
+}
+
+#[test]
+fn assembly_stats_report_n50_and_longest_contig() {
+    let stats = AssemblyStats::from_lengths(&[20, 50, 10, 40, 30]);
+    // 50 + 40 = 90 of the 150 bases reach half the total
+    assert_eq!(stats, AssemblyStats { contigs: 5, total_length: 150, n50: 40, longest: 50 });
+    assert_eq!(AssemblyStats::from_lengths(&[]), AssemblyStats::default());
+}
+
+#[test]
+fn diff_joins_both_samples_and_normalises_by_their_totals() {
+    let a: HashMap<String, usize> = [("AAA", 3), ("CCC", 1)].into_iter().map(|(kmer, count)| (kmer.to_string(), count)).collect();
+    let b: HashMap<String, usize> = [("CCC", 1), ("GGG", 3)].into_iter().map(|(kmer, count)| (kmer.to_string(), count)).collect();
+    let diffs = diff_counts(&a, &b);
+
+    let rows: Vec<(&str, usize, usize)> = diffs.iter().map(|diff| (diff.kmer.as_str(), diff.count_a, diff.count_b)).collect();
+    assert_eq!(rows, [("AAA", 3, 0), ("CCC", 1, 1), ("GGG", 0, 3)]);
+    // Both samples total 4, so only the pseudocount separates them: log2(1/4) and log2(4/1)
+    assert!((diffs[0].log2_fold_change + 2.0).abs() < 1e-9);
+    assert!(diffs[1].log2_fold_change.abs() < 1e-9);
+    assert!((diffs[2].log2_fold_change - 2.0).abs() < 1e-9);
+    assert_eq!(DiffSummary::of(&diffs), DiffSummary { shared: 1, only_a: 1, only_b: 1 });
+}
+
+#[test]
+fn context_entropy_is_low_for_repeats_and_near_two_bits_for_random_dna() {
+    let repetitive = "ACGT".repeat(500);
+    assert!(kmer_context_entropy(&repetitive, 3) < 0.1);
+    let random = generate_random_dna_sequence_seeded(20_000, 3);
+    let entropy = kmer_context_entropy(&random, 3);
+    assert!(entropy > 1.9 && entropy <= 2.0, "entropy {entropy}");
+}
+
+fn counts(pairs: &[(&str, usize)]) -> HashMap<String, usize> {
+    pairs.iter().map(|&(kmer, count)| (kmer.to_string(), count)).collect()
+}
+
+#[test]
+fn abundance_table_keeps_prevalent_kmers_and_normalises_rows() {
+    let samples = vec![
+        ("a".to_string(), counts(&[("AAA", 3), ("CCC", 1), ("GGG", 5)])),
+        ("b".to_string(), counts(&[("AAA", 1), ("CCC", 1)])),
+        ("c".to_string(), counts(&[("CCC", 2), ("TTT", 7)])),
+    ];
+    let (names, vocabulary, matrix) = abundance_table(&samples, 2);
+    assert_eq!(names, ["a", "b", "c"]);
+    assert_eq!(vocabulary, ["AAA", "CCC"]);
+    assert_eq!(matrix, vec![vec![0.75, 0.25], vec![0.5, 0.5], vec![0.0, 1.0]]);
+    for row in &matrix {
+        assert!((row.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn tetranucleotide_fingerprint_is_strand_independent_and_tells_sequences_apart() {
+    let sequence = generate_random_dna_sequence_seeded(5_000, 21);
+    let fingerprint = tetranucleotide_zscores(&sequence);
+    assert_eq!(fingerprint.len(), CANONICAL_TETRANUCLEOTIDES);
+    assert_eq!(tetranucleotide_zscores(&sequence.clone()), fingerprint);
+    assert_eq!(tetranucleotide_zscores(&reverse_complement(&sequence)), fingerprint);
+    assert_ne!(tetranucleotide_zscores(&generate_random_dna_sequence_seeded(5_000, 22)), fingerprint);
+}
+
+#[test]
+fn linguistic_complexity_separates_homopolymers_from_random_sequence() {
+    assert!(linguistic_complexity(&"A".repeat(1_000), 8) < 0.05);
+    assert!(linguistic_complexity(&generate_random_dna_sequence_seeded(1_000, 5), 8) > 0.9);
+}
+
+#[test]
+fn uniqueness_reaches_one_once_k_exceeds_the_repeat() {
+    let repeat = generate_random_dna_sequence_seeded(12, 70);
+    let flank = |seed| generate_random_dna_sequence_seeded(60, seed);
+    let sequence = [flank(71), repeat.clone(), flank(72), repeat, flank(73)].concat();
+    let curve = uniqueness_vs_k(&sequence, 10..=16);
+    assert_eq!(curve.iter().map(|&(k, _)| k).collect::<Vec<_>>(), (10..=16).collect::<Vec<_>>());
+    for &(k, fraction) in &curve {
+        if k <= 12 {
+            assert!(fraction < 1.0, "k = {k}");
+        } else {
+            assert_eq!(fraction, 1.0, "k = {k}");
+        }
+    }
+}
+
+#[test]
+fn contig_coverage_spectrum_reports_each_contigs_mean_coverage() {
+    let k = 11;
+    let (low, high) = (generate_random_dna_sequence_seeded(60, 100), generate_random_dna_sequence_seeded(60, 101));
+    let reads = [vec![low.as_str(); 10], vec![high.as_str(); 30]].concat();
+    let kmer_counts = count_kmers(reads.iter().flat_map(|read| generate_kmers(read, k)).collect());
+    let contigs = vec![low.clone(), high.clone(), "ACGT".to_string()];
+    let spectrum = contig_coverage_spectrum(&contigs, &kmer_counts, k);
+    assert_eq!(spectrum, vec![(low, 10.0), (high, 30.0), ("ACGT".to_string(), 0.0)]);
+}
+
+#[test]
+fn gc_coverage_bias_reports_an_injected_gc_trend() {
+    // Every 4-mer, with coverage rising by 5 for each G or C base.
+    let kmer_counts: HashMap<String, usize> = (0..256)
+        .map(|code| decode_kmer(code, 4))
+        .map(|kmer| {
+            let gc = kmer.bytes().filter(|&base| base == b'G' || base == b'C').count();
+            (kmer, 10 + 5 * gc)
+        })
+        .collect();
+    assert_eq!(gc_coverage_bias(&kmer_counts), vec![(0.0, 10.0), (0.25, 15.0), (0.5, 20.0), (0.75, 25.0), (1.0, 30.0)]);
+}
+
+#[test]
+fn masks_a_low_complexity_region_and_leaves_its_flanks_untouched() {
+    let (left, right) = (generate_random_dna_sequence_seeded(40, 120), generate_random_dna_sequence_seeded(40, 121));
+    let sequence = [left.as_str(), &"A".repeat(20), &right].concat();
+    let intervals = compute_mask(&sequence, 8);
+    // Windows overlapping the run's edges are low-complexity too, so the mask may reach a few bases past it.
+    let [(start, end)] = intervals[..] else { panic!("expected one interval, got {intervals:?}") };
+    assert!((33..=40).contains(&start) && (60..68).contains(&end), "{start}..{end}");
+
+    let hard = apply_mask(&sequence, &intervals, MaskStyle::Hard);
+    assert_eq!(hard[..start], sequence[..start]);
+    assert_eq!(hard[start..end], "N".repeat(end - start));
+    assert_eq!(hard[end..], sequence[end..]);
+    let soft = apply_mask(&sequence, &intervals, MaskStyle::Soft);
+    assert_eq!(soft, [&sequence[..start], &sequence[start..end].to_ascii_lowercase(), &sequence[end..]].concat());
+
+    // Inverted, empty and out-of-range intervals are ignored rather than panicking.
+    assert_eq!(apply_mask(&sequence, &[(30, 10), (5, 5), (200, 300)], MaskStyle::Hard), sequence);
+}
+
+#[test]
+fn chao1_adds_the_unseen_richness_implied_by_singletons_and_doubletons() {
+    let kmer_counts = counts(&[("AAA", 1), ("AAC", 1), ("AAG", 1), ("AAT", 1), ("ACA", 2), ("ACC", 2), ("ACG", 5)]);
+    assert_eq!(singleton_doubleton_counts(&kmer_counts), (4, 2));
+    // 7 observed + 4^2 / (2 * 2)
+    assert_eq!(chao1_estimate(&kmer_counts), 11.0);
+    // Without doubletons: 4 observed + 3 * 2 / (2 * 1)
+    let no_doubletons = counts(&[("AAA", 1), ("AAC", 1), ("AAG", 1), ("ACG", 5)]);
+    assert_eq!(chao1_estimate(&no_doubletons), 7.0);
+}
+
+#[test]
+fn finds_a_composition_change_at_the_junction_of_two_sequences() {
+    // An AT-only half followed by a GC-only half, both otherwise random.
+    let recode = |seed, low, high| -> String {
+        generate_random_dna_sequence_seeded(2_000, seed)
+            .chars()
+            .map(|base| if matches!(base, 'A' | 'C') { low } else { high })
+            .collect()
+    };
+    let sequence = recode(140, 'A', 'T') + &recode(141, 'G', 'C');
+    let changepoints = composition_changepoints(&sequence, 3, 200);
+    assert_eq!(changepoints.len(), 1, "{changepoints:?}");
+    assert!(changepoints[0].abs_diff(2_000) <= 50, "{changepoints:?}");
+}
+
+#[test]
+fn information_gain_ranks_a_class_defining_kmer_first() {
+    let marker = "GATTACAGG";
+    let samples: Vec<(String, bool)> = (0..6)
+        .map(|i| {
+            let background = generate_random_dna_sequence_seeded(200, 160 + i);
+            let positive = i % 2 == 0;
+            let sequence = if positive { [&background[..100], marker, &background[100..]].concat() } else { background };
+            (sequence, positive)
+        })
+        .collect();
+    let ranking = kmer_information_gain(&samples, marker.len());
+    assert_eq!(ranking[0], (marker.to_string(), 1.0));
+    assert!(ranking[1].1 < 1.0);
+}
+
+#[test]
+fn mlst_calls_the_present_allele_and_none_for_a_missing_locus() {
+    let allele_1 = generate_random_dna_sequence_seeded(120, 200);
+    // A second allele differing at three positions.
+    let allele_2: String = allele_1
+        .chars()
+        .enumerate()
+        .map(|(i, base)| if [30, 60, 90].contains(&i) { if base == 'G' { 'T' } else { 'G' } } else { base })
+        .collect();
+    let alleles: HashMap<String, Vec<(String, String)>> = HashMap::from([
+        ("adk".to_string(), vec![("1".to_string(), allele_1), ("2".to_string(), allele_2.clone())]),
+        ("gyrB".to_string(), vec![("1".to_string(), generate_random_dna_sequence_seeded(120, 201))]),
+    ]);
+    // The genome carries adk allele 2 on the reverse strand and no gyrB.
+    let flank = |seed| generate_random_dna_sequence_seeded(500, seed);
+    let genome = [flank(202), reverse_complement(&allele_2), flank(203)].concat();
+    let profile = mlst_profile(&genome, &alleles, 21);
+    assert_eq!(profile, HashMap::from([("adk".to_string(), Some("2".to_string())), ("gyrB".to_string(), None)]));
+}
+
+#[test]
+fn completeness_is_the_fraction_of_markers_present_in_the_assembly() {
+    let k = 15;
+    let genes: Vec<String> = (0..4).map(|seed| generate_random_dna_sequence_seeded(150, 210 + seed)).collect();
+    let markers: Vec<(String, HashSet<String>)> = genes
+        .iter()
+        .enumerate()
+        .map(|(i, gene)| (format!("marker{i}"), generate_kmers(gene, k).into_iter().collect()))
+        .collect();
+    // Three of the four marker genes are assembled: one on the reverse strand and one missing its last 10 bases.
+    let assembly = [genes[0].clone(), reverse_complement(&genes[1]), genes[2][..140].to_string()].concat();
+    assert_eq!(completeness_estimate(&assembly, &markers, k, 0.9), 0.75);
+    assert_eq!(completeness_estimate(&assembly, &markers, k, 1.0), 0.5);
+}
+
+#[test]
+fn contamination_screen_puts_the_spiked_contaminant_first() {
+    let k = 15;
+    let host = generate_random_dna_sequence_seeded(800, 230);
+    let contaminant = generate_random_dna_sequence_seeded(200, 231);
+    // The contaminant's reads come from the reverse strand of the panel sequence.
+    let reads = [host, reverse_complement(&contaminant)];
+    let sample = count_kmers(reads.iter().flat_map(|read| generate_kmers(read, k)).collect());
+    let panel = vec![
+        ("ecoli".to_string(), generate_kmers(&generate_random_dna_sequence_seeded(500, 232), k).into_iter().collect()),
+        ("phix".to_string(), generate_kmers(&contaminant, k).into_iter().collect()),
+    ];
+    let report = screen_contaminants(&sample, &panel, k);
+    assert_eq!(report, vec![("phix".to_string(), 186.0 / 972.0), ("ecoli".to_string(), 0.0)]);
+}
+
+#[test]
+fn abundance_ranks_run_from_one_with_non_increasing_counts() {
+    let ranked = abundance_rank_data(&counts(&[("AAA", 3), ("CCC", 9), ("GGG", 1), ("TTT", 3)]));
+    assert_eq!(ranked, vec![(1, 9), (2, 3), (3, 3), (4, 1)]);
+    assert!(ranked.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+    assert!(abundance_rank_data(&HashMap::new()).is_empty());
+}
+
+#[test]
+fn coverage_gc_binning_separates_two_contig_groups() {
+    let k = 15;
+    // Low-coverage AT-rich contigs and high-coverage GC-rich ones.
+    let contig = |seed, from, to| generate_random_dna_sequence_seeded(300, seed).replace(from, to);
+    let at_rich: Vec<String> = (250..254).map(|seed| contig(seed, "G", "A")).collect();
+    let gc_rich: Vec<String> = (254..258).map(|seed| contig(seed, "A", "G")).collect();
+    let reads: Vec<&String> = at_rich.iter().flat_map(|c| [c; 5]).chain(gc_rich.iter().flat_map(|c| [c; 40])).collect();
+    let kmer_counts = count_kmers(reads.iter().flat_map(|read| generate_kmers(read, k)).collect());
+
+    let contigs = [at_rich, gc_rich].concat();
+    let bins = bin_by_coverage_gc(&contigs, &kmer_counts, k, 2, 7);
+    assert!(bins[..4].iter().all(|&bin| bin == bins[0]));
+    assert!(bins[4..].iter().all(|&bin| bin == bins[4]));
+    assert_ne!(bins[0], bins[4]);
+}
+
+#[test]
+fn novel_content_windows_flag_an_inserted_segment() {
+    let reference = generate_random_dna_sequence_seeded(4000, 258);
+    let insertion = generate_random_dna_sequence_seeded(1000, 259);
+    let sample = format!("{}{}{}", &reference[..2000], insertion, &reference[2000..]);
+    let reference_kmers: HashSet<String> = generate_kmers(&reference, 21).into_iter().collect();
+
+    let windows = novel_content_windows(&sample, &reference_kmers, 21, 500);
+    assert_eq!(windows.len(), 10);
+    for (start, novelty) in windows {
+        if (2000..3000).contains(&start) {
+            assert!(novelty > 0.95, "window {start}: {novelty}");
+        } else {
+            assert!(novelty < 0.05, "window {start}: {novelty}");
+        }
+    }
+}
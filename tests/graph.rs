@@ -0,0 +1,437 @@
+use std::collections::BTreeMap;
+use kmer_analysis::graph::{
+    build_overlap_graph, compare_graphs_for_variants, consensus_graph, count_branching_nodes, create_petgraph,
+    detect_misassembly, graph_metrics, min_assemblable_k, path_coverage_profile, spell_path, DeBruijnGraph, VariantKind,
+    MAX_COLORS,
+};
+use kmer_analysis::kmer::{count_kmers, generate_kmers, generate_kmers_filtered, generate_packed_kmers, Kmer};
+use kmer_analysis::simulate::generate_random_dna_sequence_seeded;
+use kmer_analysis::{Alphabet, KmerError};
+
+#[test]
+fn builds_edges_between_overlapping_nodes() {
+    let graph = DeBruijnGraph::new(&generate_kmers("ACGTC", 3));
+    assert_eq!(graph.k, 3);
+    assert_eq!(graph.edges["AC"], BTreeMap::from([("CG".to_string(), 1)]));
+    assert_eq!(graph.edges["CG"], BTreeMap::from([("GT".to_string(), 1)]));
+    assert_eq!(graph.edges["GT"], BTreeMap::from([("TC".to_string(), 1)]));
+}
+
+#[test]
+fn contigs_spell_a_non_branching_sequence() {
+    let sequence = "ATGGCGTGCAATC";
+    let graph = DeBruijnGraph::new(&generate_kmers(sequence, 4));
+    assert_eq!(graph.contigs(), vec![sequence.to_string()]);
+}
+
+#[test]
+fn petgraph_has_one_node_per_k_minus_one_mer() {
+    let graph = DeBruijnGraph::new(&generate_kmers("ACGTC", 3));
+    let petgraph = create_petgraph(&graph);
+    assert_eq!(petgraph.node_count(), 4);
+    assert_eq!(petgraph.edge_count(), 3);
+}
+
+#[test]
+fn packed_graph_decodes_to_the_string_graph() {
+    let sequence = "ATGGCGTGCANATGGCGTACC";
+    let packed = DeBruijnGraph::<Kmer>::from_kmers(&generate_packed_kmers(sequence, 5), 5);
+    let strings = DeBruijnGraph::new(&generate_kmers_filtered(sequence, 5));
+    assert_eq!(packed.branching_node_count(), strings.branching_node_count());
+    assert_eq!(packed.decode().edge_weights(), strings.edge_weights());
+}
+
+#[test]
+fn counts_packed_kmers() {
+    let counts = count_kmers(generate_packed_kmers("AAAAC", 2));
+    assert_eq!(counts[&"AA".parse::<Kmer>().unwrap()], 3);
+    assert_eq!(counts.len(), 2);
+}
+
+#[test]
+fn assembles_the_original_sequence_through_a_repeat() {
+    // The repeated 3-mer GCA makes the graph branch, but an Eulerian path still spells it.
+    let sequence = "TTGCAAGCATT";
+    let graph = DeBruijnGraph::new(&generate_kmers(sequence, 4));
+    assert_eq!(graph.assemble(), vec![sequence.to_string()]);
+}
+
+#[test]
+fn falls_back_to_contigs_without_an_eulerian_path() {
+    let mut graph = DeBruijnGraph::new(&generate_kmers("ACGTTG", 3));
+    graph.add_sequence("GGCATC", 3).unwrap();
+    let mut assembly = graph.assemble();
+    assembly.sort();
+    assert_eq!(assembly, vec!["ACGTTG", "GGCATC"]);
+}
+
+#[test]
+fn rejects_sequences_added_with_another_k() {
+    let mut graph = DeBruijnGraph::new(&generate_kmers("ACGTTG", 3));
+    assert!(matches!(graph.add_sequence("GGCATC", 4), Err(KmerError::KMismatch { k: 4, expected: 3 })));
+    assert!(matches!(DeBruijnGraph::new(&[]).add_sequence("GGCATC", 1), Err(KmerError::InvalidK { k: 1, .. })));
+}
+
+#[test]
+fn removes_an_erroneous_tip() {
+    let reference = "ATGGCGTGCAATCCGATTAC";
+    let mut graph = DeBruijnGraph::new(&generate_kmers(reference, 5));
+    // A read whose last base is a sequencing error branches off the end of the graph.
+    graph.add_sequence("GCAATCCGAG", 5).unwrap();
+    let report = graph.remove_tips(3);
+    assert_eq!((report.nodes_before, report.nodes_after), (18, 17));
+    assert_eq!(graph.assemble(), vec![reference.to_string()]);
+}
+
+#[test]
+fn pops_a_snp_bubble_keeping_the_covered_path() {
+    let reference = "ATGGCGTGCAATCCGATTAC";
+    let variant = "ATGGCGTGCTATCCGATTAC";
+    let mut graph = DeBruijnGraph::new(&generate_kmers(reference, 5));
+    graph.add_sequence(reference, 5).unwrap();
+    graph.add_sequence(variant, 5).unwrap();
+    assert!(graph.branching_node_count() > 0);
+    let report = graph.pop_bubbles();
+    assert!(report.nodes_after < report.nodes_before);
+    assert_eq!(graph.contigs(), vec![reference.to_string()]);
+}
+
+#[test]
+fn collapses_unitigs_around_a_branch() {
+    // GCA occurs twice, so the graph splits into unitigs at the repeated node.
+    let graph = DeBruijnGraph::new(&generate_kmers("TTGCAAGCATT", 4));
+    let (unitigs, report) = graph.collapse_unitigs();
+    assert_eq!(report.nodes_before, graph.node_count());
+    assert_eq!(report.nodes_after, unitigs.segments.len());
+    assert_eq!(unitigs.links.len(), report.edges_after);
+    for &(from, to) in &unitigs.links {
+        let (from, to) = (&unitigs.segments[from], &unitigs.segments[to]);
+        assert_eq!(from[from.len() - 3..], to[..3]);
+    }
+    assert!(unitigs.coverage.iter().all(|&coverage| coverage == 1.0));
+}
+
+#[test]
+fn graph_from_counts_matches_graph_from_kmers() {
+    let kmers = generate_kmers("ACGTACGTTACGA", 4);
+    let from_kmers = DeBruijnGraph::new(&kmers);
+    let from_counts = DeBruijnGraph::from_counts(&count_kmers(kmers));
+    assert_eq!(from_counts.k, 4);
+    assert_eq!(from_counts.edge_weights(), from_kmers.edge_weights());
+}
+
+#[test]
+fn metrics_summarize_degrees_components_and_paths() {
+    // A linear path ACG -> CGT -> GTA plus a separate TTT self-loop component
+    let dbg = DeBruijnGraph::new(&["ACGT".to_string(), "CGTA".to_string(), "TTTT".to_string()]);
+    let metrics = graph_metrics(&create_petgraph(&dbg));
+    assert_eq!(metrics.nodes, 4);
+    assert_eq!(metrics.edges, 3);
+    assert_eq!(metrics.components, 2);
+    assert_eq!(metrics.branching_nodes, 0);
+    assert_eq!(metrics.in_degrees, [(0, 1), (1, 3)].into_iter().collect());
+    assert_eq!(metrics.longest_path, 2);
+    assert!(!metrics.acyclic);
+
+    let linear = graph_metrics(&create_petgraph(&DeBruijnGraph::new(&generate_kmers("ACGTTGCA", 3))));
+    assert!(linear.acyclic);
+    assert_eq!(linear.longest_path, 6);
+}
+
+#[test]
+fn colored_graph_records_which_samples_each_edge_came_from() {
+    let samples = vec![
+        ("a".to_string(), vec!["ACGTA".to_string()]),
+        ("b".to_string(), vec!["ACGTT".to_string(), "ACG".to_string()]),
+    ];
+    let dbg = DeBruijnGraph::from_samples(&samples, 3, &Alphabet::dna()).unwrap();
+    assert!(dbg.is_colored());
+    assert_eq!(dbg.samples, ["a", "b"]);
+    assert_eq!(dbg.colors[&("AC".to_string(), "CG".to_string())], 0b11);
+    assert_eq!(dbg.colors[&("GT".to_string(), "TA".to_string())], 0b01);
+    assert_eq!(dbg.colors[&("GT".to_string(), "TT".to_string())], 0b10);
+    // Multiplicities of both samples add up
+    assert_eq!(dbg.edge_weight(&"AC".to_string(), &"CG".to_string()), 3);
+
+    let node_colors = dbg.node_colors();
+    assert_eq!(dbg.sample_names(node_colors["GT"]), ["a", "b"]);
+    assert_eq!(dbg.sample_names(node_colors["TA"]), ["a"]);
+    assert_eq!(dbg.sample_edge_counts(), [(3, 1), (3, 1)]);
+    assert_eq!(dbg.color_histogram(), [(1, 2), (2, 2)].into_iter().collect());
+
+    let mut clipped = dbg.clone();
+    clipped.remove_tips(1);
+    assert!(clipped.colors.keys().all(|(node, next)| clipped.edges.get(node).is_some_and(|successors| successors.contains_key(next))));
+}
+
+#[test]
+fn colored_graph_refuses_more_samples_than_colors() {
+    let samples: Vec<(String, Vec<String>)> = (0..=MAX_COLORS).map(|i| (i.to_string(), vec!["ACGT".to_string()])).collect();
+    assert!(matches!(
+        DeBruijnGraph::from_samples(&samples, 3, &Alphabet::dna()),
+        Err(KmerError::TooManySamples { samples: 65, max: 64 })
+    ));
+}
+
+#[test]
+fn repeated_kmers_raise_edge_weights_and_low_coverage_edges_can_be_dropped() {
+    let reference = "ATGGCGTGCAATCCGATTAC";
+    let mut graph = DeBruijnGraph::new(&generate_kmers(reference, 5));
+    graph.add_sequence(reference, 5).unwrap();
+    graph.add_sequence("GCAATCCGAG", 5).unwrap();
+    assert_eq!(graph.edges["CCGA"], BTreeMap::from([("CGAG".to_string(), 1), ("CGAT".to_string(), 2)]));
+    assert_eq!(graph.edge_weight(&"TCCG".to_string(), &"CCGA".to_string()), 3);
+
+    let report = graph.remove_low_coverage_edges(2);
+    assert_eq!((report.edges_before, report.edges_after), (17, 16));
+    assert_eq!((report.nodes_before, report.nodes_after), (18, 17));
+    assert_eq!(graph.contigs(), vec![reference.to_string()]);
+}
+
+#[test]
+fn keeps_a_dead_end_better_covered_than_the_other_branch() {
+    let reference = "ATGGCGTGCAATCCGATTAC";
+    let mut graph = DeBruijnGraph::new(&generate_kmers(reference, 5));
+    for _ in 0..3 {
+        graph.add_sequence("GCAATCCGAG", 5).unwrap();
+    }
+    // The reference's own end is now the weaker branch and is the tip instead.
+    let reference_end: Vec<String> = ["TTAC", "ATTA", "GATT", "CGAT"].map(String::from).into();
+    assert_eq!(graph.find_tips(4), vec![reference_end]);
+}
+
+#[test]
+fn greedy_contigs_follow_the_best_covered_branch() {
+    let reference = "ATGGCGTGCAATCCGATTAC";
+    let mut graph = DeBruijnGraph::new(&generate_kmers(reference, 5));
+    graph.add_sequence(reference, 5).unwrap();
+    graph.add_sequence("ATGGCGTGCTATCCGATTAC", 5).unwrap();
+    assert!(graph.contigs().len() > 1);
+
+    let contigs = graph.greedy_contigs();
+    assert_eq!(contigs[0].0, reference);
+    assert_eq!(contigs[0].1, 43.0 / 16.0);
+    assert_eq!(contigs.len(), 2);
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+#[test]
+fn layout_places_every_node_and_keeps_neighbours_closer_than_distant_nodes() {
+    let sequence = "ATGGCGTGCAATCCGATTAC";
+    let graph = DeBruijnGraph::new(&generate_kmers(sequence, 4));
+    let layout = graph.layout();
+    assert_eq!(layout.len(), graph.node_count());
+    assert!(layout.values().all(|(x, y)| x.is_finite() && y.is_finite()));
+    // Adjacent nodes along the path versus the two ends of the path.
+    let neighbours = distance(layout["GCG"], layout["CGT"]);
+    let ends = distance(layout["ATG"], layout["TAC"]);
+    assert!(neighbours < ends);
+}
+
+#[test]
+fn reports_only_the_under_covered_side_path() {
+    let reference = "ATGGCGTGCAATCCGATTAC";
+    let mut graph = DeBruijnGraph::new(&generate_kmers(reference, 5));
+    graph.add_sequence(reference, 5).unwrap();
+    graph.add_sequence(reference, 5).unwrap();
+    // A single read carrying a SNP diverges from the well-covered path and rejoins it.
+    graph.add_sequence("GCGTGCTATCCGA", 5).unwrap();
+    let regions = graph.low_coverage_regions(2);
+    assert_eq!(regions, vec![vec!["GTGC", "TGCT", "GCTA", "CTAT", "TATC", "ATCC"]]);
+}
+
+#[test]
+fn counts_branching_nodes_and_complexity() {
+    let linear = DeBruijnGraph::new(&generate_kmers("ATGGCGTGCAATC", 4));
+    assert_eq!(linear.branching_node_count(), 0);
+    assert_eq!(linear.complexity_ratio(), 0.0);
+    // Only the repeated GCA node fans in and out.
+    let branched = DeBruijnGraph::new(&generate_kmers("TTGCAAGCATT", 4));
+    assert_eq!(branched.branching_node_count(), 1);
+    assert_eq!(branched.complexity_ratio(), 1.0 / 8.0);
+}
+
+#[test]
+fn trimming_removes_nested_tips_over_several_passes() {
+    let reference = "ATGGCGTGCAATCCGATTAC";
+    let mut graph = DeBruijnGraph::new(&generate_kmers(reference, 5));
+    for _ in 0..2 {
+        graph.add_sequence(reference, 5).unwrap();
+    }
+    // Two erroneous reads leave the reference at CCGA and split again at GAGT, so the two
+    // dead ends only expose the CGAG-GAGT stem as a tip once they are clipped.
+    graph.add_sequence("ATCCGAGTTG", 5).unwrap();
+    graph.add_sequence("CCGAGTC", 5).unwrap();
+
+    let mut single_pass = graph.clone();
+    assert_eq!(single_pass.trim_tips(3, 1), 2);
+    assert_ne!(single_pass.contigs(), vec![reference.to_string()]);
+
+    assert_eq!(graph.trim_tips(3, 10), 3);
+    assert_eq!(graph.contigs(), vec![reference.to_string()]);
+}
+
+#[test]
+fn degree_map_records_in_and_out_degrees_of_a_branching_graph() {
+    let graph = DeBruijnGraph::new(&generate_kmers("TTGCAAGCATT", 4));
+    let degrees = graph.degrees();
+    assert_eq!(degrees.len(), 8);
+    assert_eq!(degrees["TTG"], (0, 1));
+    assert_eq!(degrees["GCA"], (2, 2));
+    assert_eq!(degrees["ATT"], (1, 0));
+    assert_eq!(count_branching_nodes(&degrees), 1);
+    // Tips found with the shared map match those found from scratch.
+    assert_eq!(graph.find_tips_with_degrees(3, &degrees), graph.find_tips(3));
+}
+
+#[test]
+fn threading_reads_resolves_a_collapsed_repeat() {
+    let k = 9;
+    let random = |length, seed| generate_random_dna_sequence_seeded(length, seed);
+    let repeat = random(20, 30);
+    let genome = [random(30, 31), repeat.clone(), random(30, 32), repeat, random(30, 33)].concat();
+    let graph = DeBruijnGraph::new(&generate_kmers(&genome, k));
+    // The repeat is collapsed, so unitigs alone cannot say which flank follows which.
+    assert!(graph.contigs().len() > 1);
+
+    let reads: Vec<String> = (0..=genome.len() - 40).map(|start| genome[start..start + 40].to_string()).collect();
+    assert_eq!(graph.thread_reads(&reads), vec![genome]);
+}
+
+#[test]
+fn consensus_keeps_only_the_core_shared_by_every_graph() {
+    let core = generate_random_dna_sequence_seeded(40, 40);
+    let graphs: Vec<DeBruijnGraph> = (41..44)
+        .map(|seed| {
+            let flank = generate_random_dna_sequence_seeded(15, seed);
+            DeBruijnGraph::new(&generate_kmers(&(flank + &core), 7))
+        })
+        .collect();
+    let consensus = consensus_graph(&graphs, 3);
+    assert_eq!(consensus.k, 7);
+    assert_eq!(consensus.edge_weights(), DeBruijnGraph::new(&generate_kmers(&core, 7)).edge_weights());
+    assert!(consensus_graph(&graphs, 1).edge_count() > consensus.edge_count());
+}
+
+#[test]
+fn graph_comparison_reports_a_novel_junction_with_its_flanks() {
+    let (left, right) = (generate_random_dna_sequence_seeded(30, 60), generate_random_dna_sequence_seeded(30, 61));
+    // The insertion differs from the bases either side of it, so the junctions fall exactly at its ends.
+    let insertion = "TATCGATTGCAGTA";
+    let reference = DeBruijnGraph::new(&generate_kmers(&[left.as_str(), &right].concat(), 7));
+    let sample = DeBruijnGraph::new(&generate_kmers(&[left.as_str(), insertion, &right].concat(), 7));
+
+    let variants = compare_graphs_for_variants(&reference, &sample);
+    let novel: Vec<_> = variants.iter().filter(|variant| variant.kind == VariantKind::Novel).collect();
+    assert_eq!(novel.len(), 1);
+    assert_eq!(novel[0].left_flank.as_deref(), Some(&left[24..]));
+    assert_eq!(novel[0].right_flank.as_deref(), Some(&right[..6]));
+    assert_eq!(spell_path(&novel[0].path), [&left[24..], insertion, &right[..6]].concat());
+    assert!(variants.iter().any(|variant| variant.kind == VariantKind::Missing));
+}
+
+#[test]
+fn overlap_graph_links_reads_that_overlap_end_to_start() {
+    let genome = generate_random_dna_sequence_seeded(1_000, 90);
+    let reads = vec![
+        genome[0..300].to_string(),
+        genome[200..500].to_string(),
+        genome[400..700].to_string(),
+        generate_random_dna_sequence_seeded(300, 91),
+    ];
+    let graph = build_overlap_graph(&reads, 15, 10, 50);
+    assert_eq!(graph.node_count(), 4);
+    let mut edges: Vec<(usize, usize, usize)> = graph
+        .raw_edges()
+        .iter()
+        .map(|edge| (graph[edge.source()], graph[edge.target()], edge.weight))
+        .collect();
+    edges.sort();
+    assert_eq!(edges, [(0, 1, 100), (1, 2, 100)]);
+}
+
+#[test]
+fn smallest_assemblable_k_exceeds_the_repeat_length() {
+    let repeat = generate_random_dna_sequence_seeded(12, 110);
+    let flank = |seed| generate_random_dna_sequence_seeded(40, seed);
+    let sequence = [flank(111), repeat.clone(), flank(112), repeat, flank(113)].concat();
+    let k = min_assemblable_k(&sequence, 2..=40).unwrap();
+    assert!(k > 12, "k = {k}");
+    assert!(DeBruijnGraph::new(&generate_kmers(&sequence, k)).is_simple_path());
+    assert_eq!(min_assemblable_k(&sequence, 2..=10), None);
+}
+
+#[test]
+fn flags_an_injected_coverage_jump_along_a_path() {
+    let sequence = generate_random_dna_sequence_seeded(40, 130);
+    let mut graph = DeBruijnGraph::new(&generate_kmers(&sequence, 9));
+    for _ in 0..9 {
+        graph.add_sequence(&sequence[20..], 9).unwrap();
+    }
+    let path = generate_kmers(&sequence, 8);
+    let profile = path_coverage_profile(&path, &graph);
+    assert_eq!(profile, [vec![1; 20], vec![10; 12]].concat());
+    assert_eq!(detect_misassembly(&profile, 3.0), vec![20]);
+    assert!(detect_misassembly(&profile, 20.0).is_empty());
+}
+
+#[test]
+fn incremental_insertion_matches_building_from_both_sequences() {
+    let first = generate_random_dna_sequence_seeded(80, 170);
+    // Overlaps the first sequence so shared k-mers gain weight rather than new edges.
+    let second = first[40..].to_string() + &generate_random_dna_sequence_seeded(40, 171);
+    let mut incremental = DeBruijnGraph::new(&[]);
+    incremental.add_sequence(&first, 7).unwrap();
+    incremental.add_sequence(&second, 7).unwrap();
+
+    let at_once = DeBruijnGraph::from_sequences(&[first, second], 7, &Alphabet::dna());
+    assert_eq!(incremental.k, at_once.k);
+    assert_eq!(incremental.edges, at_once.edges);
+    assert!(incremental.edge_weights().values().any(|&weight| weight == 2));
+}
+
+#[test]
+fn shortest_reconstruction_takes_the_shorter_route_between_anchors() {
+    let reference = "ATGGCGTGCAATCCGATTAC";
+    let mut graph = DeBruijnGraph::new(&generate_kmers(reference, 5));
+    // A longer alternative route between the same flanks, carrying a 4-base insertion.
+    graph.add_sequence("GTGCAAGGTTTCCGAT", 5).unwrap();
+    assert_eq!(graph.shortest_reconstruction("ATGGC", "ATTAC").as_deref(), Some(reference));
+    assert_eq!(graph.shortest_reconstruction("GCAAG", "TCCGA").as_deref(), Some("GCAAGGTTTCCGA"));
+    assert_eq!(graph.shortest_reconstruction("ATTAC", "ATGGC"), None);
+    assert_eq!(graph.shortest_reconstruction("ATGGC", "CCCCC"), None);
+}
+
+#[test]
+fn petgraph_edges_point_from_prefix_to_suffix_with_multiplicity_weights() {
+    // AAC occurs twice, so AA -> AC is one edge of weight 2 rather than two parallel edges.
+    let graph = DeBruijnGraph::new(&generate_kmers("AACTAAC", 3));
+    let petgraph = create_petgraph(&graph);
+    let mut edges: Vec<(&str, &str, usize)> = petgraph
+        .raw_edges()
+        .iter()
+        .map(|edge| (petgraph[edge.source()].as_str(), petgraph[edge.target()].as_str(), edge.weight))
+        .collect();
+    edges.sort();
+    assert_eq!(edges, [("AA", "AC", 2), ("AC", "CT", 1), ("CT", "TA", 1), ("TA", "AA", 1)]);
+}
+
+#[test]
+fn eulerian_path_reconstructs_the_source_sequence() {
+    let sequence = generate_random_dna_sequence_seeded(200, 240);
+    let graph = DeBruijnGraph::new(&generate_kmers(&sequence, 12));
+    assert_eq!(graph.eulerian_path(), Some(sequence));
+
+    // With a repeat the walk may order the unique segments differently, but it still uses
+    // every k-mer exactly as often as the source sequence does.
+    let repeat = "ACGTTGCA";
+    let sequence = ["TTT", repeat, "GGCC", repeat, "CATG", repeat, "AAA"].concat();
+    let graph = DeBruijnGraph::new(&generate_kmers(&sequence, 5));
+    let walk = graph.eulerian_path().unwrap();
+    assert_eq!(walk.len(), sequence.len());
+    assert_eq!(count_kmers(generate_kmers(&walk, 5)), count_kmers(generate_kmers(&sequence, 5)));
+    assert!(walk.starts_with("TTT") && walk.ends_with("AAA"));
+}
@@ -0,0 +1,70 @@
+use kmer_analysis::index::{bwt, inverse_bwt, FmIndex, KmerIndex, MphfIndex, Position};
+use kmer_analysis::kmer::generate_kmers;
+use kmer_analysis::simulate::generate_random_dna_sequence_seeded;
+use std::collections::HashSet;
+
+#[test]
+fn locates_every_occurrence_across_sequences() {
+    let sequences = vec!["ACGTACG".to_string(), "TTACGNACG".to_string()];
+    let index = KmerIndex::build(&sequences, 3);
+    let at = |sequence, offset| Position { sequence, offset };
+    assert_eq!(index.locate("ACG"), vec![at(0, 0), at(0, 4), at(1, 2), at(1, 6)]);
+    assert_eq!(index.locate("tac"), vec![at(0, 3), at(1, 1)]);
+    // K-mers spanning the N aren't indexed
+    assert!(index.locate("CGN").is_empty());
+    assert!(index.locate("ACGT").is_empty());
+    assert_eq!(index.k(), 3);
+}
+
+#[test]
+fn inverse_bwt_recovers_the_original_sequence() {
+    let (transformed, index) = bwt("BANANA");
+    assert_eq!(transformed, "ANNB$AA");
+    assert_eq!(inverse_bwt(&transformed, index), "BANANA");
+    for sequence in ["", "A", "AAAAAAAA", &"ACGT".repeat(50), &generate_random_dna_sequence_seeded(2_000, 6)] {
+        let (transformed, index) = bwt(sequence);
+        assert_eq!(inverse_bwt(&transformed, index), sequence);
+    }
+}
+
+#[test]
+fn fm_index_counts_and_locates_repeated_and_absent_patterns() {
+    let text = "ACGTACGTTTACGA";
+    let index = FmIndex::build(text);
+    assert_eq!(index.count("ACG"), 3);
+    assert_eq!(index.locate("ACG"), vec![0, 4, 10]);
+    assert_eq!(index.count("T"), 4);
+    assert_eq!(index.locate("T"), vec![3, 7, 8, 9]);
+    assert_eq!(index.count("GG"), 0);
+    assert!(index.locate("GG").is_empty());
+    assert!(index.locate("ACGTACGTTTACGAA").is_empty());
+
+    // On a longer text, every occurrence found by a naive scan is located.
+    let genome = generate_random_dna_sequence_seeded(5_000, 150);
+    let index = FmIndex::build(&genome);
+    let pattern = &genome[1_234..1_240];
+    let expected: Vec<usize> = (0..=genome.len() - 6).filter(|&i| &genome[i..i + 6] == pattern).collect();
+    assert_eq!(index.count(pattern), expected.len());
+    assert_eq!(index.locate(pattern), expected);
+}
+
+#[test]
+fn mphf_maps_every_member_to_a_distinct_index_in_range() {
+    let sequence = generate_random_dna_sequence_seeded(5000, 257);
+    let mut kmers = generate_kmers(&sequence, 21);
+    kmers.sort();
+    kmers.dedup();
+    let index = MphfIndex::build(&kmers);
+    assert_eq!(index.len(), kmers.len());
+
+    let mut seen = HashSet::new();
+    for kmer in &kmers {
+        let slot = index.lookup(kmer).expect("every member has an index");
+        assert!(slot < kmers.len());
+        assert!(seen.insert(slot), "{} shares index {}", kmer, slot);
+    }
+    // No keys are stored, so the index is a small fraction of the raw k-mer bytes
+    let key_bytes: usize = kmers.iter().map(String::len).sum();
+    assert!(index.size_in_bytes() * 10 < key_bytes, "{} vs {}", index.size_in_bytes(), key_bytes);
+    assert!(MphfIndex::build(&[]).is_empty());
+}
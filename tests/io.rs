@@ -0,0 +1,467 @@
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use kmer_analysis::io::fasta::{count_kmers_in_file, detect_quality_encoding, records, QualityEncoding, SequenceFormat};
+use kmer_analysis::graph::DeBruijnGraph;
+use kmer_analysis::io::batch::glob_match;
+use kmer_analysis::io::{
+    batch_samples, count_kmers_on_disk, read_batch_manifest, BatchSample, count_kmers_packed_streaming, DiskCountOptions, parse_sequences, read_sequences, write_graph_to_csv, write_graph_to_gfa, write_kmer_counts_to_csv,
+    write_counts_long_csv, write_fasta, write_fastq, write_kmer_summary_to_csv, write_matrix_to_csv, FastqRecord,
+    write_graph_to_json, write_kmer_counts_to_json,
+};
+use kmer_analysis::io::{
+    count_kmers_streaming, count_kmers_streaming_with_buffer, is_saved_index, load_index, open_input, save_graph,
+    save_kmer_counts, write_kmer_counts_jellyfish, write_kmer_counts_kmc, write_kmer_counts_to_tsv, write_manifest,
+    write_wig, AnalysisParams, KMER_DATABASE_MAGIC, KmerDatabase, SavedIndex,
+};
+use kmer_analysis::simulate::generate_random_dna_sequence_seeded;
+use kmer_analysis::kmer::{count_kmers, decode_kmer, generate_kmers, generate_kmers_filtered, CountOrder, KmerSummary};
+
+fn temp_file(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("kmer-analysis-{}-{}", std::process::id(), name))
+}
+
+#[test]
+fn reads_multi_record_fasta() {
+    let path = temp_file("reads.fa");
+    fs::write(&path, ">first sample\nACGT\nAC\n>second\nGGGG\n").unwrap();
+    assert_eq!(read_sequences(path.to_str().unwrap()).unwrap(), vec!["ACGTAC", "GGGG"]);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn streams_fastq_records_with_quality() {
+    let parsed = records("@read1\nACGT\n+\n@III\n@read2\nGG\n+\nII\n".as_bytes()).unwrap();
+    assert_eq!(parsed.format(), SequenceFormat::Fastq);
+    let parsed: Vec<_> = parsed.map(Result::unwrap).collect();
+    assert_eq!(parsed.len(), 2);
+    assert_eq!(parsed[0].id, "read1");
+    assert_eq!(parsed[0].quality.as_deref(), Some("@III"));
+    assert_eq!(parsed[1].sequence, "GG");
+}
+
+#[test]
+fn raw_text_is_one_record() {
+    assert_eq!(parse_sequences("ACG\nTT\n"), vec!["ACGTT"]);
+}
+
+#[test]
+fn counts_do_not_span_records() {
+    let path = temp_file("records.fa");
+    fs::write(&path, ">a\nAAAA\n>b\nCCCC\n").unwrap();
+    let counts = count_kmers_in_file(path.to_str().unwrap(), 3, false).unwrap();
+    assert_eq!(counts.len(), 2);
+    assert_eq!(counts["AAA"], 2);
+    assert_eq!(counts["CCC"], 2);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn canonical_counts_merge_strands() {
+    let path = temp_file("strands.fa");
+    fs::write(&path, ">forward\nAACG\n>reverse\nCGTT\n").unwrap();
+    let counts = count_kmers_in_file(path.to_str().unwrap(), 4, true).unwrap();
+    assert_eq!(counts.len(), 1);
+    assert_eq!(counts["AACG"], 2);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn writes_counts_as_csv_in_either_order() {
+    let path = temp_file("counts.csv");
+    let counts = count_kmers(generate_kmers("CCCCAC", 2));
+    write_kmer_counts_to_csv(&counts, CountOrder::Kmer, path.to_str().unwrap()).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "K-mer,Count\nAC,1\nCA,1\nCC,3\n");
+    write_kmer_counts_to_csv(&counts, CountOrder::Count, path.to_str().unwrap()).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "K-mer,Count\nCC,3\nAC,1\nCA,1\n");
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn writes_counts_in_tool_dump_formats() {
+    let counts = count_kmers(generate_kmers("AAACA", 2));
+    let path = temp_file("counts.dump");
+    let file_name = path.to_str().unwrap();
+    write_kmer_counts_to_tsv(&counts, CountOrder::Kmer, file_name).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "K-mer\tCount\nAA\t2\nAC\t1\nCA\t1\n");
+    write_kmer_counts_jellyfish(&counts, CountOrder::Kmer, file_name).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), ">2\nAA\n>1\nAC\n>1\nCA\n");
+    write_kmer_counts_kmc(&counts, CountOrder::Kmer, file_name).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "AA\t2\nAC\t1\nCA\t1\n");
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn writes_one_summary_row_per_k() {
+    let path = temp_file("summary.csv");
+    let summaries: Vec<KmerSummary> =
+        [2, 3].iter().map(|&k| KmerSummary::from_counts(k, &count_kmers(generate_kmers("AAAAC", k)))).collect();
+    write_kmer_summary_to_csv(&summaries, path.to_str().unwrap()).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "K,Distinct,Unique,Total\n2,2,1,4\n3,2,1,3\n");
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn writes_labelled_matrix() {
+    let path = temp_file("matrix.csv");
+    let labels = vec!["a.fa".to_string(), "b.fa".to_string()];
+    write_matrix_to_csv(&labels, &[vec![0.0, 0.25], vec![0.25, 0.0]], path.to_str().unwrap()).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), ",a.fa,b.fa\na.fa,0.000000,0.250000\nb.fa,0.250000,0.000000\n");
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn writes_wrapped_fasta_that_reads_back() {
+    let path = temp_file("contigs.fa");
+    let file_name = path.to_str().unwrap();
+    let contig = "ACGT".repeat(20);
+    write_fasta(&[("contig_1 length=80".to_string(), contig.clone()), ("contig_2".to_string(), "GGA".to_string())], file_name).unwrap();
+    let written = fs::read_to_string(&path).unwrap();
+    assert_eq!(written.lines().collect::<Vec<_>>(), vec![">contig_1 length=80", &contig[..60], &contig[60..], ">contig_2", "GGA"]);
+    assert_eq!(read_sequences(file_name).unwrap(), vec![contig, "GGA".to_string()]);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn gzipped_outputs_read_back_transparently() {
+    let path = temp_file("reads.fq.gz");
+    let file_name = path.to_str().unwrap();
+    let record = FastqRecord { id: "r1".to_string(), sequence: "ACGTAC".to_string(), quality: "IIIIII".to_string() };
+    write_fastq(&[record], file_name).unwrap();
+    assert!(fs::read(&path).unwrap().starts_with(&[0x1f, 0x8b]));
+    assert_eq!(read_sequences(file_name).unwrap(), vec!["ACGTAC"]);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn reads_every_member_of_a_bgzip_style_file() {
+    let path = temp_file("members.fa.gz");
+    let mut contents = Vec::new();
+    for chunk in [">a\nACGT", "AC\n>b\nGGGG\n"] {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(chunk.as_bytes()).unwrap();
+        contents.extend(encoder.finish().unwrap());
+    }
+    fs::write(&path, contents).unwrap();
+    let file_name = path.to_str().unwrap();
+    assert_eq!(read_sequences(file_name).unwrap(), vec!["ACGTAC", "GGGG"]);
+    assert_eq!(count_kmers_packed_streaming(file_name, 4, false).unwrap().values().sum::<usize>(), 4);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn disk_counting_matches_in_memory_counts_in_kmer_order() {
+    // The poly-A run fills one bucket past the limit, so it has to be split again
+    let path = temp_file("disk.fa");
+    let sequence = format!("{}{}", "A".repeat(3000), generate_random_dna_sequence_seeded(3000, 7));
+    fs::write(&path, format!(">a\n{}\n>b\nACGTNACGT\n", sequence)).unwrap();
+    let file_name = path.to_str().unwrap();
+    let tmp_dir = temp_file("disk-tmp");
+    let options = DiskCountOptions { tmp_dir: tmp_dir.clone(), memory_limit: 1 << 12 };
+    let counts: Vec<(u64, usize)> = count_kmers_on_disk(file_name, 5, true, &options).unwrap().map(Result::unwrap).collect();
+    let mut expected: Vec<(u64, usize)> = count_kmers_packed_streaming(file_name, 5, true).unwrap().into_iter().collect();
+    expected.sort();
+    assert_eq!(counts, expected);
+    // Every temporary file is gone once the counts have been read
+    assert_eq!(fs::read_dir(&tmp_dir).unwrap().count(), 0);
+    fs::remove_dir(tmp_dir).unwrap();
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn packed_streaming_matches_per_record_counts() {
+    let path = temp_file("streaming.fa");
+    fs::write(&path, ">a first\nACGTAC\nGTnAC\n>b\nacgtTT\n").unwrap();
+    let file_name = path.to_str().unwrap();
+    let packed = count_kmers_packed_streaming(file_name, 3, false).unwrap();
+    let decoded: std::collections::HashMap<String, usize> =
+        packed.into_iter().map(|(code, count)| (decode_kmer(code, 3), count)).collect();
+    assert_eq!(decoded, count_kmers_in_file(file_name, 3, false).unwrap());
+    assert!(count_kmers_packed_streaming(file_name, 33, false).is_err());
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn writes_unitigs_as_gfa() {
+    let path = temp_file("graph.gfa");
+    let graph = DeBruijnGraph::new(&generate_kmers("TTGCAAGCATT", 4));
+    write_graph_to_gfa(&graph, path.to_str().unwrap()).unwrap();
+    let written = fs::read_to_string(&path).unwrap();
+    // GCA occurs twice: the repeat is one unitig that loops onto itself and into the exit.
+    let expected = [
+        "H\tVN:Z:1.0",
+        "S\t1\tGCAAGCA\tLN:i:7\tKC:i:4",
+        "S\t2\tGCATT\tLN:i:5\tKC:i:2",
+        "S\t3\tTTGCA\tLN:i:5\tKC:i:2",
+        "L\t1\t+\t1\t+\t3M",
+        "L\t1\t+\t2\t+\t3M",
+        "L\t3\t+\t1\t+\t3M",
+        "L\t3\t+\t2\t+\t3M",
+    ];
+    assert_eq!(written.lines().collect::<Vec<_>>(), expected);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn saved_counts_load_back_unchanged() {
+    let path = temp_file("counts.idx");
+    let file_name = path.to_str().unwrap();
+    let counts = count_kmers(generate_kmers(&"ACGTTGCAAGT".repeat(30), 7));
+    save_kmer_counts(&counts, 7, file_name).unwrap();
+    assert!(is_saved_index(file_name));
+    match load_index(file_name).unwrap() {
+        SavedIndex::Counts { k, counts: loaded } => {
+            assert_eq!(k, 7);
+            assert_eq!(loaded, counts);
+        }
+        SavedIndex::Graph(_) => panic!("saved counts loaded as a graph"),
+    }
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn saved_graphs_keep_their_edge_weights() {
+    let path = temp_file("graph.idx.gz");
+    let file_name = path.to_str().unwrap();
+    let graph = DeBruijnGraph::new(&generate_kmers("ACGTACGTTTACGTA", 4));
+    save_graph(&graph, file_name).unwrap();
+    match load_index(file_name).unwrap() {
+        SavedIndex::Graph(loaded) => {
+            assert_eq!(loaded.k, 4);
+            assert_eq!(loaded.edge_weights(), graph.edge_weights());
+        }
+        SavedIndex::Counts { .. } => panic!("saved graph loaded as counts"),
+    }
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn graph_csv_lists_the_weight_of_every_edge() {
+    let path = temp_file("graph.csv");
+    let graph = DeBruijnGraph::new(&generate_kmers("ACGACGT", 3));
+    write_graph_to_csv(&graph, path.to_str().unwrap()).unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "Node,Connected Nodes,Weights\nAC,CG,2\nCG,\"GA, GT\",\"1, 1\"\nGA,AC,1\n"
+    );
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn rejects_saving_ambiguous_kmers_and_loading_other_files() {
+    let path = temp_file("bad.idx");
+    let file_name = path.to_str().unwrap();
+    let counts = count_kmers(vec!["ACNT".to_string()]);
+    assert_eq!(save_kmer_counts(&counts, 4, file_name).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    fs::write(&path, ">a\nACGT\n").unwrap();
+    assert!(!is_saved_index(file_name));
+    assert_eq!(load_index(file_name).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn batch_inputs_come_from_a_directory_a_glob_or_a_manifest() {
+    let dir = temp_file("batch");
+    fs::create_dir_all(&dir).unwrap();
+    for name in ["b.fa.gz", "a.fastq", "notes.md", ".hidden.fa"] {
+        fs::write(dir.join(name), "ACGT\n").unwrap();
+    }
+    let names = |samples: Vec<BatchSample>| samples.into_iter().map(|sample| sample.name).collect::<Vec<_>>();
+    assert_eq!(names(batch_samples(dir.to_str().unwrap()).unwrap()), ["a", "b"]);
+    assert_eq!(names(batch_samples(dir.join("*.md").to_str().unwrap()).unwrap()), ["notes.md"]);
+    assert!(batch_samples(dir.join("*.fq").to_str().unwrap()).is_err());
+
+    // Paths are relative to the manifest; a lone path is named after its file
+    let manifest = dir.join("samples.tsv");
+    fs::write(&manifest, "sample\tpath\n# comment\nfirst\ta.fastq\n\nb.fa.gz\n").unwrap();
+    let samples = read_batch_manifest(manifest.to_str().unwrap()).unwrap();
+    assert_eq!(
+        samples,
+        [BatchSample { name: "first".to_string(), path: dir.join("a.fastq") }, BatchSample { name: "b".to_string(), path: dir.join("b.fa.gz") }]
+    );
+    fs::write(&manifest, "x\ta.fastq\nx\tb.fa.gz\n").unwrap();
+    assert!(read_batch_manifest(manifest.to_str().unwrap()).is_err());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn glob_stars_match_any_run_and_question_marks_one_character() {
+    assert!(glob_match("*.fa", "sample.fa"));
+    assert!(glob_match("s?_*_R1*", "s1_lane2_R1.fq.gz"));
+    assert!(glob_match("*", ""));
+    assert!(!glob_match("*.fa", "sample.fasta"));
+    assert!(!glob_match("s?.fa", "s10.fa"));
+}
+
+#[test]
+fn detects_and_decodes_phred_64_qualities() {
+    // Phred+64 scores 2, 20, 30 and 40 are 'B', 'T', '^' and 'h'.
+    let phred64 = FastqRecord { id: "r1".to_string(), sequence: "ACGT".to_string(), quality: "BT^h".to_string() };
+    assert_eq!(detect_quality_encoding(std::slice::from_ref(&phred64)), QualityEncoding::Phred64);
+    assert_eq!(phred64.phred_scores(QualityEncoding::Phred64), [2, 20, 30, 40]);
+
+    let phred33 = FastqRecord { quality: "#5?I".to_string(), ..phred64 };
+    assert_eq!(detect_quality_encoding(std::slice::from_ref(&phred33)), QualityEncoding::Phred33);
+    assert_eq!(phred33.phred_scores(QualityEncoding::Phred33), [2, 20, 30, 40]);
+}
+
+#[test]
+fn writes_a_fixed_step_wig_track() {
+    let path = temp_file("coverage.wig");
+    write_wig("contig1", &[0, 3, 12], path.to_str().unwrap()).unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "track type=wiggle_0 name=\"contig1\"\nfixedStep chrom=contig1 start=1 step=1\n0\n3\n12\n"
+    );
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn kmer_database_answers_membership_on_either_strand() {
+    let path = temp_file("kmers.db");
+    let sequences = vec!["ACGTTGCA".to_string(), "GGATNCC".to_string()];
+    let database = KmerDatabase::build(&sequences, 4, path.to_str().unwrap()).unwrap();
+    // GCAA and ATCC only occur as the reverse complements of TTGC and GGAT.
+    for present in ["ACGT", "TTGC", "GCAA", "GGAT", "ATCC"] {
+        assert!(database.lookup(present), "{present}");
+    }
+    for absent in ["AAAA", "ATCG", "GATN", "ACG"] {
+        assert!(!database.lookup(absent), "{absent}");
+    }
+    let reopened = KmerDatabase::open(path.to_str().unwrap()).unwrap();
+    assert!(reopened.lookup("CGTT") && !reopened.lookup("CCCC"));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn kmer_database_rejects_a_count_that_overflows_the_file_size() {
+    let path = temp_file("overflow.db");
+    // 2^61 codes of 8 bytes wrap around to exactly the header's size
+    let mut header = KMER_DATABASE_MAGIC.to_vec();
+    header.extend(4u64.to_le_bytes());
+    header.extend((1u64 << 61).to_le_bytes());
+    fs::write(&path, header).unwrap();
+    let error = KmerDatabase::open(path.to_str().unwrap()).err().expect("overflowing count is rejected");
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn long_csv_has_one_row_per_record_and_kmer() {
+    let path = temp_file("long.csv");
+    let records = vec![
+        ("read2".to_string(), count_kmers(generate_kmers("ACGTA", 3))),
+        ("read1".to_string(), count_kmers(generate_kmers("AAAA", 3))),
+    ];
+    write_counts_long_csv(&records, path.to_str().unwrap()).unwrap();
+    let contents = fs::read_to_string(&path).unwrap();
+    let lines: Vec<&str> = contents.lines().collect();
+    assert_eq!(lines.len(), 1 + 1 + 3);
+    assert_eq!(lines[0], "record_id,kmer,count");
+    assert_eq!(lines[1], "read1,AAA,2");
+    assert_eq!(lines[2..], ["read2,ACG,1", "read2,CGT,1", "read2,GTA,1"]);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn tolerates_trailing_newlines_and_empty_records() {
+    assert_eq!(parse_sequences(">empty\n>first\nACG\n\nTT\n>blank\n\n>last\nGG\n\n\n"), vec!["ACGTT", "GG"]);
+    assert_eq!(parse_sequences("\n\n@read1\nACGT\n+\nIIII\n\n"), vec!["ACGT"]);
+    assert!(parse_sequences("").is_empty());
+    assert!(parse_sequences("\n\n").is_empty());
+    assert!(parse_sequences(">only\n").is_empty());
+}
+
+#[test]
+fn manifest_records_parameters_and_output_checksums() {
+    let (output, manifest) = (temp_file("counts.txt"), temp_file("manifest.json"));
+    fs::write(&output, "abc").unwrap();
+    let params = AnalysisParams { k: 5, seed: Some(42), length: Some(10_000), ..Default::default() };
+    write_manifest(&params, std::slice::from_ref(&output), manifest.to_str().unwrap()).unwrap();
+
+    let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&manifest).unwrap()).unwrap();
+    assert_eq!(json["parameters"]["k"], 5);
+    assert_eq!(json["parameters"]["seed"], 42);
+    assert_eq!(json["parameters"]["length"], 10_000);
+    assert!(json["parameters"]["input"].is_null());
+    let outputs = json["outputs"].as_array().unwrap();
+    assert_eq!(outputs.len(), 1);
+    assert_eq!(outputs[0]["path"], output.display().to_string());
+    assert_eq!(outputs[0]["size"], 3);
+    assert_eq!(outputs[0]["sha256"], "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    fs::remove_file(output).unwrap();
+    fs::remove_file(manifest).unwrap();
+}
+
+#[test]
+fn manifest_is_gzipped_under_a_gz_name() {
+    let (output, manifest) = (temp_file("manifest-counts.txt"), temp_file("manifest.json.gz"));
+    fs::write(&output, "abc").unwrap();
+    write_manifest(&AnalysisParams { k: 7, ..Default::default() }, std::slice::from_ref(&output), manifest.to_str().unwrap())
+        .unwrap();
+    assert!(fs::read(&manifest).unwrap().starts_with(&[0x1f, 0x8b]));
+    let json: serde_json::Value = serde_json::from_reader(open_input(manifest.to_str().unwrap()).unwrap()).unwrap();
+    assert_eq!(json["parameters"]["k"], 7);
+    fs::remove_file(output).unwrap();
+    fs::remove_file(manifest).unwrap();
+}
+
+#[test]
+fn streaming_counts_do_not_depend_on_the_buffer_size() {
+    let first = generate_random_dna_sequence_seeded(3000, 262);
+    let second = generate_random_dna_sequence_seeded(500, 263).to_lowercase();
+    let path = temp_file("streaming.fa");
+    fs::write(&path, format!(">first\n{}\n{}NN{}\n>second\n{}\n", &first[..1000], &first[1000..2000], &first[2000..], second)).unwrap();
+    let file_name = path.to_str().unwrap();
+
+    let k = 11;
+    let mut kmers = generate_kmers_filtered(&first[..2000], k);
+    kmers.extend(generate_kmers_filtered(&first[2000..], k));
+    kmers.extend(generate_kmers_filtered(&second.to_uppercase(), k));
+    let expected = count_kmers(kmers);
+    assert_eq!(count_kmers_streaming(file_name, k).unwrap(), expected);
+    // Buffers smaller than k, around k and far larger than the file must all agree
+    for buffer_size in [1, 2, k - 1, k, k + 1, 7, 4096, 1 << 20] {
+        assert_eq!(count_kmers_streaming_with_buffer(file_name, k, buffer_size).unwrap(), expected, "buffer {}", buffer_size);
+    }
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn streaming_counts_mask_non_ascii_bytes_across_buffer_boundaries() {
+    let path = temp_file("non-ascii.fa");
+    fs::write(&path, ">x\nACGTé\nACGT\n").unwrap();
+    let file_name = path.to_str().unwrap();
+    let expected = count_kmers(vec!["AC", "CG", "GT", "AC", "CG", "GT"].into_iter().map(String::from).collect());
+    for buffer_size in [1, 2, 5, 4096] {
+        assert_eq!(count_kmers_streaming_with_buffer(file_name, 2, buffer_size).unwrap(), expected, "buffer {}", buffer_size);
+    }
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn json_output_round_trips_through_serde() {
+    let counts_path = temp_file("counts.json");
+    let counts = count_kmers(generate_kmers("ACGACGTT", 3));
+    write_kmer_counts_to_json(&counts, counts_path.to_str().unwrap()).unwrap();
+    let parsed: std::collections::HashMap<String, usize> =
+        serde_json::from_str(&fs::read_to_string(&counts_path).unwrap()).unwrap();
+    assert_eq!(parsed, counts);
+
+    let graph_path = temp_file("graph.json");
+    let graph = DeBruijnGraph::new(&generate_kmers("ACGACGT", 3));
+    write_graph_to_json(&graph, graph_path.to_str().unwrap()).unwrap();
+    let nodes: serde_json::Value = serde_json::from_str(&fs::read_to_string(&graph_path).unwrap()).unwrap();
+    assert_eq!(
+        nodes,
+        serde_json::json!([
+            { "node": "AC", "edges": ["CG"], "weights": [2] },
+            { "node": "CG", "edges": ["GA", "GT"], "weights": [1, 1] },
+            { "node": "GA", "edges": ["AC"], "weights": [1] },
+        ])
+    );
+    fs::remove_file(counts_path).unwrap();
+    fs::remove_file(graph_path).unwrap();
+}
@@ -0,0 +1,328 @@
+use std::collections::HashMap;
+use kmer_analysis::io::FastqRecord;
+use kmer_analysis::KmerError;
+use kmer_analysis::kmer::{
+    base_composition, canonical_kmer, chunk_sequence, check_k, try_generate_kmers, Kmer, count_canonical_kmers, count_kmers, count_kmers_parallel, count_kmers_weighted, count_reduced_kmers, count_records_parallel, decode_kmer, encode_kmer, filter_counts, frequent_kmers_stream, generate_kmers, gc_content, generate_kmers_filtered, reverse_complement,
+    mask_low_quality, mask_low_quality_records, minimal_kmer_cover, murphy8_alphabet, sanitize_sequence, sanitize_sequence_with, sorted_counts, strobemers, top_kmers, validate_sequence, AmbiguousBasePolicy,
+    CountOrder, InvalidBasePolicy, KmerSummary,
+};
+use kmer_analysis::simulate::generate_random_dna_sequence_seeded;
+
+#[test]
+fn generates_overlapping_kmers() {
+    assert_eq!(generate_kmers("ACGTA", 3), vec!["ACG", "CGT", "GTA"]);
+    assert!(generate_kmers("ACG", 4).is_empty());
+    assert!(generate_kmers("ACG", 0).is_empty());
+}
+
+#[test]
+fn counts_and_ranks_kmers() {
+    let counts = count_kmers(generate_kmers("AAAAC", 2));
+    assert_eq!(counts["AA"], 3);
+    assert_eq!(counts["AC"], 1);
+    assert_eq!(top_kmers(&counts, 1), vec![("AA".to_string(), 3)]);
+}
+
+#[test]
+fn sorts_counts_by_kmer_or_count() {
+    let counts = count_kmers(generate_kmers("CCCCAC", 2));
+    let kmers = |order| sorted_counts(&counts, order).into_iter().map(|(kmer, _)| kmer.as_str()).collect::<Vec<_>>();
+    assert_eq!(kmers(CountOrder::Kmer), vec!["AC", "CA", "CC"]);
+    assert_eq!(kmers(CountOrder::Count), vec!["CC", "AC", "CA"]);
+    let packed = count_kmers(vec![encode_kmer("GT").unwrap(), encode_kmer("AC").unwrap()]);
+    let codes: Vec<String> = sorted_counts(&packed, CountOrder::Kmer).into_iter().map(|(&code, _)| decode_kmer(code, 2)).collect();
+    assert_eq!(codes, vec!["AC", "GT"]);
+}
+
+#[test]
+fn summarizes_distinct_unique_and_total_kmers() {
+    let counts = count_kmers(generate_kmers("AAAACG", 2));
+    assert_eq!(
+        KmerSummary::from_counts(2, &counts),
+        KmerSummary { k: 2, distinct: 3, unique: 2, total: 5 }
+    );
+}
+
+#[test]
+fn skips_kmers_spanning_ambiguous_bases() {
+    let sequence = sanitize_sequence("acgRtt");
+    assert_eq!(sequence, "ACGNTT");
+    assert_eq!(generate_kmers_filtered(&sequence, 2), vec!["AC", "CG", "TT"]);
+}
+
+#[test]
+fn canonical_kmer_is_strand_independent() {
+    assert_eq!(reverse_complement("AACG"), "CGTT");
+    assert_eq!(canonical_kmer("CGTT"), canonical_kmer("AACG"));
+}
+
+#[test]
+fn canonical_counting_merges_reverse_complements() {
+    let counts = count_canonical_kmers(vec!["AACG".to_string(), "CGTT".to_string(), "ACGT".to_string()]);
+    assert_eq!(counts["AACG"], 2);
+    assert_eq!(counts["ACGT"], 1);
+    assert!(!counts.contains_key("CGTT"));
+}
+
+#[test]
+fn encoding_round_trips() {
+    let code = encode_kmer("GATTACA").unwrap();
+    assert_eq!(decode_kmer(code, 7), "GATTACA");
+    assert_eq!(encode_kmer("GANTACA"), None);
+}
+
+#[test]
+fn parallel_record_counts_match_sequential_counts() {
+    let records = vec!["ACGTNACGTTGCA".repeat(20_000), "GGATCCAT".to_string()];
+    let sequential = count_kmers(records.iter().flat_map(|record| generate_kmers_filtered(record, 5)).collect());
+    assert_eq!(count_records_parallel(&records, 5, false), sequential);
+    let canonical = count_records_parallel(&records, 5, true);
+    assert_eq!(canonical.values().sum::<usize>(), sequential.values().sum::<usize>());
+}
+
+#[test]
+fn validation_normalises_case_and_whitespace() {
+    assert_eq!(validate_sequence("acgT\nnR tt\n", AmbiguousBasePolicy::Skip).unwrap(), "ACGTNNTT");
+}
+
+#[test]
+fn validation_reports_the_offending_character() {
+    assert_eq!(
+        validate_sequence("ACGTN", AmbiguousBasePolicy::Error).unwrap_err().to_string(),
+        "ambiguous base 'N' at position 5"
+    );
+    assert!(matches!(
+        validate_sequence("AC-GT", AmbiguousBasePolicy::Skip),
+        Err(KmerError::InvalidCharacter { character: '-', position: 3 })
+    ));
+}
+
+#[test]
+fn reports_an_out_of_range_k_instead_of_panicking() {
+    assert!(check_k(3, 3).is_ok());
+    assert!(matches!(check_k(0, 3), Err(KmerError::InvalidK { k: 0, .. })));
+    assert_eq!(try_generate_kmers("ACG", 4).unwrap_err().to_string(), "k = 4 is out of range: must be between 1 and 3");
+    assert_eq!(try_generate_kmers("ACGT", 3).unwrap(), vec!["ACG", "CGT"]);
+    assert!(matches!("ACGN".parse::<Kmer>(), Err(KmerError::InvalidKmer(_))));
+}
+
+#[test]
+fn masks_low_quality_bases_and_counts_filtered_kmers() {
+    assert_eq!(mask_low_quality("ACGTA", &[30, 30, 5, 30, 30], 20), "ACNTA");
+    let records = vec![
+        FastqRecord { id: "r1".to_string(), sequence: "ACGTAC".to_string(), quality: "II#III".to_string() },
+        FastqRecord { id: "r2".to_string(), sequence: "ACNTAC".to_string(), quality: "IIIII#".to_string() },
+    ];
+    let (masked, filtered) = mask_low_quality_records(&records, 3, 20);
+    assert_eq!(masked, vec!["ACNTAC", "ACNTAN"]);
+    // r1 loses ACG, CGT and GTA; r2 only loses TAC, as its N already hid the others
+    assert_eq!(filtered, 4);
+}
+
+#[test]
+fn filters_counts_outside_the_bounds() {
+    let mut counts = count_kmers(generate_kmers("AAAAACCGT", 2));
+    assert_eq!(filter_counts(&mut counts, 2, None), 4);
+    assert_eq!(counts.keys().collect::<Vec<_>>(), vec!["AA"]);
+    let mut counts = count_kmers(generate_kmers("AAAAACCGT", 2));
+    assert_eq!(filter_counts(&mut counts, 0, Some(1)), 1);
+    assert!(!counts.contains_key("AA"));
+}
+
+#[test]
+fn chunked_kmers_concatenate_to_the_whole_sequence_kmers() {
+    let sequence = generate_random_dna_sequence_seeded(1_000, 9);
+    let k = 7;
+    for chunk_size in [3, 7, 50, 333, 5_000] {
+        let chunked: Vec<String> = chunk_sequence(&sequence, chunk_size, k)
+            .into_iter()
+            .flat_map(|chunk| generate_kmers(chunk, k))
+            .collect();
+        assert_eq!(chunked, generate_kmers(&sequence, k), "chunk size {chunk_size}");
+    }
+}
+
+#[test]
+fn lowering_a_reads_quality_lowers_its_weighted_kmer_counts() {
+    let read = |id: &str, sequence: &str, quality: &str| FastqRecord {
+        id: id.to_string(),
+        sequence: sequence.to_string(),
+        quality: quality.to_string(),
+    };
+    let good = vec![read("r1", "ACGTAC", "IIIIII"), read("r2", "GGGTTT", "IIIIII")];
+    let degraded = vec![read("r1", "ACGTAC", "IIIIII"), read("r2", "GGGTTT", "++++++")];
+    let before = count_kmers_weighted(&good, 3);
+    let after = count_kmers_weighted(&degraded, 3);
+
+    for kmer in ["GGG", "GGT", "GTT", "TTT"] {
+        assert!(after[kmer] < before[kmer], "{kmer}");
+        // Three Q10 bases are each right with probability 0.9.
+        assert!((after[kmer] - 0.729).abs() < 1e-9);
+    }
+    for kmer in ["ACG", "CGT", "GTA", "TAC"] {
+        assert_eq!(after[kmer], before[kmer]);
+    }
+}
+
+#[test]
+fn minimal_cover_spans_every_base_with_the_fewest_kmers() {
+    let sequence = generate_random_dna_sequence_seeded(103, 2);
+    for k in [1, 5, 10, 103] {
+        let cover = minimal_kmer_cover(&sequence, k);
+        let mut covered = vec![false; sequence.len()];
+        for (start, kmer) in &cover {
+            assert_eq!(&sequence[*start..start + k], kmer);
+            covered[*start..start + k].iter_mut().for_each(|base| *base = true);
+        }
+        assert!(covered.iter().all(|&base| base), "k = {k}");
+        assert_eq!(cover.len(), sequence.len().div_ceil(k), "k = {k}");
+    }
+    assert!(minimal_kmer_cover("ACG", 4).is_empty());
+}
+
+#[test]
+fn misra_gries_keeps_heavy_hitters_with_few_counters() {
+    let k = 8;
+    let mut reads: Vec<String> = (0..200).map(|seed| generate_random_dna_sequence_seeded(20, seed)).collect();
+    for _ in 0..300 {
+        reads.push("ACGTTGCA".to_string());
+        reads.push("GGCCTTAA".to_string());
+    }
+    let distinct = count_kmers(reads.iter().flat_map(|read| generate_kmers(read, k)).collect()).len();
+    let capacity = 16;
+    assert!(capacity < distinct);
+
+    let frequent = frequent_kmers_stream(reads.into_iter(), k, capacity);
+    assert!(frequent.len() <= capacity);
+    for heavy in ["ACGTTGCA", "GGCCTTAA"] {
+        assert!(frequent[heavy] <= 300 && frequent[heavy] > 0, "{heavy}");
+    }
+}
+
+#[test]
+fn packed_kmers_round_trip_and_match_string_canonical_forms() {
+    let sequence = generate_random_dna_sequence_seeded(500, 12);
+    for k in [1, 5, 16, 32] {
+        for kmer in generate_kmers(&sequence, k) {
+            let packed: Kmer = kmer.parse().unwrap();
+            assert_eq!(packed.to_string(k), kmer);
+            assert_eq!(packed.reverse_complement(k).to_string(k), reverse_complement(&kmer));
+            assert_eq!(packed.canonical(k).to_string(k), canonical_kmer(&kmer));
+            assert_eq!(packed.is_palindrome(k), kmer == reverse_complement(&kmer));
+        }
+    }
+    assert!("ACGTNA".parse::<Kmer>().is_err());
+}
+
+#[test]
+fn strobemers_are_deterministic_and_survive_a_mismatch_between_strobes() {
+    let (k, w_min, w_max) = (10, 20, 40);
+    let sequence = generate_random_dna_sequence_seeded(1_000, 14);
+    let seeds = strobemers(&sequence, k, w_min, w_max, 2);
+    assert_eq!(seeds, strobemers(&sequence, k, w_min, w_max, 2));
+
+    let mut mutated = sequence.clone().into_bytes();
+    mutated[500] = if mutated[500] == b'A' { b'C' } else { b'A' };
+    let mutated = String::from_utf8(mutated).unwrap();
+    let mutated_seeds = strobemers(&mutated, k, w_min, w_max, 2);
+
+    // Seeds whose first strobe ends before the mismatch and whose second strobe starts after it.
+    let spanning: Vec<&(usize, u64)> = seeds.iter().filter(|&&(start, _)| start + k <= 500 && start + w_min > 500).collect();
+    assert_eq!(spanning.len(), 10);
+    assert!(spanning.iter().all(|seed| mutated_seeds.contains(seed)));
+    // Every contiguous k-mer as long as a strobemer's span over the mismatch is lost.
+    let span = w_max + k;
+    assert!((501 - span..=500).all(|start| !mutated.contains(&sequence[start..start + span])));
+}
+
+#[test]
+fn similar_proteins_share_more_reduced_kmers_than_raw_kmers() {
+    let a = "MKVLIAGSTEDKRFYWHPLVNQ";
+    // Conservative substitutions within Murphy's classes: I/V, T/S, D/E, R/K, Y/F, W/Y, Q/N.
+    let b = "MKILVAGTSDEKKYFFHPIVQN";
+    let shared =
+        |a: &HashMap<String, usize>, b: &HashMap<String, usize>| a.keys().filter(|kmer| b.contains_key(*kmer)).count();
+    let (raw_a, raw_b) = (count_kmers(generate_kmers(a, 4)), count_kmers(generate_kmers(b, 4)));
+    let alphabet = murphy8_alphabet();
+    let (reduced_a, reduced_b) = (count_reduced_kmers(a, &alphabet, 4), count_reduced_kmers(b, &alphabet, 4));
+    assert!(shared(&reduced_a, &reduced_b) > shared(&raw_a, &raw_b));
+    assert_eq!(shared(&reduced_a, &reduced_b), reduced_a.len());
+
+    // Windows over an unclassified residue are skipped rather than counted with an 'X'.
+    let counts = count_reduced_kmers("LLBLLL", &alphabet, 3);
+    assert_eq!(counts, HashMap::from([("LLL".to_string(), 1)]));
+}
+
+#[test]
+fn oversized_k_yields_no_kmers_for_a_short_sequence() {
+    let sequence = generate_random_dna_sequence_seeded(30, 15);
+    assert!(generate_kmers(&sequence, 50).is_empty());
+    assert!(generate_kmers_filtered(&sequence, 50).is_empty());
+    assert!(count_kmers_parallel(&sequence, 50).is_empty());
+    assert!(matches!(try_generate_kmers(&sequence, 50), Err(KmerError::InvalidK { k: 50, .. })));
+}
+
+#[test]
+fn palindromes_are_counted_once_per_occurrence_and_odd_kmers_always_merge() {
+    // GAATTC is an even-length palindrome, counted once for each of its two occurrences.
+    let counts = count_canonical_kmers(generate_kmers("GAATTCGAATTC", 6));
+    assert_eq!(counts["GAATTC"], 2);
+    assert_eq!(counts.values().sum::<usize>(), 7);
+
+    // Extending a palindrome by one base gives an odd-length k-mer, which can never be its own
+    // reverse complement (the middle base would have to pair with itself), so it always merges.
+    let counts = count_canonical_kmers(vec!["GAATTCG".to_string(), "CGAATTC".to_string(), "ATA".to_string()]);
+    assert_eq!(counts, HashMap::from([("CGAATTC".to_string(), 2), ("ATA".to_string(), 1)]));
+    assert!(generate_kmers(&generate_random_dna_sequence_seeded(200, 16), 5)
+        .iter()
+        .all(|kmer| reverse_complement(kmer) != *kmer));
+}
+
+#[test]
+fn sanitizing_acgtnnacgt_skips_or_drops_the_ambiguous_bases() {
+    let masked = sanitize_sequence("acgtNNacgt");
+    assert_eq!(masked, "ACGTNNACGT");
+    assert_eq!(generate_kmers_filtered(&masked, 3), vec!["ACG", "CGT", "ACG", "CGT"]);
+    assert!(generate_kmers_filtered(&masked, 5).is_empty());
+
+    let dropped = sanitize_sequence_with("acgtNNacgt", InvalidBasePolicy::Drop);
+    assert_eq!(dropped, "ACGTACGT");
+    assert_eq!(generate_kmers_filtered(&dropped, 5), vec!["ACGTA", "CGTAC", "GTACG", "TACGT"]);
+}
+
+#[test]
+fn parallel_counts_match_sequential_counts_on_a_random_sequence() {
+    let sequence = generate_random_dna_sequence_seeded(1000, 257);
+    for k in [1, 4, 11, 31] {
+        assert_eq!(count_kmers_parallel(&sequence, k), count_kmers(generate_kmers(&sequence, k)), "k = {}", k);
+    }
+}
+
+#[test]
+fn top_kmers_break_count_ties_lexicographically() {
+    let counts = HashMap::from([
+        ("TTT".to_string(), 5),
+        ("GGA".to_string(), 2),
+        ("CAT".to_string(), 2),
+        ("ACG".to_string(), 2),
+        ("AAA".to_string(), 1),
+    ]);
+    let ranked = |n| top_kmers(&counts, n).into_iter().map(|(kmer, _)| kmer).collect::<Vec<_>>();
+    assert_eq!(ranked(3), vec!["TTT", "ACG", "CAT"]);
+    assert_eq!(ranked(10), vec!["TTT", "ACG", "CAT", "GGA", "AAA"]);
+    assert!(top_kmers(&counts, 0).is_empty());
+}
+
+#[test]
+fn gc_content_and_base_composition_of_known_sequences() {
+    assert_eq!(gc_content("GCGC"), 1.0);
+    assert_eq!(gc_content("ATAT"), 0.0);
+    assert_eq!(gc_content(""), 0.0);
+    assert_eq!(gc_content("NNNN"), 0.0);
+    // Lowercase counts like uppercase and N is left out of the denominator
+    assert_eq!(gc_content("gcAT"), 0.5);
+    assert_eq!(gc_content("GCNNAT"), 0.5);
+
+    let composition = base_composition("acGT nN-X");
+    assert_eq!(composition, HashMap::from([('A', 1), ('C', 1), ('G', 1), ('T', 1), ('N', 2), ('?', 2)]));
+}
@@ -0,0 +1,99 @@
+use std::collections::HashSet;
+use kmer_analysis::kmer::{canonical_kmer, count_kmers, generate_kmers, reverse_complement};
+use kmer_analysis::mapping::{
+    anchor_chain, detect_chimeric_contigs, detect_strand, estimate_insert_sizes, shared_kmers_oriented, split_contigs,
+    strand_coverage_asymmetry, Strand,
+};
+use kmer_analysis::simulate::{generate_random_dna_sequence_seeded, simulate_paired_reads};
+
+#[test]
+fn anchor_chain_follows_the_collinear_diagonal() {
+    let k = 11;
+    let a = generate_random_dna_sequence_seeded(300, 1);
+    // b is an unrelated 40 bp prefix followed by a[100..250], so shared anchors sit on b = a - 60.
+    let b = generate_random_dna_sequence_seeded(40, 2) + &a[100..250];
+    let expected: Vec<(usize, usize)> = (100..=250 - k).map(|i| (i, i - 60)).collect();
+    assert_eq!(anchor_chain(&a, &b, k), expected);
+}
+
+#[test]
+fn insert_size_estimates_cluster_around_the_simulated_insert() {
+    let reference = generate_random_dna_sequence_seeded(20_000, 4);
+    let pairs = simulate_paired_reads(&reference, 100, 300, 15, 5.0, 8);
+    let sizes = estimate_insert_sizes(&pairs, &reference, 15);
+    assert_eq!(sizes.len(), pairs.len());
+    let mean = sizes.iter().sum::<usize>() as f64 / sizes.len() as f64;
+    assert!((mean - 300.0).abs() < 5.0, "mean insert {mean}");
+    assert!(sizes.iter().all(|&size| (200..=400).contains(&size)));
+}
+
+#[test]
+fn shared_kmers_are_forward_when_collinear_and_reverse_when_inverted() {
+    let k = 12;
+    let a = generate_random_dna_sequence_seeded(200, 11);
+    let forward = shared_kmers_oriented(&a, &a[50..150], k);
+    assert_eq!(forward.len(), 100 - k + 1);
+    assert!(forward.iter().all(|&(i, j, strand)| strand == Strand::Forward && i == j + 50));
+
+    let inverted = shared_kmers_oriented(&a, &reverse_complement(&a[50..150]), k);
+    assert_eq!(inverted.len(), 100 - k + 1);
+    assert!(inverted.iter().all(|&(i, j, strand)| strand == Strand::Reverse && i + j == 150 - k));
+}
+
+#[test]
+fn detects_the_strand_a_read_came_from() {
+    let k = 15;
+    let reference = generate_random_dna_sequence_seeded(2_000, 13);
+    let reference_kmers: HashSet<String> = generate_kmers(&reference, k).into_iter().collect();
+    let read = &reference[700..850];
+    assert_eq!(detect_strand(read, &reference_kmers, k), Strand::Forward);
+    assert_eq!(detect_strand(&reverse_complement(read), &reference_kmers, k), Strand::Reverse);
+    let unrelated = generate_random_dna_sequence_seeded(150, 14);
+    assert_eq!(detect_strand(&unrelated, &reference_kmers, k), Strand::Unknown);
+}
+
+#[test]
+fn finds_and_splits_the_junction_of_a_chimeric_contig() {
+    let (left, right) = (generate_random_dna_sequence_seeded(1_000, 190), generate_random_dna_sequence_seeded(1_000, 191));
+    let genuine = generate_random_dna_sequence_seeded(1_000, 192);
+    // Pairs come from each true molecule, so none spans the misjoin.
+    let pairs = [
+        simulate_paired_reads(&left, 100, 300, 20, 20.0, 193),
+        simulate_paired_reads(&right, 100, 300, 20, 20.0, 194),
+        simulate_paired_reads(&genuine, 100, 300, 20, 20.0, 195),
+    ]
+    .concat();
+    let contigs = vec![left + &right, genuine.clone()];
+    let splits = detect_chimeric_contigs(&contigs, &pairs, 15);
+    let [(0, position)] = splits[..] else { panic!("expected one split in the chimera, got {splits:?}") };
+    assert!(position.abs_diff(1_000) <= 50, "split at {position}");
+
+    let pieces = split_contigs(&contigs, &splits);
+    assert_eq!(pieces.len(), 3);
+    assert_eq!(pieces[0].len(), position);
+    assert_eq!(pieces[0].clone() + &pieces[1], contigs[0]);
+    assert_eq!(pieces[2], genuine);
+}
+
+#[test]
+fn strand_asymmetry_flags_kmers_seen_mostly_on_one_strand() {
+    let k = 12;
+    let genome = generate_random_dna_sequence_seeded(300, 220);
+    let biased = &genome[100..130];
+    let forward_reads = [vec![genome.as_str(); 10], vec![biased; 30]].concat();
+    let reverse_genome = reverse_complement(&genome);
+    let reverse_reads = vec![reverse_genome.as_str(); 10];
+    let count = |reads: &[&str]| count_kmers(reads.iter().flat_map(|read| generate_kmers(read, k)).collect());
+
+    let asymmetry = strand_coverage_asymmetry(&count(&forward_reads), &count(&reverse_reads));
+    let biased_kmers: HashSet<String> = generate_kmers(biased, k).iter().map(|kmer| canonical_kmer(kmer)).collect();
+    assert_eq!(asymmetry.len(), 300 - k + 1);
+    for (kmer, log_ratio) in &asymmetry {
+        if biased_kmers.contains(kmer) {
+            // 41 forward against 11 reverse, each with a pseudocount.
+            assert!((log_ratio - (41.0f64 / 11.0).log2()).abs() < 1e-9, "{kmer}");
+        } else {
+            assert_eq!(*log_ratio, 0.0, "{kmer}");
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use std::collections::HashSet;
+use kmer_analysis::kmer::reverse_complement;
+use kmer_analysis::minimizer::{canonical_minimizers, count_minimizers, minimizers, record_minimizers};
+use kmer_analysis::simulate::generate_random_dna_sequence_seeded;
+
+#[test]
+fn every_window_contributes_its_smallest_kmer() {
+    let sequence = generate_random_dna_sequence_seeded(500, 1);
+    let (k, w) = (7, 5);
+    let selected: HashSet<usize> = minimizers(&sequence, k, w).into_iter().map(|(position, _)| position).collect();
+    for start in 0..=sequence.len() - k - w + 1 {
+        let smallest = (start..start + w).min_by_key(|&i| &sequence[i..i + k]).unwrap();
+        assert!(selected.contains(&smallest));
+    }
+}
+
+#[test]
+fn canonical_minimizers_ignore_strand() {
+    let sequence = generate_random_dna_sequence_seeded(500, 2);
+    let forward: HashSet<String> = canonical_minimizers(&sequence, 9, 6).into_iter().map(|(_, kmer)| kmer).collect();
+    let reverse: HashSet<String> =
+        canonical_minimizers(&reverse_complement(&sequence), 9, 6).into_iter().map(|(_, kmer)| kmer).collect();
+    assert_eq!(forward, reverse);
+}
+
+#[test]
+fn record_minimizers_never_span_an_n() {
+    let records = vec!["ACGTACGTNNTTGCAAGG".to_string(), "GGATCC".to_string()];
+    let selected = record_minimizers(&records, 4, 3, false);
+    assert!(!selected.is_empty());
+    assert!(selected.iter().all(|kmer| kmer.len() == 4 && !kmer.contains('N')));
+}
+
+#[test]
+fn minimizer_counts_keep_a_fraction_of_the_kmers() {
+    let sequence = generate_random_dna_sequence_seeded(20_000, 3);
+    let counts = count_minimizers(&[sequence.clone(), sequence], 21, 10, true);
+    // Density is about 2 / (w + 1), and every selected k-mer is seen once per copy
+    let fraction = counts.len() as f64 / 20_000.0;
+    assert!((0.14..0.22).contains(&fraction), "{fraction}");
+    assert!(counts.values().all(|&count| count == 2));
+}
+
+#[test]
+fn minimizers_of_a_hand_computed_example() {
+    // K-mers of GTACGTTA: GTA TAC ACG CGT GTT TTA. ACG is the minimum of the first three windows
+    // and is emitted once; the last window, CGT GTT TTA, selects CGT.
+    let to_owned = |selected: &[(usize, &str)]| -> Vec<(usize, String)> {
+        selected.iter().map(|&(position, kmer)| (position, kmer.to_string())).collect()
+    };
+    assert_eq!(minimizers("GTACGTTA", 3, 3), to_owned(&[(2, "ACG"), (3, "CGT")]));
+    // Equal k-mers go to the leftmost in each window
+    assert_eq!(minimizers("AAAAA", 2, 2), to_owned(&[(0, "AA"), (1, "AA"), (2, "AA")]));
+    // Fewer than w k-mers form one window
+    assert_eq!(minimizers("TACGT", 3, 5), to_owned(&[(1, "ACG")]));
+    assert!(minimizers("GT", 3, 5).is_empty());
+}
@@ -0,0 +1,35 @@
+use kmer_analysis::kmer::{canonical_kmer, count_kmers, count_records_parallel, generate_kmers_filtered, reverse_complement};
+use kmer_analysis::nthash::{nthash, NtHash};
+use kmer_analysis::simulate::generate_random_dna_sequence_seeded;
+
+#[test]
+fn rolled_hashes_equal_hashing_each_kmer_afresh() {
+    let sequence = format!("{}NACGTN{}", generate_random_dna_sequence_seeded(300, 1), generate_random_dna_sequence_seeded(300, 2));
+    for canonical in [false, true] {
+        let rolled: Vec<(usize, u64)> = NtHash::new(&sequence, 21, canonical).collect();
+        let afresh: Vec<(usize, u64)> = (0..=sequence.len() - 21)
+            .filter_map(|start| nthash(&sequence[start..start + 21], canonical).map(|hash| (start, hash)))
+            .collect();
+        assert_eq!(rolled, afresh);
+        // The windows spanning either N, and the 4 bases between them, are skipped
+        assert_eq!(rolled.len(), 2 * (300 - 21 + 1));
+    }
+}
+
+#[test]
+fn canonical_hashes_ignore_strand() {
+    let kmer = "ACGGTCAGTTAGC";
+    assert_eq!(nthash(kmer, true), nthash(&reverse_complement(kmer), true));
+    assert_ne!(nthash(kmer, false), nthash(&reverse_complement(kmer), false));
+    assert_eq!(nthash("ACGNT", true), None);
+    assert_eq!(NtHash::new("ACGT", 0, true).next(), None);
+}
+
+#[test]
+fn hashed_counting_matches_counting_strings() {
+    let sequences = vec![generate_random_dna_sequence_seeded(5_000, 3), "ACGTNNACGTTTACGT".to_string()];
+    let kmers: Vec<String> = sequences.iter().flat_map(|sequence| generate_kmers_filtered(sequence, 9)).collect();
+    assert_eq!(count_records_parallel(&sequences, 9, false), count_kmers(kmers.clone()));
+    let canonical: Vec<String> = kmers.iter().map(|kmer| canonical_kmer(kmer)).collect();
+    assert_eq!(count_records_parallel(&sequences, 9, true), count_kmers(canonical));
+}
@@ -0,0 +1,37 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use kmer_analysis::plot::{kmer_histogram_svg, plot_count_scatter, plot_kmer_histogram, plot_spectrum, SpectrumAxes};
+use kmer_analysis::KmerError;
+
+fn temp_file(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("kmer-analysis-{}-{}", std::process::id(), name))
+}
+
+#[test]
+fn empty_counts_are_an_error_rather_than_a_panic() {
+    let path = temp_file("empty.png");
+    let result = plot_kmer_histogram(&HashMap::new(), path.to_str().unwrap(), 1280, 480);
+    assert!(matches!(result, Err(KmerError::NothingToPlot(_))));
+    assert!(!path.exists());
+    assert!(matches!(kmer_histogram_svg(&HashMap::new(), 640, 480), Err(KmerError::NothingToPlot(_))));
+}
+
+#[test]
+fn an_empty_spectrum_creates_no_image() {
+    for name in ["empty-spectrum.png", "empty-spectrum.svg"] {
+        let path = temp_file(name);
+        let result = plot_spectrum(&BTreeMap::new(), path.to_str().unwrap(), SpectrumAxes::default());
+        assert!(matches!(result, Err(KmerError::NothingToPlot(_))));
+        assert!(!path.exists(), "{}", name);
+    }
+}
+
+#[test]
+fn an_empty_count_scatter_creates_no_image() {
+    for name in ["empty-scatter.png", "empty-scatter.svg"] {
+        let path = temp_file(name);
+        let result = plot_count_scatter(&[], path.to_str().unwrap());
+        assert!(matches!(result, Err(KmerError::NothingToPlot(_))));
+        assert!(!path.exists(), "{}", name);
+    }
+}
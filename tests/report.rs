@@ -0,0 +1,21 @@
+use kmer_analysis::report::{Report, ReportSection, ReportTable};
+
+#[test]
+fn renders_sections_tables_and_inline_plots() {
+    let report = Report {
+        title: "K-mers of <sample>".to_string(),
+        sections: vec![ReportSection {
+            title: "k = 21".to_string(),
+            tables: vec![ReportTable::key_values("Counts", &[("Distinct", "42".to_string())])],
+            plots: vec!["<svg width=\"10\" height=\"10\"></svg>".to_string()],
+        }],
+    };
+    let html = report.to_html();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<title>K-mers of &lt;sample&gt;</title>"));
+    assert!(html.contains("<summary>k = 21</summary>"));
+    assert!(html.contains("<caption>Counts</caption>"));
+    assert!(html.contains("<tr><td>Distinct</td><td>42</td></tr>"));
+    assert!(html.contains("<svg width=\"10\" height=\"10\"></svg>"));
+    assert!(!html.contains("<script"));
+}
@@ -0,0 +1,90 @@
+use kmer_analysis::kmer::{count_kmers, gc_content, generate_kmers, reverse_complement};
+use kmer_analysis::simulate::{
+    generate_random_dna_sequence_seeded, generate_sequence, simulate_paired_reads, simulate_reads, MarkovModel,
+    SequenceModel,
+};
+
+#[test]
+fn seeded_generation_is_reproducible() {
+    assert_eq!(generate_random_dna_sequence_seeded(200, 7), generate_random_dna_sequence_seeded(200, 7));
+    assert_ne!(generate_random_dna_sequence_seeded(200, 7), generate_random_dna_sequence_seeded(200, 8));
+    let model = SequenceModel::uniform();
+    assert_eq!(generate_sequence(&model, 200, 3), generate_sequence(&model, 200, 3));
+}
+
+#[test]
+fn gc_model_hits_the_target() {
+    let sequence = generate_sequence(&SequenceModel::with_gc_content(0.7), 20_000, 1);
+    assert_eq!(sequence.len(), 20_000);
+    assert!((gc_content(&sequence) - 0.7).abs() < 0.02);
+}
+
+#[test]
+fn base_frequencies_can_exclude_bases() {
+    let sequence = generate_sequence(&SequenceModel::BaseFrequencies([1.0, 0.0, 0.0, 1.0]), 1_000, 2);
+    assert!(sequence.bytes().all(|base| base == b'A' || base == b'T'));
+}
+
+#[test]
+fn markov_chain_only_emits_trained_kmers() {
+    // Every 3-mer of a repeated motif is followed by one base, so an order-2 chain reproduces it.
+    let training = "ACGTTGCA".repeat(50);
+    let model = MarkovModel::train(&training, 2);
+    let sequence = generate_sequence(&SequenceModel::Markov(model), 500, 4);
+    assert_eq!(sequence.len(), 500);
+    let trained = count_kmers(generate_kmers(&training, 3));
+    assert!(generate_kmers(&sequence, 3).iter().all(|kmer| trained.contains_key(kmer)));
+}
+
+#[test]
+fn error_free_reads_come_from_either_strand() {
+    let reference = generate_random_dna_sequence_seeded(2_000, 11);
+    let reads = simulate_reads(&reference, 100, 5.0, 0.0, 0.0, 12);
+    assert_eq!(reads.len(), 100);
+    for read in &reads {
+        assert_eq!(read.sequence.len(), 100);
+        assert_eq!(read.quality, "I".repeat(100));
+        let start: usize = read.id.split('_').nth(1).unwrap().parse().unwrap();
+        let original = &reference[start..start + 100];
+        if read.id.ends_with('-') {
+            assert_eq!(read.sequence, reverse_complement(original));
+        } else {
+            assert_eq!(read.sequence, original);
+        }
+    }
+}
+
+#[test]
+fn errors_are_injected_at_about_the_given_rate() {
+    let reference = generate_random_dna_sequence_seeded(5_000, 21);
+    let reads = simulate_reads(&reference, 150, 20.0, 0.05, 0.0, 22);
+    let (mut mismatches, mut bases) = (0, 0);
+    for read in reads.iter().filter(|read| read.id.ends_with('+')) {
+        let start: usize = read.id.split('_').nth(1).unwrap().parse().unwrap();
+        let original = &reference.as_bytes()[start..start + 150];
+        mismatches += read.sequence.bytes().zip(original).filter(|(a, b)| a != *b).count();
+        bases += 150;
+    }
+    let rate = mismatches as f64 / bases as f64;
+    assert!((rate - 0.05).abs() < 0.01, "substitution rate {}", rate);
+    let with_indels = simulate_reads(&reference, 150, 2.0, 0.0, 0.02, 23);
+    assert!(with_indels.iter().all(|read| read.sequence.len() == 150));
+}
+
+#[test]
+fn paired_reads_match_the_requested_insert_with_mate_two_reversed() {
+    let reference = generate_random_dna_sequence_seeded(20_000, 80);
+    let pairs = simulate_paired_reads(&reference, 100, 400, 30, 10.0, 81);
+    assert_eq!(pairs.len(), 1_000);
+
+    let mut inserts = Vec::new();
+    for (mate1, mate2) in &pairs {
+        assert_eq!((mate1.len(), mate2.len()), (100, 100));
+        let start = reference.find(mate1.as_str()).expect("mate 1 is on the forward strand");
+        assert!(reference.find(mate2.as_str()).is_none());
+        let end = reference.find(&reverse_complement(mate2)).expect("mate 2 is on the reverse strand") + 100;
+        inserts.push(end - start);
+    }
+    let mean = inserts.iter().sum::<usize>() as f64 / inserts.len() as f64;
+    assert!((mean - 400.0).abs() < 5.0, "mean insert {mean}");
+}
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use kmer_analysis::kmer::{count_kmers, count_records_parallel, generate_kmers, reverse_complement};
+use kmer_analysis::simulate::generate_random_dna_sequence_seeded;
+use kmer_analysis::sketch::{
+    count_kmers_approximate, jaccard_similarity, kmer_collision_probability, kmer_correlation, overlap_vs_threshold,
+    pairwise_matrix, phylogenomic_distances, profile_distance, BloomFilter, CountMinSketch, MinHashSketch,
+    weighted_jaccard_similarity, WeightedMinHashSketch,
+};
+
+#[test]
+fn record_sketches_skip_ambiguous_kmers_and_ignore_strand() {
+    let genome = generate_random_dna_sequence_seeded(5_000, 1);
+    let (first, second) = genome.split_at(2_500);
+    // The same k-mers as the genome, apart from those spanning the split, read from both strands.
+    let records = vec![first.to_string(), format!("NNNN{}", reverse_complement(second))];
+    let whole = MinHashSketch::from_records(std::slice::from_ref(&genome), 21, 500);
+    let split = MinHashSketch::from_records(&records, 21, 500);
+    assert!(whole.jaccard(&split) > 0.95);
+    assert_eq!(split.hashes.len(), 500);
+}
+
+#[test]
+fn pairwise_matrix_is_symmetric_with_zero_self_distance() {
+    let sketches: Vec<MinHashSketch> = (1..=3)
+        .map(|seed| MinHashSketch::from_records(&[generate_random_dna_sequence_seeded(2_000, seed)], 15, 200))
+        .collect();
+    let matrix = pairwise_matrix(&sketches, MinHashSketch::mash_distance);
+    for (i, row) in matrix.iter().enumerate() {
+        assert_eq!(row[i], 0.0);
+        assert!(row[i].is_sign_positive());
+        for (j, &distance) in row.iter().enumerate() {
+            assert_eq!(distance, matrix[j][i]);
+        }
+    }
+    assert_eq!(matrix[0][1], 1.0);
+    assert_eq!(pairwise_matrix(&sketches, MinHashSketch::jaccard)[2][2], 1.0);
+}
+
+#[test]
+fn bloom_filter_has_no_false_negatives() {
+    let mut filter = BloomFilter::new(1 << 16, 4);
+    assert!(!filter.insert("ACGTA"));
+    assert!(filter.insert("ACGTA"));
+    assert!(filter.contains("ACGTA"));
+    assert!(!filter.contains("TTTTT"));
+}
+
+#[test]
+fn approximate_counts_keep_repeated_kmers_and_never_undercount() {
+    let genome = generate_random_dna_sequence_seeded(10_000, 4);
+    let sequences = vec![genome.clone(), genome[..5_000].to_string()];
+    let exact = count_records_parallel(&sequences, 15, true);
+    let approx = count_kmers_approximate(&sequences, 15, true, 1 << 20);
+    for (kmer, &count) in &exact {
+        match approx.get(kmer) {
+            Some(&estimate) => assert!(estimate >= count),
+            None => assert_eq!(count, 1, "{kmer} is missing"),
+        }
+    }
+    // Only the repeated first half is kept, apart from the odd Bloom filter false positive
+    let repeated = exact.values().filter(|&&count| count > 1).count();
+    assert!(approx.len() >= repeated && approx.len() < repeated + 50);
+}
+
+fn profile(counts: &[(&str, usize)]) -> HashMap<String, usize> {
+    counts.iter().map(|&(kmer, count)| (kmer.to_string(), count)).collect()
+}
+
+#[test]
+fn correlation_is_one_for_identical_profiles_and_negative_for_opposite_ones() {
+    let a = profile(&[("AAA", 10), ("CCC", 5), ("GGG", 1)]);
+    assert!((kmer_correlation(&a, &a) - 1.0).abs() < 1e-12);
+    let opposite = profile(&[("AAA", 1), ("CCC", 5), ("GGG", 10)]);
+    assert!(kmer_correlation(&a, &opposite) < 0.0);
+}
+
+#[test]
+fn count_min_never_underestimates_and_is_exact_when_well_sized() {
+    let kmers = generate_kmers(&generate_random_dna_sequence_seeded(20_000, 17), 8);
+    let truth = count_kmers(kmers.clone());
+    let mut small = CountMinSketch::new(256, 4);
+    let mut large = CountMinSketch::new(1 << 20, 4);
+    for kmer in &kmers {
+        small.add(kmer);
+        large.add(kmer);
+    }
+    assert!(truth.iter().all(|(kmer, &count)| small.estimate(kmer) >= count));
+    assert!(truth.iter().any(|(kmer, &count)| small.estimate(kmer) > count));
+    assert!(truth.iter().all(|(kmer, &count)| large.estimate(kmer) == count));
+}
+
+#[test]
+fn phylogenomic_distances_are_symmetric_and_rank_relatives_closer() {
+    let genome = generate_random_dna_sequence_seeded(10_000, 50);
+    // A close relative differing at every 100th base, and an unrelated genome.
+    let relative: String = genome
+        .chars()
+        .enumerate()
+        .map(|(i, base)| if i % 100 == 0 { if base == 'A' { 'C' } else { 'A' } } else { base })
+        .collect();
+    let genomes = vec![
+        ("genome".to_string(), genome),
+        ("relative".to_string(), relative),
+        ("unrelated".to_string(), generate_random_dna_sequence_seeded(10_000, 51)),
+    ];
+    let (names, distances) = phylogenomic_distances(&genomes, 16, 500);
+    assert_eq!(names, ["genome", "relative", "unrelated"]);
+    for (i, row) in distances.iter().enumerate() {
+        assert_eq!(row[i], 0.0);
+        assert!(row.iter().zip(&distances).all(|(&distance, other)| distance == other[i]));
+    }
+    assert!(distances[0][1] < distances[0][2]);
+    assert!(distances[0][1] > 0.0);
+}
+
+#[test]
+fn overlap_at_higher_thresholds_reflects_only_abundant_shared_kmers() {
+    let a = profile(&[("AAA", 10), ("CCC", 8), ("GGG", 1), ("TTT", 2)]);
+    let b = profile(&[("AAA", 12), ("CCC", 6), ("ACG", 1), ("TTT", 1)]);
+    assert_eq!(overlap_vs_threshold(&a, &b, &[1, 5, 20]), vec![(1, 0.6), (5, 1.0), (20, 0.0)]);
+}
+
+#[test]
+fn profile_distance_is_zero_for_identical_and_one_for_disjoint_composition() {
+    let a = profile(&[("AAA", 2), ("CCC", 2)]);
+    // Same frequencies at twice the depth.
+    assert_eq!(profile_distance(&a, &profile(&[("AAA", 4), ("CCC", 4)])), 0.0);
+    assert_eq!(profile_distance(&a, &profile(&[("GGG", 1)])), 1.0);
+    assert_eq!(profile_distance(&a, &profile(&[("AAA", 1), ("GGG", 1)])), 0.5);
+}
+
+#[test]
+fn weighted_minhash_is_below_jaccard_when_abundances_differ() {
+    let kmers = generate_kmers(&generate_random_dna_sequence_seeded(300, 180), 12);
+    // The same k-mers, evenly covered in one sample and heavily skewed in the other.
+    let even: HashMap<String, usize> = kmers.iter().map(|kmer| (kmer.clone(), 5)).collect();
+    let skewed: HashMap<String, usize> =
+        kmers.iter().enumerate().map(|(i, kmer)| (kmer.clone(), if i % 10 == 0 { 100 } else { 1 })).collect();
+    assert_eq!(jaccard_similarity(&even, &skewed), 1.0);
+
+    let (a, b) = (WeightedMinHashSketch::from_counts(&even, 64), WeightedMinHashSketch::from_counts(&skewed, 64));
+    assert_eq!(a.similarity(&WeightedMinHashSketch::from_counts(&even, 64)), 1.0);
+    assert!(a.similarity(&b) < 0.5, "similarity {}", a.similarity(&b));
+}
+
+#[test]
+fn collision_probability_nears_one_at_the_kmer_space_and_is_tiny_far_below() {
+    // 4^10 = 1,048,576 possible 10-mers.
+    assert!(kmer_collision_probability(1 << 20, 10) > 0.999_999);
+    assert!(kmer_collision_probability(100, 31) < 1e-12);
+    assert!(kmer_collision_probability(1_000, 10) < kmer_collision_probability(10_000, 10));
+    assert_eq!(kmer_collision_probability(1, 5), 0.0);
+}
+
+#[test]
+fn jaccard_is_one_for_identical_samples_and_zero_for_disjoint_ones() {
+    let a = profile(&[("ACG", 2), ("CGT", 1)]);
+    let disjoint = profile(&[("GGG", 2), ("TTT", 1)]);
+    assert_eq!(jaccard_similarity(&a, &a), 1.0);
+    assert_eq!(jaccard_similarity(&a, &disjoint), 0.0);
+    assert_eq!(weighted_jaccard_similarity(&a, &a), 1.0);
+    assert_eq!(weighted_jaccard_similarity(&a, &disjoint), 0.0);
+
+    // Same k-mers, different abundances: (1 + 1) / (2 + 3)
+    let skewed = profile(&[("ACG", 1), ("CGT", 3)]);
+    assert_eq!(jaccard_similarity(&a, &skewed), 1.0);
+    assert!((weighted_jaccard_similarity(&a, &skewed) - 0.4).abs() < 1e-12);
+    assert_eq!(jaccard_similarity(&HashMap::new(), &HashMap::new()), 0.0);
+}
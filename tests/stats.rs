@@ -0,0 +1,27 @@
+use kmer_analysis::simulate::generate_random_dna_sequence_seeded;
+use kmer_analysis::stats::{sliding_window_stats, SequenceStats};
+
+#[test]
+fn counts_bases_gc_and_entropy() {
+    let stats = SequenceStats::of("ACGTac\ngtNN");
+    assert_eq!(stats, SequenceStats { length: 10, base_counts: [2, 2, 2, 2], other: 2 });
+    assert_eq!(stats.gc_content(), 0.5);
+    assert_eq!(stats.entropy(), 2.0);
+    assert_eq!(SequenceStats::of("AAAA").entropy(), 0.0);
+    assert_eq!(SequenceStats::of("").gc_content(), 0.0);
+}
+
+#[test]
+fn sliding_windows_match_stats_of_each_window() {
+    let sequence = generate_random_dna_sequence_seeded(1000, 3);
+    for (window, step) in [(100, 1), (100, 30), (50, 80)] {
+        let windows = sliding_window_stats(&sequence, window, step);
+        assert_eq!(windows.len(), (sequence.len() - window) / step + 1);
+        for stats in windows {
+            assert_eq!(stats.stats, SequenceStats::of(&sequence[stats.start..stats.start + window]));
+        }
+    }
+    let short = sliding_window_stats("ACG", 10, 5);
+    assert_eq!(short.len(), 1);
+    assert_eq!(short[0].stats.length, 3);
+}